@@ -1,90 +1,846 @@
+#[cfg(feature = "bindgen")]
 use bindgen::{self, callbacks, Bindings, CargoCallbacks};
-use once_cell::sync::Lazy;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
 
-use std::{collections::HashSet, env, fs, path::PathBuf};
+#[cfg(feature = "bindgen")]
+#[path = "build/binding_output.rs"]
+mod binding_output;
+#[cfg(feature = "bindgen")]
+#[path = "build/clang_target.rs"]
+mod clang_target;
+#[path = "build/conan.rs"]
+mod conan;
+#[path = "build/configure_args.rs"]
+mod configure_args;
+#[path = "build/dylib_name.rs"]
+mod dylib_name;
+// Only `MIN_RELEASE` is needed without binding generation.
+#[cfg_attr(not(feature = "bindgen"), allow(dead_code))]
+#[path = "build/ffmpeg_version.rs"]
+mod ffmpeg_version;
+#[path = "build/homebrew.rs"]
+mod homebrew;
+#[path = "build/libs_override.rs"]
+mod libs_override;
+#[path = "build/newtype_enums.rs"]
+mod newtype_enums;
+#[path = "build/normalize_path.rs"]
+mod normalize_path;
+#[path = "build/sha256.rs"]
+mod sha256;
+#[path = "build/system_include.rs"]
+mod system_include;
+#[path = "build/target_env.rs"]
+mod target_env;
+#[cfg(target_os = "windows")]
+#[path = "build/vcpkg_status.rs"]
+mod vcpkg_status;
+// Only vcpkg reports the FFmpeg release rather than the library version.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[path = "build/version_match.rs"]
+mod version_match;
 
 /// All the libs that FFmpeg has
-static LIBS: Lazy<[&str; 7]> = Lazy::new(|| {
-    [
-        "avcodec",
+static LIBS: [&str; 8] = [
+    "avcodec",
+    "avdevice",
+    "avfilter",
+    "avformat",
+    "avutil",
+    "postproc",
+    "swresample",
+    "swscale",
+];
+
+/// The libs FFmpeg can be configured without (`--disable-avdevice`...). When
+/// the FFmpeg linked lacks one of them, it's left out instead of failing.
+static SKIPPABLE_LIBS: [&str; 4] = ["avdevice", "avfilter", "swresample", "swscale"];
+
+/// The libs of `SKIPPABLE_LIBS` left out by `skip_missing_libs()`, and
+/// libpostproc on docs.rs.
+static MISSING_LIBS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The defines (`NAME` or `NAME=VALUE`) of the Cflags pkg-config reports for
+/// the libs linked, which the headers are parsed with like a C program would.
+static PKG_CONFIG_DEFINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The majors of `version_lib()` found for the libraries linked, and which
+/// libraries they are, recorded by the linking for `check_libs_version()`.
+static LIBS_VERSION: Mutex<Option<(Vec<u32>, String)>> = Mutex::new(None);
+
+/// The libs of `FFMPEG_LIBS`, which replace the ones of the cargo features,
+/// set by `EnvVars::init()`.
+static LIBS_OVERRIDE: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+/// The libs of `LIBS` enabled by their cargo features (avutil always is), or
+/// the ones of `FFMPEG_LIBS`, minus the ones the FFmpeg linked lacks.
+fn enabled_libs() -> Vec<&'static str> {
+    let missing_libs = MISSING_LIBS.lock().unwrap();
+    let libs = match LIBS_OVERRIDE.get() {
+        Some(libs) => libs.clone(),
+        None => LIBS
+            .iter()
+            .copied()
+            .filter(|libname| {
+                *libname == "avutil"
+                    || env::var(format!("CARGO_FEATURE_{}", libname.to_uppercase())).is_ok()
+            })
+            .collect(),
+    };
+    libs.into_iter()
+        .filter(|libname| !missing_libs.iter().any(|missing| missing == libname))
+        .collect()
+}
+
+/// Replace the libs of the cargo features with the ones of `FFMPEG_LIBS`.
+/// Libs this crate doesn't know (e.g. of an FFmpeg fork) are linked, but
+/// none of their headers are bound.
+fn override_libs(ffmpeg_libs: &str) {
+    let libs = libs_override::parse_libs(ffmpeg_libs, &LIBS)
+        .unwrap_or_else(|e| panic!("FFMPEG_LIBS: {}", e));
+    // The crate uses these whenever their features are enabled.
+    for libname in ["avcodec", "avformat"] {
+        if env::var(format!("CARGO_FEATURE_{}", libname.to_uppercase())).is_ok()
+            && !libs.iter().any(|lib| lib == libname)
+        {
+            panic!(
+                "FFMPEG_LIBS doesn't list {}, but its `{}` feature is enabled. List it, or disable the feature.",
+                libname, libname
+            );
+        }
+    }
+    for libname in libs
+        .iter()
+        .filter(|libname| !LIBS.contains(&libname.as_str()))
+    {
+        println!(
+            "cargo:warning=FFMPEG_LIBS: lib{} isn't an FFmpeg library this crate knows, it's linked but none of its headers are bound.",
+            libname
+        );
+    }
+    // The build script runs once, so the names are leaked to be as `'static`
+    // as the ones of `LIBS`.
+    let libs = libs
+        .into_iter()
+        .map(|libname| &*Box::leak(libname.into_boxed_str()))
+        .collect();
+    LIBS_OVERRIDE.set(libs).unwrap();
+}
+
+/// Leave out the libs of `SKIPPABLE_LIBS` that aren't `found` (`where` they're
+/// looked for), with a warning, so that neither they nor their headers are
+/// used from then on. Returns the other libs.
+fn skip_missing_libs<'a>(
+    library_names: &[&'a str],
+    found: impl Fn(&str) -> bool,
+    place: &str,
+) -> Vec<&'a str> {
+    let mut libs = Vec::new();
+    for libname in library_names {
+        if *libname == "postproc" && !found(libname) {
+            panic!(
+                "The `postproc` feature is enabled, but libpostproc isn't found {}. FFmpeg only has it when configured with --enable-gpl (and not since 8.0), disable the feature otherwise.",
+                place
+            );
+        }
+        if SKIPPABLE_LIBS.contains(libname) && !found(libname) {
+            println!(
+                "cargo:warning=lib{} isn't found {}, it's left out as FFmpeg seems to be configured with --disable-{}.",
+                libname, place, libname
+            );
+            MISSING_LIBS.lock().unwrap().push(libname.to_string());
+        } else {
+            libs.push(*libname);
+        }
+    }
+    libs
+}
+
+/// Emit the `ff_has_<lib>` cfg of every lib that is linked, however it's
+/// found, including the ones of `FFMPEG_LIBS` this crate doesn't know whose
+/// name can be a cfg.
+fn emit_lib_cfgs() {
+    let enabled_libs = enabled_libs();
+    let extra_libs = enabled_libs.iter().filter(|libname| {
+        !LIBS.contains(libname)
+            && libname
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+    for libname in LIBS.iter().chain(extra_libs) {
+        println!("cargo:rustc-check-cfg=cfg(ff_has_{})", libname);
+        if enabled_libs.contains(libname) {
+            println!("cargo:rustc-cfg=ff_has_{}", libname);
+        }
+    }
+}
+
+/// Whitelist of the headers we want to generate bindings
+static HEADERS: [&str; 67] = [
+    "libavcodec/avcodec.h",
+    "libavcodec/avfft.h",
+    "libavcodec/dv_profile.h",
+    "libavcodec/vorbis_parser.h",
+    "libavdevice/avdevice.h",
+    "libavfilter/avfilter.h",
+    "libavfilter/buffersink.h",
+    "libavfilter/buffersrc.h",
+    "libavformat/avformat.h",
+    "libavformat/avio.h",
+    "libavutil/adler32.h",
+    "libavutil/aes.h",
+    "libavutil/audio_fifo.h",
+    "libavutil/avstring.h",
+    "libavutil/avutil.h",
+    "libavutil/base64.h",
+    "libavutil/blowfish.h",
+    "libavutil/bprint.h",
+    "libavutil/buffer.h",
+    "libavutil/camellia.h",
+    "libavutil/cast5.h",
+    "libavutil/channel_layout.h",
+    "libavutil/cpu.h",
+    "libavutil/crc.h",
+    "libavutil/dict.h",
+    "libavutil/display.h",
+    "libavutil/downmix_info.h",
+    "libavutil/error.h",
+    "libavutil/eval.h",
+    "libavutil/fifo.h",
+    "libavutil/file.h",
+    "libavutil/frame.h",
+    "libavutil/hash.h",
+    "libavutil/hmac.h",
+    "libavutil/hwcontext.h",
+    "libavutil/hwcontext_drm.h",
+    "libavutil/imgutils.h",
+    "libavutil/lfg.h",
+    "libavutil/log.h",
+    "libavutil/macros.h",
+    "libavutil/mastering_display_metadata.h",
+    "libavutil/mathematics.h",
+    "libavutil/md5.h",
+    "libavutil/mem.h",
+    "libavutil/motion_vector.h",
+    "libavutil/murmur3.h",
+    "libavutil/opt.h",
+    "libavutil/parseutils.h",
+    "libavutil/pixdesc.h",
+    "libavutil/pixfmt.h",
+    "libavutil/random_seed.h",
+    "libavutil/rational.h",
+    "libavutil/replaygain.h",
+    "libavutil/ripemd.h",
+    "libavutil/samplefmt.h",
+    "libavutil/sha.h",
+    "libavutil/sha512.h",
+    "libavutil/spherical.h",
+    "libavutil/stereo3d.h",
+    "libavutil/threadmessage.h",
+    "libavutil/time.h",
+    "libavutil/timecode.h",
+    "libavutil/twofish.h",
+    "libavutil/xtea.h",
+    "libpostproc/postprocess.h",
+    "libswresample/swresample.h",
+    "libswscale/swscale.h",
+];
+
+#[cfg(feature = "bindgen")]
+/// The headers of `HEADERS` the binding can't do without, the others are
+/// skipped if the FFmpeg in the include dir lacks them.
+static CORE_HEADERS: [&str; 3] = [
+    "libavcodec/avcodec.h",
+    "libavformat/avformat.h",
+    "libavutil/avutil.h",
+];
+
+/// Name prefixes used to sort items of the flat binding into per-library
+/// namespaces. Groups are checked in order and the first matching prefix wins,
+/// so the exceptions that belong to `avutil` come first.
+static NAMESPACE_PREFIXES: [(&str, &[&str]); 8] = [
+    (
+        "avutil",
+        &[
+            "AVPictureType",
+            "FF_API_",
+            "FF_LAMBDA_",
+            "FF_QP2LAMBDA",
+            "FF_QUALITY_SCALE",
+            "av_read_image_line",
+            "av_write_image_line",
+        ],
+    ),
+    (
         "avdevice",
+        &[
+            "AVAppToDevMessageType",
+            "AVDevToAppMessageType",
+            "AVDevice",
+            "LIBAVDEVICE_",
+            "av_device_",
+            "av_input_audio_device_next",
+            "av_input_video_device_next",
+            "av_output_audio_device_next",
+            "av_output_video_device_next",
+            "avdevice_",
+        ],
+    ),
+    (
         "avfilter",
-        "avformat",
-        "avutil",
-        "swresample",
+        &[
+            "AVABufferSink",
+            "AVBufferSink",
+            "AVBufferSrc",
+            "AVFILTER_",
+            "AVFilter",
+            "AV_BUFFERSINK_",
+            "AV_BUFFERSRC_",
+            "LIBAVFILTER_",
+            "av_abuffersink_",
+            "av_buffersink_",
+            "av_buffersrc_",
+            "av_filter_",
+            "avfilter_",
+        ],
+    ),
+    (
         "swscale",
-    ]
-});
+        &["LIBSWSCALE_", "SWS_", "Sws", "sws_", "swscale_"],
+    ),
+    (
+        "swresample",
+        &["LIBSWRESAMPLE_", "SWR_", "Swr", "swr_", "swresample_"],
+    ),
+    ("postproc", &["LIBPOSTPROC_", "PP_", "pp_", "postproc_"]),
+    (
+        "avformat",
+        &[
+            "AVChapter",
+            "AVCodecTag",
+            "AVDurationEstimationMethod",
+            "AVFMT",
+            "AVFormat",
+            "AVINDEX_",
+            "AVIO",
+            "AVIndexEntry",
+            "AVInputFormat",
+            "AVOpenCallback",
+            "AVOutputFormat",
+            "AVPROBE_",
+            "AVPacketList",
+            "AVProbeData",
+            "AVProgram",
+            "AVSEEK_",
+            "AVSTREAM_",
+            "AVStream",
+            "AVTimebaseSource",
+            "AV_DISPOSITION_",
+            "AV_FRAME_FILENAME_",
+            "AV_PROGRAM_",
+            "AV_PTS_WRAP_",
+            "FF_FDEBUG_",
+            "LIBAVFORMAT_",
+            "URLContext",
+            "av_add_index_entry",
+            "av_append_packet",
+            "av_apply_bitstream_filters",
+            "av_codec_get_id",
+            "av_codec_get_tag",
+            "av_demuxer_",
+            "av_dump_format",
+            "av_filename_number",
+            "av_find_best_stream",
+            "av_find_default_stream_index",
+            "av_find_input_format",
+            "av_find_program_from_stream",
+            "av_format_",
+            "av_get_frame_filename",
+            "av_get_output_timestamp",
+            "av_get_packet",
+            "av_guess_",
+            "av_hex_dump",
+            "av_iformat_",
+            "av_index_search_timestamp",
+            "av_interleaved_write_",
+            "av_match_ext",
+            "av_muxer_",
+            "av_new_program",
+            "av_oformat_",
+            "av_pkt_dump",
+            "av_probe_input_",
+            "av_program_",
+            "av_read_",
+            "av_register_all",
+            "av_register_input_format",
+            "av_register_output_format",
+            "av_sdp_create",
+            "av_seek_frame",
+            "av_stream_",
+            "av_url_split",
+            "av_write_",
+            "avformat_",
+            "avio_",
+            "avpriv_io_",
+        ],
+    ),
+    (
+        "avcodec",
+        &[
+            "AVAudioServiceType",
+            "AVBSF",
+            "AVBitStreamFilter",
+            "AVCPBProperties",
+            "AVCodec",
+            "AVDVProfile",
+            "AVDiscard",
+            "AVFieldOrder",
+            "AVHWAccel",
+            "AVLockOp",
+            "AVPacket",
+            "AVPanScan",
+            "AVPicture",
+            "AVProducerReferenceTime",
+            "AVProfile",
+            "AVSideDataParamChangeFlags",
+            "AVSubtitle",
+            "AVVorbisParseContext",
+            "AV_CODEC_",
+            "AV_EF_",
+            "AV_GET_BUFFER_",
+            "AV_GET_ENCODE_BUFFER_",
+            "AV_HWACCEL_",
+            "AV_INPUT_BUFFER_",
+            "AV_PARSER_",
+            "AV_PKT_",
+            "AV_SUBTITLE_",
+            "DCT",
+            "DV_",
+            "FFT",
+            "FF_",
+            "LIBAVCODEC_",
+            "MpegEncContext",
+            "PARSER_",
+            "RDFT",
+            "RcOverride",
+            "VORBIS_",
+            "av_bitstream_filter_",
+            "av_bsf_",
+            "av_codec_",
+            "av_copy_packet",
+            "av_cpb_properties_alloc",
+            "av_dct_",
+            "av_dup_packet",
+            "av_dv_",
+            "av_fast_padded_malloc",
+            "av_fast_padded_mallocz",
+            "av_fft_",
+            "av_free_packet",
+            "av_get_audio_frame_duration",
+            "av_get_bits_per_sample",
+            "av_get_codec_tag_string",
+            "av_get_exact_bits_per_sample",
+            "av_get_pcm_codec",
+            "av_get_profile_name",
+            "av_grow_packet",
+            "av_hwaccel_",
+            "av_imdct_",
+            "av_init_packet",
+            "av_lockmgr_register",
+            "av_mdct_",
+            "av_new_packet",
+            "av_packet_",
+            "av_parser_",
+            "av_picture_",
+            "av_rdft_",
+            "av_register_bitstream_filter",
+            "av_register_codec_parser",
+            "av_register_hwaccel",
+            "av_shrink_packet",
+            "av_vorbis_",
+            "av_xiphlacing",
+            "avcodec_",
+            "avpicture_",
+            "avsubtitle_",
+        ],
+    ),
+];
 
-/// Whitelist of the headers we want to generate bindings
-static HEADERS: Lazy<[&str; 63]> = Lazy::new(|| {
-    [
-        "libavcodec/avcodec.h",
-        "libavcodec/avfft.h",
-        "libavcodec/dv_profile.h",
-        "libavcodec/vorbis_parser.h",
-        "libavdevice/avdevice.h",
-        "libavfilter/avfilter.h",
-        "libavfilter/buffersink.h",
-        "libavfilter/buffersrc.h",
-        "libavformat/avformat.h",
-        "libavformat/avio.h",
-        "libavutil/adler32.h",
-        "libavutil/aes.h",
-        "libavutil/audio_fifo.h",
-        "libavutil/avstring.h",
-        "libavutil/avutil.h",
-        "libavutil/base64.h",
-        "libavutil/blowfish.h",
-        "libavutil/bprint.h",
-        "libavutil/buffer.h",
-        "libavutil/camellia.h",
-        "libavutil/cast5.h",
-        "libavutil/channel_layout.h",
-        "libavutil/cpu.h",
-        "libavutil/crc.h",
-        "libavutil/dict.h",
-        "libavutil/display.h",
-        "libavutil/downmix_info.h",
-        "libavutil/error.h",
-        "libavutil/eval.h",
-        "libavutil/fifo.h",
-        "libavutil/file.h",
-        "libavutil/frame.h",
-        "libavutil/hash.h",
-        "libavutil/hmac.h",
-        "libavutil/hwcontext_drm.h",
-        "libavutil/imgutils.h",
-        "libavutil/lfg.h",
-        "libavutil/log.h",
-        "libavutil/macros.h",
-        "libavutil/mathematics.h",
-        "libavutil/md5.h",
-        "libavutil/mem.h",
-        "libavutil/motion_vector.h",
-        "libavutil/murmur3.h",
-        "libavutil/opt.h",
-        "libavutil/parseutils.h",
-        "libavutil/pixdesc.h",
-        "libavutil/pixfmt.h",
-        "libavutil/random_seed.h",
-        "libavutil/rational.h",
-        "libavutil/replaygain.h",
-        "libavutil/ripemd.h",
-        "libavutil/samplefmt.h",
-        "libavutil/sha.h",
-        "libavutil/sha512.h",
-        "libavutil/stereo3d.h",
-        "libavutil/threadmessage.h",
-        "libavutil/time.h",
-        "libavutil/timecode.h",
-        "libavutil/twofish.h",
-        "libavutil/xtea.h",
-        "libswresample/swresample.h",
-        "libswscale/swscale.h",
-    ]
-});
+/// Plain data structs (no pointers or unions) that get serde derives when the
+/// `serde` feature is enabled. `AVDetectionBBox` isn't here because serde
+/// can't derive for its 64-byte label arrays.
+static SERDE_TYPES: [&str; 5] = [
+    "AVContentLightMetadata",
+    "AVMasteringDisplayMetadata",
+    "AVMotionVector",
+    "AVRational",
+    "AVSphericalMapping",
+];
+
+/// Items every usable binding has, one or more per library: (kind, name) as
+/// returned by `binding_items()`.
+static SENTINEL_ITEMS: [(&str, &str); 12] = [
+    ("constant", "LIBAVUTIL_VERSION_MAJOR"),
+    ("constant", "LIBAVCODEC_VERSION_MAJOR"),
+    ("constant", "LIBAVFORMAT_VERSION_MAJOR"),
+    ("type", "AVCodecContext"),
+    ("type", "AVFrame"),
+    ("type", "AVPixelFormat"),
+    ("function", "avcodec_send_packet"),
+    ("function", "avformat_open_input"),
+    ("function", "avdevice_register_all"),
+    ("function", "avfilter_get_by_name"),
+    ("function", "sws_getContext"),
+    ("function", "swr_alloc"),
+];
+
+/// System libraries linked by the `link-<name>` features: (name, libs). The
+/// libs of one dependency are in link order.
+static SYSTEM_DEPENDENCIES: [(&str, &[&str]); 5] = [
+    ("zlib", &["z"]),
+    ("bz2", &["bz2"]),
+    ("lzma", &["lzma"]),
+    ("openssl", &["ssl", "crypto"]),
+    ("mbedtls", &["mbedtls", "mbedx509", "mbedcrypto"]),
+];
+
+/// The frameworks a static FFmpeg built with the default configure options
+/// needs on macOS (and iOS and tvOS, which have them too), for VideoToolbox,
+/// AudioToolbox and SecureTransport.
+static MACOS_FRAMEWORKS: [&str; 6] = [
+    "AudioToolbox",
+    "CoreFoundation",
+    "CoreMedia",
+    "CoreVideo",
+    "Security",
+    "VideoToolbox",
+];
+
+/// The system libs it needs on macOS.
+static MACOS_LIBS: [&str; 3] = ["iconv", "bz2", "z"];
+
+/// The libs a static FFmpeg needs last on Linux with glibc, which
+/// `FFMPEG_LIBS_DIR` can't discover.
+static LINUX_GNU_LIBS: [&str; 3] = ["m", "pthread", "dl"];
+
+/// The libs a static FFmpeg needs last on the BSDs, which `FFMPEG_LIBS_DIR`
+/// can't discover.
+static BSD_LIBS: [&str; 3] = ["execinfo", "m", "pthread"];
+
+/// Where the packages (FreeBSD's pkg, OpenBSD's and NetBSD's ports) of the BSDs
+/// put their `.pc` files.
+static BSD_PKG_CONFIG_DIRS: [&str; 2] =
+    ["/usr/local/libdata/pkgconfig", "/usr/local/lib/pkgconfig"];
+
+/// The libs a static FFmpeg needs last on musl, where pkg-config drops the
+/// `-pthread` of `Libs.private`.
+static MUSL_LIBS: [&str; 2] = ["pthread", "m"];
+
+/// The libs a static FFmpeg needs last on Android.
+static ANDROID_LIBS: [&str; 2] = ["z", "m"];
+
+/// The NDK libs of the MediaCodec support of libavcodec.
+static ANDROID_MEDIACODEC_LIBS: [&str; 2] = ["mediandk", "android"];
+
+/// The FFmpeg libs in the order static linking needs them, each one before
+/// the libs it uses.
+static STATIC_LINK_ORDER: [&str; 8] = [
+    "avdevice",
+    "avfilter",
+    "avformat",
+    "avcodec",
+    "postproc",
+    "swresample",
+    "swscale",
+    "avutil",
+];
+
+/// `library_names` in the order of `STATIC_LINK_ORDER`. Libs it doesn't have
+/// (of `FFMPEG_LIBS`) come right before avutil, which they likely depend on.
+fn static_link_order<'a>(library_names: &[&'a str]) -> Vec<&'a str> {
+    let mut libs = library_names.to_vec();
+    libs.sort_by_key(
+        |libname| match STATIC_LINK_ORDER.iter().position(|x| x == libname) {
+            Some(position) => (position, 1),
+            None => (STATIC_LINK_ORDER.len() - 1, 0),
+        },
+    );
+    libs
+}
+
+/// Fail on settings that only make sense for desktop targets when building
+/// for iOS or tvOS, where FFmpeg has to be linked statically into the app.
+fn check_mobile_apple_settings(env_vars: &EnvVars) {
+    if !matches!(
+        env::var("CARGO_CFG_TARGET_OS").as_deref(),
+        Ok("ios") | Ok("tvos")
+    ) {
+        return;
+    }
+    let desktop_only = [
+        ("FFMPEG_DLL_PATH", env_vars.ffmpeg_dll_path.is_some()),
+        (
+            "FFMPEG_VCPKG_TRIPLET",
+            env_vars.ffmpeg_vcpkg_triplet.is_some(),
+        ),
+    ];
+    for (name, set) in desktop_only {
+        if set {
+            panic!(
+                "{} isn't supported for {}, link a static FFmpeg with FFMPEG_LIBS_DIR or FFMPEG_PKG_CONFIG_PATH instead.",
+                name,
+                env::var("TARGET").unwrap()
+            );
+        }
+    }
+}
+
+/// Fail on settings that can't work for Emscripten, which only links static
+/// libraries, and require a static FFmpeg built with `emconfigure` since the
+/// one of the system can't be used.
+fn check_emscripten_settings(env_vars: &EnvVars) {
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("emscripten")
+        || env_vars.docs_rs.is_some()
+        || env_vars.ffmpeg_no_link.is_some()
+    {
+        return;
+    }
+    let dynamic_only = [
+        ("FFMPEG_DLL_PATH", env_vars.ffmpeg_dll_path.is_some()),
+        (
+            "FFMPEG_DYNAMIC_LINKING",
+            env_vars.ffmpeg_dynamic_linking.is_some(),
+        ),
+        (
+            "FFMPEG_LINK_MODE=dynamic",
+            env_vars.ffmpeg_link_mode.as_deref() == Some("dynamic"),
+        ),
+    ];
+    for (name, set) in dynamic_only {
+        if set {
+            panic!(
+                "{} isn't supported for {}, link a static FFmpeg built with emconfigure with FFMPEG_LIBS_DIR or FFMPEG_PKG_CONFIG_PATH instead.",
+                name,
+                env::var("TARGET").unwrap()
+            );
+        }
+    }
+    if env_vars.ffmpeg_libs_dir.is_none()
+        && env_vars.ffmpeg_pkg_config_path.is_none()
+        && env_vars.ffmpeg_conan_build_info.is_none()
+        && env_vars.cargo_feature_build.is_none()
+        && env_vars.cargo_feature_prebuilt.is_none()
+    {
+        panic!(
+            "Set FFMPEG_LIBS_DIR (or FFMPEG_PKG_CONFIG_PATH) to the static FFmpeg built with emconfigure for {}.",
+            env::var("TARGET").unwrap()
+        );
+    }
+}
+
+/// Link system libs after everything else, as link args too with whole
+/// archives.
+fn emit_trailing_libs(libs: &[&str], whole_archive: bool) {
+    for lib in libs {
+        if whole_archive {
+            println!("cargo:rustc-link-arg=-l{}", lib);
+        } else {
+            println!("cargo:rustc-link-lib={}", lib);
+        }
+    }
+}
+
+/// Link `LINUX_GNU_LIBS` after everything else when linking FFmpeg
+/// statically from `FFMPEG_LIBS_DIR` for Linux with glibc.
+fn emit_linux_gnu_dependencies(whole_archive: bool) {
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux")
+        && env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("gnu")
+    {
+        emit_trailing_libs(&LINUX_GNU_LIBS, whole_archive);
+    }
+}
+
+/// Whether the target is one of the BSDs, whose packages install FFmpeg and
+/// its dependencies under `/usr/local`.
+fn is_bsd_target() -> bool {
+    matches!(
+        env::var("CARGO_CFG_TARGET_OS").as_deref(),
+        Ok("freebsd" | "openbsd" | "netbsd" | "dragonfly")
+    )
+}
+
+/// Whether the build is for the host, so its `/usr/local` is the target's.
+fn is_native_build() -> bool {
+    env::var("TARGET").unwrap() == env::var("HOST").unwrap()
+}
+
+/// Link `BSD_LIBS` after everything else when linking FFmpeg statically from
+/// `FFMPEG_LIBS_DIR` for a BSD, and search `/usr/local/lib` for the packaged
+/// dependencies (x264, lame...) when building natively.
+fn emit_bsd_dependencies(whole_archive: bool) {
+    if !is_bsd_target() {
+        return;
+    }
+    if is_native_build() {
+        println!("cargo:rustc-link-search=native=/usr/local/lib");
+    }
+    emit_trailing_libs(&BSD_LIBS, whole_archive);
+}
+
+/// Let the pkg-config probes find the FFmpeg packaged for a BSD when building
+/// natively, by searching `BSD_PKG_CONFIG_DIRS` after the `PKG_CONFIG_PATH`
+/// already set.
+fn add_bsd_pkg_config_dirs() {
+    if !is_bsd_target() || !is_native_build() {
+        return;
+    }
+    let dirs: Vec<&str> = BSD_PKG_CONFIG_DIRS
+        .iter()
+        .copied()
+        .filter(|dir| Path::new(dir).is_dir())
+        .collect();
+    if dirs.is_empty() {
+        return;
+    }
+    let (name, existing) = match targeted_env_var("PKG_CONFIG_PATH") {
+        Some((name, existing)) => (name, existing),
+        None => ("PKG_CONFIG_PATH".to_string(), String::new()),
+    };
+    env::set_var(
+        name,
+        normalize_path::prepend_path_list(&existing, Some(&dirs.join(":")), false),
+    );
+}
+
+/// Link what a static FFmpeg needs for Emscripten, whose libc has everything
+/// else: the threads, only if the crate is built with them (`-sUSE_PTHREADS`
+/// or the `atomics` target feature), as emcc links every object with them then.
+fn emit_emscripten_dependencies() {
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("emscripten") {
+        return;
+    }
+    let pthreads = env::var("CARGO_ENCODED_RUSTFLAGS")
+        .is_ok_and(|flags| flags.contains("USE_PTHREADS"))
+        || env::var("CARGO_CFG_TARGET_FEATURE")
+            .is_ok_and(|features| features.split(',').any(|feature| feature == "atomics"));
+    if pthreads {
+        println!("cargo:rustc-link-arg=-pthread");
+    }
+}
+
+/// Link `MUSL_LIBS` after everything else when linking FFmpeg statically for
+/// a musl target.
+fn emit_musl_dependencies(whole_archive: bool) {
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("musl") {
+        emit_trailing_libs(&MUSL_LIBS, whole_archive);
+    }
+}
+
+/// Link what a static FFmpeg needs on Android after everything else:
+/// `ANDROID_LIBS`, and `ANDROID_MEDIACODEC_LIBS` if the libavcodec in
+/// `libdir` is built with MediaCodec.
+fn emit_android_dependencies(libdir: Option<&Path>, whole_archive: bool) {
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("android") {
+        return;
+    }
+    let needle = b"AMediaCodec_";
+    let mediacodec = libdir.is_some_and(|libdir| {
+        fs::read(libdir.join("libavcodec.a"))
+            .is_ok_and(|archive| archive.windows(needle.len()).any(|x| x == needle))
+    });
+    if mediacodec {
+        emit_trailing_libs(&ANDROID_MEDIACODEC_LIBS, whole_archive);
+    }
+    emit_trailing_libs(&ANDROID_LIBS, whole_archive);
+}
+
+/// The system libs a static FFmpeg of MSYS2 needs on windows-gnu.
+static WINDOWS_GNU_LIBS: [&str; 5] = ["bcrypt", "ws2_32", "secur32", "mfuuid", "strmiids"];
+
+/// Keys of the `FFMPEG_BUILD_CONFIG` file and the env vars they mirror.
+static BUILD_CONFIG_KEYS: [(&str, &str); 28] = [
+    ("allow_older", "FFMPEG_ALLOW_OLDER"),
+    ("android_api", "FFMPEG_ANDROID_API"),
+    ("binding_cache_dir", "FFMPEG_BINDING_CACHE_DIR"),
+    ("binding_output_path", "FFMPEG_BINDING_OUTPUT_PATH"),
+    ("binding_path", "FFMPEG_BINDING_PATH"),
+    ("conan_build_info", "FFMPEG_CONAN_BUILD_INFO"),
+    ("defines", "FFMPEG_BINDGEN_DEFINES"),
+    ("dir", "FFMPEG_DIR"),
+    ("dll_path", "FFMPEG_DLL_PATH"),
+    ("dynamic_linking", "FFMPEG_DYNAMIC_LINKING"),
+    ("extra_headers", "FFMPEG_EXTRA_HEADERS"),
+    ("extra_link_libs", "FFMPEG_EXTRA_LINK_LIBS"),
+    ("implib_dir", "FFMPEG_IMPLIB_DIR"),
+    ("include_dir", "FFMPEG_INCLUDE_DIR"),
+    ("libs", "FFMPEG_LIBS"),
+    ("libs_dir", "FFMPEG_LIBS_DIR"),
+    ("link_by_path", "FFMPEG_LINK_BY_PATH"),
+    ("link_mode", "FFMPEG_LINK_MODE"),
+    ("macos_frameworks", "FFMPEG_MACOS_FRAMEWORKS"),
+    ("no_binding_cache", "FFMPEG_NO_BINDING_CACHE"),
+    ("no_brew", "FFMPEG_NO_BREW"),
+    ("no_link", "FFMPEG_NO_LINK"),
+    ("pkg_config_path", "FFMPEG_PKG_CONFIG_PATH"),
+    ("skip_version_check", "FFMPEG_SKIP_VERSION_CHECK"),
+    ("sysroot", "FFMPEG_SYSROOT"),
+    ("vcpkg_features", "FFMPEG_VCPKG_FEATURES"),
+    ("vcpkg_triplet", "FFMPEG_VCPKG_TRIPLET"),
+    ("whole_archive", "FFMPEG_WHOLE_ARCHIVE"),
+];
+
+/// The bindings vendored in `src`: the feature selecting each, its file and the
+/// libavutil major version of the FFmpeg release it's generated from. The
+/// first one is used when no feature is selected.
+static VENDORED_BINDINGS: [(&str, &str, u32); 1] = [("ffmpeg4", "src/binding_4_4.rs", 56)];
+
+/// The vendored binding whose `ffmpegN` feature is enabled, `None` if none is.
+fn selected_vendored_binding() -> Option<(&'static str, &'static str, u32)> {
+    VENDORED_BINDINGS
+        .iter()
+        .find(|(feature, _, _)| {
+            env::var_os(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_some()
+        })
+        .copied()
+}
+
+/// The selected vendored binding, the 4.4 one by default.
+fn vendored_binding() -> (&'static str, &'static str, u32) {
+    selected_vendored_binding().unwrap_or(VENDORED_BINDINGS[0])
+}
+
+/// Warn if the headers the build uses (the ones the binding is generated from,
+/// or `FFMPEG_INCLUDE_DIR`) aren't of the FFmpeg version the selected `ffmpegN`
+/// feature is for.
+fn check_vendored_binding_version(env_vars: &EnvVars, include_dir: Option<&str>) {
+    let (feature, _, major) = match selected_vendored_binding() {
+        Some(vendored) => vendored,
+        None => return,
+    };
+    let include_dir = match include_dir.or(env_vars.ffmpeg_include_dir.as_deref()) {
+        Some(include_dir) => include_dir,
+        None => return,
+    };
+    if let Some(version) = parse_header_version(include_dir, "avutil") {
+        if version >> 16 != major {
+            println!(
+                "cargo:warning=The `{}` feature is for libavutil {}, but the headers in {} are of libavutil {}.{}. Enable the feature of the FFmpeg version you link.",
+                feature,
+                major,
+                include_dir,
+                version >> 16,
+                version >> 8 & 0xff
+            );
+        }
+    }
+}
 
+#[cfg(feature = "bindgen")]
 /// Filter out all symbols in the HashSet, and for others things it will act
 /// exactly the same as `CargoCallback`.
 #[derive(Debug)]
@@ -93,6 +849,7 @@ struct FilterCargoCallbacks {
     emitted_macro: HashSet<String>,
 }
 
+#[cfg(feature = "bindgen")]
 impl FilterCargoCallbacks {
     fn new(set: HashSet<String>) -> Self {
         Self {
@@ -102,6 +859,7 @@ impl FilterCargoCallbacks {
     }
 }
 
+#[cfg(feature = "bindgen")]
 impl callbacks::ParseCallbacks for FilterCargoCallbacks {
     fn will_parse_macro(&self, name: &str) -> callbacks::MacroParsingBehavior {
         if self.emitted_macro.contains(name) {
@@ -110,15 +868,47 @@ impl callbacks::ParseCallbacks for FilterCargoCallbacks {
             callbacks::MacroParsingBehavior::Default
         }
     }
+
+    fn include_file(&self, filename: &str) {
+        self.inner.include_file(filename)
+    }
 }
 
 fn use_prebuilt_binding(from: &str, to: &str) {
+    println!("cargo:rerun-if-changed={}", from);
     fs::copy(from, to).expect("Prebuilt binding file failed to be copied.");
 }
 
+#[cfg(feature = "bindgen")]
+/// The rustfmt config the binding is formatted with, instead of the one rustfmt
+/// would find from the build dir or the user's config dir.
+const BINDING_RUSTFMT_CONFIG: &str = "edition = \"2018\"\nnewline_style = \"Unix\"\n";
+
+#[cfg(feature = "bindgen")]
+/// Write `content` to `path` unless it's there already. Rewriting an unchanged
+/// file the build script reruns on would make cargo rerun it on every build,
+/// as it's newer than the last run.
+fn write_if_changed(path: &Path, content: &str) {
+    if fs::read_to_string(path).ok().as_deref() != Some(content) {
+        fs::write(path, content)
+            .unwrap_or_else(|e| panic!("Cannot write {}: {}", path.display(), e));
+    }
+}
+
+#[cfg(feature = "bindgen")]
+/// Generate the binding of `headers` through a single wrapper header written
+/// to `wrapper_path`, which includes them in order, so bindgen parses their
+/// shared includes once. Every include root is passed as a `-I` dir, in order,
+/// so each header is found in the first root having it, and the includes of
+/// the ones in a root are found in the others (e.g. the libdrm headers of
+/// `hwcontext_drm.h` in their own prefix). It's formatted with
+/// `BINDING_RUSTFMT_CONFIG`, written next to the wrapper.
 fn generate_bindings<T: Into<String>>(
-    ffmpeg_include_dir: Option<&str>,
+    include_roots: &[String],
     headers: impl Iterator<Item = T>,
+    clang_args: &[String],
+    layout_tests: bool,
+    wrapper_path: &Path,
 ) -> Result<Bindings, ()> {
     // Because of the strange `FP_*` in `math.h` https://github.com/rust-lang/rust-bindgen/issues/687
     let filter_callback = FilterCargoCallbacks::new(
@@ -133,222 +923,3449 @@ fn generate_bindings<T: Into<String>>(
         .collect(),
     );
 
+    let wrapper: String = headers
+        .map(|header| format!("#include <{}>\n", header.into()))
+        .collect();
+    write_if_changed(wrapper_path, &wrapper);
+    println!("cargo:rerun-if-changed={}", wrapper_path.display());
+    let rustfmt_config_path = wrapper_path.with_file_name("rustfmt.toml");
+    write_if_changed(&rustfmt_config_path, BINDING_RUSTFMT_CONFIG);
+
     // Bindgen the headers
-    headers
-        // map header short path to full path
-        .map(|header| {
-            if let Some(ffmpeg_include_dir) = ffmpeg_include_dir {
-                format!("{}/{}", ffmpeg_include_dir, header.into())
-            } else {
-                header.into()
-            }
-        })
-        .fold(
-            if let Some(ffmpeg_include_dir) = ffmpeg_include_dir {
-                bindgen::builder()
-                    .parse_callbacks(Box::new(filter_callback))
-                    // Add clang path, for `#include` header finding in bindgen process.
-                    .clang_arg(format!("-I{}/", ffmpeg_include_dir))
-            } else {
-                bindgen::builder().parse_callbacks(Box::new(filter_callback))
-            },
-            |builder, header| builder.header(header),
-        )
+    bindgen::builder()
+        .parse_callbacks(Box::new(filter_callback))
+        // Add clang paths, for `#include` header finding in bindgen process.
+        .clang_args(include_roots.iter().map(|root| format!("-I{}", root)))
+        .header(wrapper_path.to_str().unwrap())
+        .clang_args(clang_args)
+        .layout_tests(layout_tests)
+        .rustfmt_configuration_file(Some(rustfmt_config_path))
         .generate()
 }
 
-#[allow(dead_code)]
-pub struct EnvVars {
-    docs_rs: Option<String>,
-    out_dir: Option<String>,
-    ffmpeg_include_dir: Option<String>,
-    ffmpeg_dll_path: Option<String>,
-    ffmpeg_pkg_config_path: Option<String>,
-    ffmpeg_libs_dir: Option<String>,
-    ffmpeg_binding_path: Option<String>,
+#[cfg(feature = "bindgen")]
+/// The binding of `headers` in its canonical form (see
+/// `binding_output::canonicalize()`), with the include roots and `OUT_DIR` in
+/// its comments replaced by placeholders.
+fn canonical_binding(
+    env_vars: &EnvVars,
+    include_dir: &str,
+    headers: &[String],
+    clang_args: &[String],
+) -> String {
+    let out_dir = env_vars.out_dir.as_ref().unwrap();
+    let roots = include_roots(include_dir);
+    let binding = generate_bindings(
+        &roots,
+        headers.iter(),
+        clang_args,
+        env_vars.ffmpeg_no_layout_tests.is_none(),
+        &Path::new(out_dir).join("wrapper.h"),
+    )
+    .expect("Binding generation failed.")
+    .to_string();
+    let mut paths: Vec<(&str, &str)> = roots
+        .iter()
+        .map(|root| (root.trim_end_matches(['/', '\\']), "<include dir>"))
+        .collect();
+    paths.push((out_dir.as_str(), "<out dir>"));
+    binding_output::canonicalize(&binding, &paths)
 }
 
-impl EnvVars {
-    fn init() -> Self {
-        // TODO use a macro here to init with printing env_changed.
-        /* Workaround of cargo rerun-if-env-changed bug
-        println!("cargo:rerun-if-env-changed=DOCS_RS");
-        println!("cargo:rerun-if-env-changed=VCPKG_ROOT");
-        println!("cargo:rerun-if-env-changed=FFMPEG_PKG_CONFIG_PATH");
-        println!("cargo:rerun-if-env-changed=FFMPEG_DYNAMIC_LINKING");
-        */
-        Self {
-            docs_rs: env::var("DOCS_RS").ok(),
-            out_dir: env::var("OUT_DIR").ok(),
-            ffmpeg_include_dir: env::var("FFMPEG_INCLUDE_DIR").ok(),
-            ffmpeg_dll_path: env::var("FFMPEG_DLL_PATH").ok(),
-            ffmpeg_pkg_config_path: env::var("FFMPEG_PKG_CONFIG_PATH").ok(),
-            ffmpeg_libs_dir: env::var("FFMPEG_LIBS_DIR").ok(),
-            ffmpeg_binding_path: env::var("FFMPEG_BINDING_PATH").ok(),
+#[cfg(feature = "bindgen")]
+/// Names of the `FF_API_*` deprecation guards in the version headers of the
+/// FFmpeg in the include dir.
+fn deprecation_guards(include_dir: &str) -> BTreeSet<String> {
+    let mut guards = BTreeSet::new();
+    for libname in enabled_libs() {
+        for header in &["version.h", "version_major.h"] {
+            let content = match find_include(include_dir, &format!("lib{}/{}", libname, header))
+                .and_then(|path| fs::read_to_string(path).ok())
+            {
+                Some(content) => content,
+                None => continue,
+            };
+            guards.extend(content.lines().filter_map(|line| {
+                let name = line
+                    .trim_start()
+                    .strip_prefix("#define ")?
+                    .split_whitespace()
+                    .next()?;
+                if name.starts_with("FF_API_") {
+                    Some(name.to_string())
+                } else {
+                    None
+                }
+            }));
         }
     }
+    guards
 }
 
-#[cfg(not(target_os = "windows"))]
-mod non_windows {
-    use super::*;
-
-    /// Try probing ffmpeg installed in system with no side effect. Return unfound Err(library name) when failed.
-    // TODO: this is useful in static lib searching
-    #[allow(dead_code)]
-    fn try_probe_system_ffmpeg(library_names: &[&str]) -> Result<(), String> {
-        match library_names.iter().find(|libname| {
-            pkg_config::Config::new()
-                // Remove side effect by disable metadata emitting
-                .cargo_metadata(false)
-                .probe(&libname)
-                .is_err()
-        }) {
-            Some(&libname) => Err(libname.to_string()),
-            None => Ok(()),
-        }
+#[cfg(feature = "bindgen")]
+/// Clang `-D` args from `FFMPEG_BINDGEN_DEFINES` ("A=0;B=1;C"), the Cflags of
+/// pkg-config (`PKG_CONFIG_DEFINES`) and, with the `no-deprecated` feature,
+/// ones setting every deprecation guard to 0 so the binding only contains the
+/// non-deprecated API. User defines come last so they win.
+fn bindgen_defines(env_vars: &EnvVars, include_dir: &str) -> Vec<String> {
+    let mut defines = Vec::new();
+    if env_vars.cargo_feature_no_deprecated.is_some() {
+        defines.extend(
+            deprecation_guards(include_dir)
+                .into_iter()
+                .map(|guard| format!("-D{}=0", guard)),
+        );
+    }
+    defines.extend(
+        PKG_CONFIG_DEFINES
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|define| format!("-D{}", define)),
+    );
+    if let Some(bindgen_defines) = env_vars.ffmpeg_bindgen_defines.as_ref() {
+        defines.extend(
+            bindgen_defines
+                .split(';')
+                .map(str::trim)
+                .filter(|define| !define.is_empty())
+                .map(|define| format!("-D{}", define)),
+        );
     }
+    defines
+}
 
-    pub fn static_linking_with_pkg_config(
-        library_names: &[&str],
-        ffmpeg_pkg_config_path: &str,
-    ) -> Vec<String> {
-        env::set_var("PKG_CONFIG_PATH", ffmpeg_pkg_config_path);
-        // TODO: if specific library is not enabled, we should not probe it. If we
-        // want to implement this, we Should modify try_probe_system_ffmpeg() too.
-        let mut paths = HashSet::new();
-        for libname in library_names {
-            let new_paths = pkg_config::Config::new()
-                // currently only support building with static libraries.
-                .statik(true)
-                .cargo_metadata(true)
-                .probe(&format!("lib{}", libname))
-                .unwrap_or_else(|_| panic!("{} not found!", libname))
-                .include_paths;
-            for new_path in new_paths {
-                let new_path = new_path.to_str().unwrap().to_string();
-                paths.insert(new_path);
+#[cfg(feature = "bindgen")]
+/// The sysroot and the API level to use for an Android target: the ones of
+/// the NDK clang in `CC_<target>` (as the `cc` crate takes it), otherwise the
+/// sysroot of the NDK in `ANDROID_NDK_HOME`. `FFMPEG_ANDROID_API` overrides
+/// the API level.
+fn android_ndk(env_vars: &EnvVars) -> (Option<String>, Option<String>) {
+    let target = env::var("TARGET").unwrap();
+    let cc = tracked_env_var(&format!("CC_{}", target))
+        .or_else(|| tracked_env_var(&format!("CC_{}", target.replace('-', "_"))))
+        .or_else(|| tracked_env_var("TARGET_CC"));
+    let (cc_sysroot, cc_api) = match cc.as_deref().and_then(clang_target::ndk_clang_info) {
+        Some((sysroot, api)) => (sysroot, Some(api)),
+        None => (None, None),
+    };
+    let sysroot = cc_sysroot.or_else(|| {
+        tracked_env_var("ANDROID_NDK_HOME")
+            .map(|ndk_home| clang_target::ndk_sysroot(&ndk_home, &env::var("HOST").unwrap()))
+    });
+    (sysroot, env_vars.ffmpeg_android_api.clone().or(cc_api))
+}
+
+#[cfg(feature = "bindgen")]
+/// The sysroot of the Emscripten SDK, from `EM_CACHE`, `EM_CONFIG` or `EMSDK`
+/// (as `emsdk_env` sets them).
+fn emscripten_sysroot() -> Option<String> {
+    let em_config_path = tracked_env_var("EM_CONFIG");
+    let em_config = em_config_path
+        .as_ref()
+        .map(|path| (path, fs::read_to_string(path).unwrap_or_default()));
+    clang_target::emscripten_sysroot(
+        tracked_env_var("EM_CACHE").as_deref(),
+        em_config
+            .as_ref()
+            .map(|(path, content)| (path.as_str(), content.as_str())),
+        tracked_env_var("EMSDK").as_deref(),
+    )
+}
+
+#[cfg(feature = "bindgen")]
+/// The path of an Xcode SDK, from `xcrun --sdk <sdk> --show-sdk-path`.
+fn xcrun_sdk_path(sdk: &str) -> String {
+    let output = std::process::Command::new("xcrun")
+        .args(["--sdk", sdk, "--show-sdk-path"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .unwrap_or_else(|| {
+            panic!(
+                "Cannot find the {} SDK with xcrun, install Xcode or set FFMPEG_SYSROOT.",
+                sdk
+            )
+        });
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[cfg(feature = "bindgen")]
+/// Generate the binding from the headers in the include dir and write it to
+/// `OUT_DIR`. When cross compiling, clang is told the target, and the sysroot
+/// from `FFMPEG_SYSROOT` (or `PKG_CONFIG_SYSROOT_DIR`, or the NDK for Android,
+/// or the Xcode SDK for iOS and tvOS) if there is one. For Android,
+/// `__ANDROID_API__` is defined too, and for iOS and tvOS the minimum OS
+/// version is passed.
+fn write_generated_binding(env_vars: &EnvVars, include_dir: &str, output_binding_path: &str) {
+    let target = env::var("TARGET").unwrap();
+    let (ndk_sysroot, android_api) = if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("android")
+    {
+        android_ndk(env_vars)
+    } else {
+        (None, None)
+    };
+    let apple_sdk = clang_target::apple_sdk(&target);
+    let emscripten = env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("emscripten");
+    let sysroot = env_vars
+        .ffmpeg_sysroot
+        .clone()
+        .or_else(|| tracked_env_var("PKG_CONFIG_SYSROOT_DIR"))
+        .or(ndk_sysroot)
+        .or_else(|| apple_sdk.as_ref().map(|sdk| xcrun_sdk_path(sdk.name)))
+        .or_else(|| {
+            if emscripten {
+                emscripten_sysroot()
+            } else {
+                None
             }
-        }
-        paths.into_iter().collect()
+        });
+    let mut clang_args =
+        clang_target::cross_clang_args(&target, &env::var("HOST").unwrap(), sysroot.as_deref());
+    if let Some(android_api) = android_api {
+        clang_args.push(format!("-D__ANDROID_API__={}", android_api));
     }
-
-    pub fn static_linking_with_libs_dir(library_names: &[&str], ffmpeg_libs_dir: &str) {
-        println!("cargo:rustc-link-search=native={}", ffmpeg_libs_dir);
-        for library_name in library_names {
-            println!("cargo:rustc-link-lib=static={}", library_name);
+    if let Some(sdk) = apple_sdk {
+        let deployment_target = tracked_env_var(sdk.deployment_target_var);
+        clang_args.push(sdk.version_min_arg(deployment_target.as_deref()));
+    }
+    if emscripten {
+        // Functions are hidden by default on wasm, and bindgen skips hidden
+        // ones.
+        clang_args.push("-fvisibility=default".to_string());
+    }
+    if is_bsd_target() && is_native_build() {
+        // The clang of the BSDs doesn't search the dir of the packaged
+        // dependencies the FFmpeg headers include.
+        clang_args.push("-idirafter/usr/local/include".to_string());
+    }
+    clang_args.extend(bindgen_defines(env_vars, include_dir));
+    // Last, so they can override everything else but the `-I` dirs.
+    if let Some(extra_clang_args) = env_vars.ffmpeg_bindgen_clang_args.as_ref() {
+        clang_args.extend(shlex::split(extra_clang_args).unwrap_or_else(|| {
+            panic!(
+                "FFMPEG_BINDGEN_CLANG_ARGS has unbalanced quotes: {}",
+                extra_clang_args
+            )
+        }));
+        // Shown by `cargo build -vv`.
+        println!(
+            "FFMPEG_BINDGEN_CLANG_ARGS is set, the clang args are: {:?}",
+            include_roots(include_dir)
+                .iter()
+                .map(|root| format!("-I{}", root))
+                .chain(clang_args.iter().cloned())
+                .collect::<Vec<_>>()
+        );
+    }
+    check_ffmpeg_version(env_vars, include_dir);
+    check_libs_version(env_vars, include_dir);
+    let roots = include_roots(include_dir).join(", ");
+    let headers = env_vars.headers().into_iter().filter(|header| {
+        if find_include(include_dir, header).is_some() {
+            return true;
+        }
+        // Trimmed down or older header sets lack some of `HEADERS`, which are
+        // left out of the binding. The core ones and the ones asked for in
+        // `FFMPEG_EXTRA_HEADERS` are needed.
+        if CORE_HEADERS.contains(&header.as_str()) || !HEADERS.contains(&header.as_str()) {
+            panic!(
+                "{} isn't in any of the include dirs {}, which come from {}.",
+                header,
+                roots,
+                env_vars.include_dir_origin(include_dir)
+            );
+        }
+        println!(
+            "cargo:warning={} isn't in any of the include dirs {}, it's left out of the binding.",
+            header, roots
+        );
+        false
+    });
+    let headers: Vec<String> = headers.collect();
+    let cache_path = binding_cache_path(env_vars, include_dir, &headers, &clang_args);
+    let check_reproducible = env_vars.ffmpeg_check_reproducible.is_some();
+    match cache_path.as_ref() {
+        // The check is of the generation, a cached binding would skip it.
+        Some(cache_path) if cache_path.is_file() && !check_reproducible => {
+            fs::copy(cache_path, output_binding_path)
+                .expect("Cached binding file failed to be copied.");
+            // Shown by `cargo build -vv`.
+            println!("Binding taken from the cache {}.", cache_path.display());
+        }
+        _ => {
+            let binding = canonical_binding(env_vars, include_dir, &headers, &clang_args);
+            if check_reproducible {
+                let again = canonical_binding(env_vars, include_dir, &headers, &clang_args);
+                if let Some((line, first, second)) =
+                    binding_output::first_difference(&binding, &again)
+                {
+                    panic!(
+                        "FFMPEG_CHECK_REPRODUCIBLE: generating the binding twice gave different output, from line {}:\n  first:  {:?}\n  second: {:?}",
+                        line, first, second
+                    );
+                }
+                // Shown by `cargo build -vv`.
+                println!("The binding is generated the same twice.");
+            }
+            fs::write(output_binding_path, binding).expect("Cannot write binding to file.");
+            if let Some(cache_path) = cache_path.as_ref() {
+                store_cached_binding(output_binding_path, cache_path);
+            }
         }
     }
+    if let Some(ffmpeg_binding_output_path) = env_vars.ffmpeg_binding_output_path.as_ref() {
+        export_binding(output_binding_path, ffmpeg_binding_output_path);
+    }
 }
 
-#[cfg(target_os = "windows")]
-mod windows {
-    use super::*;
-    pub fn static_linking_inner(_env_vars: &EnvVars, _library_names: &[&str]) -> Vec<String> {
-        vcpkg::Config::new()
-            .find_package("ffmpeg")
-            .unwrap()
-            .include_paths
-            .into_iter()
-            .map(|x| x.to_str().unwrap().to_string())
-            .collect()
+#[cfg(feature = "bindgen")]
+/// Where the binding of these headers and clang args is cached: a file named
+/// by the hash of the FFmpeg headers in the include dir, the headers bound, the
+/// clang args, `FFMPEG_NO_LAYOUT_TESTS`, the target and the crate version, in
+/// `FFMPEG_BINDING_CACHE_DIR`
+/// or `ffmpeg-binding-cache` in the target dir. `None` with
+/// `FFMPEG_NO_BINDING_CACHE`. Since the headers aren't passed to bindgen on a
+/// cache hit, the build script is rerun when one of the hashed ones changes.
+fn binding_cache_path(
+    env_vars: &EnvVars,
+    include_dir: &str,
+    headers: &[String],
+    clang_args: &[String],
+) -> Option<PathBuf> {
+    if env_vars.ffmpeg_no_binding_cache.is_some() {
+        return None;
+    }
+    let cache_dir = match env_vars.ffmpeg_binding_cache_dir.as_ref() {
+        Some(cache_dir) => PathBuf::from(cache_dir),
+        // `<target dir>[/<triple>]/<profile>/build/rusty_ffmpeg-<hash>/out`
+        None => Path::new(env_vars.out_dir.as_ref().unwrap())
+            .ancestors()
+            .nth(4)?
+            .join("ffmpeg-binding-cache"),
+    };
+    let mut header_dirs: BTreeSet<PathBuf> = BTreeSet::new();
+    for root in include_roots(include_dir) {
+        header_dirs.extend(
+            LIBS.iter()
+                .map(|libname| Path::new(&root).join(format!("lib{}", libname))),
+        );
+        header_dirs.extend(headers.iter().filter_map(|header| {
+            Path::new(&root)
+                .join(header)
+                .parent()
+                .map(Path::to_path_buf)
+        }));
     }
+    let mut header_files: Vec<PathBuf> = header_dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "h"))
+        .collect();
+    header_files.sort();
+    let mut key = format!(
+        "{}\n{}\n{}\n{}\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        env::var("TARGET").unwrap(),
+        headers.join(";"),
+        clang_args.join(";"),
+        env_vars.ffmpeg_no_layout_tests.is_some(),
+    )
+    .into_bytes();
+    for header_file in header_files {
+        println!("cargo:rerun-if-changed={}", header_file.display());
+        key.extend_from_slice(header_file.to_string_lossy().as_bytes());
+        key.extend(fs::read(&header_file).ok()?);
+    }
+    Some(cache_dir.join(format!("{}.rs", sha256::sha256_hex(&key))))
 }
 
-fn dynamic_linking(env_vars: &EnvVars) {
-    let ffmpeg_dll_path = env_vars.ffmpeg_dll_path.as_ref().unwrap();
+#[cfg(feature = "bindgen")]
+/// Put a generated binding in the cache, through a temporary file renamed in
+/// place so that concurrent builds never read a partial one. Failing to cache
+/// only warns.
+fn store_cached_binding(binding_path: &str, cache_path: &Path) {
+    let temp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+    let stored = fs::create_dir_all(cache_path.parent().unwrap())
+        .and_then(|_| fs::copy(binding_path, &temp_path))
+        .and_then(|_| fs::rename(&temp_path, cache_path));
+    if let Err(e) = stored {
+        let _ = fs::remove_file(&temp_path);
+        println!(
+            "cargo:warning=The binding isn't cached in {}: {}",
+            cache_path.display(),
+            e
+        );
+    }
+}
 
-    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+#[cfg(feature = "bindgen")]
+/// Copy the generated binding to `FFMPEG_BINDING_OUTPUT_PATH`, as it is before
+/// the post-processing `FFMPEG_BINDING_PATH` bindings get too, so it can be
+/// used as one later.
+fn export_binding(from: &str, to: &str) {
+    if let Some(parent) = Path::new(to)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).unwrap_or_else(|e| {
+            panic!(
+                "Cannot create {} for FFMPEG_BINDING_OUTPUT_PATH: {}",
+                parent.display(),
+                e
+            )
+        });
+    }
+    fs::copy(from, to).unwrap_or_else(|e| {
+        panic!(
+            "Cannot write the binding to FFMPEG_BINDING_OUTPUT_PATH {}: {}",
+            to, e
+        )
+    });
+    // Shown by `cargo build -vv`.
+    println!("Binding exported to {}.", to);
+}
 
-    // Extract dll name and the dir the dll is in.
-    let (ffmpeg_dll_name, ffmpeg_dll_dir) = {
-        let mut ffmpeg_dll_path = PathBuf::from(ffmpeg_dll_path);
-        // Without extension.
-        let ffmpeg_dll_filename = ffmpeg_dll_path.file_stem().unwrap().to_str().unwrap();
-        let ffmpeg_dll_name = if cfg!(target_os = "windows") {
-            ffmpeg_dll_filename
-        } else {
-            ffmpeg_dll_filename.trim_start_matches("lib")
+#[cfg(feature = "bindgen")]
+/// Fail early if the headers in the include dir are older than
+/// `ffmpeg_version::MIN_RELEASE`, naming the settings they come from, rather
+/// than with clang errors about missing headers or an incomplete binding. Only
+/// warn with `FFMPEG_ALLOW_OLDER`.
+fn check_ffmpeg_version(env_vars: &EnvVars, include_dir: &str) {
+    let problem = match parse_header_version(include_dir, "avutil") {
+        Some(version) => {
+            let version = (version >> 16, version >> 8 & 0xff);
+            if ffmpeg_version::is_supported(version) {
+                return;
+            }
+            format!(
+                "{} detected at {}",
+                ffmpeg_version::describe(version),
+                include_dir
+            )
         }
-        .to_string();
-        // Remove file name.
-        ffmpeg_dll_path.pop();
-        let ffmpeg_dll_path = ffmpeg_dll_path.to_str().unwrap().to_string();
-        (ffmpeg_dll_name, ffmpeg_dll_path)
+        None => format!("No libavutil/version.h found at {}", include_dir),
     };
+    let message = format!(
+        "{}, but rusty_ffmpeg requires FFmpeg >= {}. The include dir comes from {}.",
+        problem,
+        ffmpeg_version::MIN_RELEASE,
+        env_vars.include_dir_origin(include_dir)
+    );
+    if env_vars.ffmpeg_allow_older.is_some() {
+        println!(
+            "cargo:warning={} Going on since FFMPEG_ALLOW_OLDER is set.",
+            message
+        );
+    } else {
+        panic!(
+            "{} Point it at newer FFmpeg headers, or set FFMPEG_ALLOW_OLDER=1 to use these anyway.",
+            message
+        );
+    }
+}
 
-    println!("cargo:rustc-link-lib=dylib={}", ffmpeg_dll_name);
-    println!("cargo:rustc-link-search=native={}", ffmpeg_dll_dir);
+#[cfg(not(feature = "bindgen"))]
+fn write_generated_binding(_env_vars: &EnvVars, include_dir: &str, _output_binding_path: &str) {
+    panic!(
+        "Generating the binding from the headers in {} needs the `bindgen` feature. Enable it, set FFMPEG_BINDING_PATH to a prebuilt binding, or enable the `ffmpegN` feature of a vendored one.",
+        include_dir
+    );
+}
 
-    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
-        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
-    } else if let Some(ffmpeg_include_dir) = env_vars.ffmpeg_include_dir.as_ref() {
-        generate_bindings(Some(&ffmpeg_include_dir), HEADERS.iter().cloned())
-            .expect("Binding generation failed.")
-            // Is it correct to generate binding to one file? :-/
-            .write_to_file(output_binding_path)
-            .expect("Cannot write binding to file.");
-    } else {
-        panic!("No binding generation method is set!");
+/// Point the `std` paths of the binding in `OUT_DIR` to `core`, so it can be
+/// used in `no_std` crates. Done after the binding is in place rather than by
+/// bindgen's `use_core()` so that prebuilt bindings get the same treatment.
+fn make_binding_no_std(env_vars: &EnvVars) {
+    let binding_path = format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    let binding = fs::read_to_string(&binding_path).expect("Cannot read binding file.");
+    let binding = binding
+        .replace("::std::os::raw::", "::core::ffi::")
+        .replace("::std::", "::core::");
+    fs::write(&binding_path, binding).expect("Cannot write binding to file.");
+}
+
+/// The `&CStr` form of a bindgen string constant line like
+/// `pub const P_tmpdir: &'static [u8; 5usize] = b"/tmp\0";`. Literals with
+/// a NUL before the end are left alone.
+fn cstr_constant(line: &str) -> Option<String> {
+    let rest = line.strip_prefix("pub const ")?;
+    let (name, rest) = rest.split_once(": &'static [u8; ")?;
+    let (_, literal) = rest.split_once("usize] = ")?;
+    let bytes = literal.strip_prefix("b\"")?.strip_suffix("\\0\";")?;
+    if bytes.contains("\\0") || bytes.contains("\\x00") {
+        return None;
     }
+    Some(format!(
+        "pub const {}: &::std::ffi::CStr = unsafe {{ ::std::ffi::CStr::from_bytes_with_nul_unchecked({}) }};",
+        name,
+        literal.strip_suffix(';')?
+    ))
 }
 
-fn static_linking(env_vars: &EnvVars) {
-    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+/// With the `cstr-constants` feature, turn the string constants of the
+/// binding in `OUT_DIR` from NUL terminated byte arrays into `&CStr`, as
+/// bindgen's `generate_cstr()` would. Done before `make_binding_no_std()`, so
+/// the paths end up in `core`.
+fn make_string_constants_cstr(env_vars: &EnvVars) {
+    let binding_path = format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    let binding = fs::read_to_string(&binding_path).expect("Cannot read binding file.");
+    let binding: Vec<_> = binding
+        .lines()
+        .map(|line| cstr_constant(line).unwrap_or_else(|| line.to_string()))
+        .collect();
+    fs::write(&binding_path, binding.join("\n") + "\n").expect("Cannot write binding to file.");
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        use non_windows::*;
-        // Hint: set PKG_CONFIG_PATH to some placeholder value will let pkg_config probing system library.
-        if let Some(ffmpeg_pkg_config_path) = env_vars.ffmpeg_pkg_config_path.as_ref() {
-            // Probe libraries(enable emitting cargo metadata)
-            let include_paths = static_linking_with_pkg_config(&*LIBS, ffmpeg_pkg_config_path);
-            if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
-                use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
-            } else if let Some(ffmpeg_include_dir) = env_vars.ffmpeg_include_dir.as_ref() {
-                // If use ffmpeg_pkg_config_path with ffmpeg_include_dir, prefer using the user given dir rather than pkg_config_path.
-                generate_bindings(Some(ffmpeg_include_dir), HEADERS.iter().cloned())
-                    .expect("Binding generation failed.")
-                    .write_to_file(output_binding_path)
-                    .expect("Cannot write binding to file.");
-            } else {
-                generate_bindings(Some(&include_paths[0]), HEADERS.iter().cloned())
-                    .expect("Binding generation failed.")
-                    .write_to_file(output_binding_path)
-                    .expect("Cannot write binding to file.");
-            }
-        } else if let Some(ffmpeg_libs_dir) = env_vars.ffmpeg_libs_dir.as_ref() {
-            static_linking_with_libs_dir(&*LIBS, ffmpeg_libs_dir);
-            if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
-                use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
-            } else if let Some(ffmpeg_include_dir) = env_vars.ffmpeg_include_dir.as_ref() {
-                generate_bindings(Some(&ffmpeg_include_dir), HEADERS.iter().cloned())
-                    .expect("Binding generation failed.")
-                    .write_to_file(output_binding_path)
-                    .expect("Cannot write binding to file.");
+/// With the `newtype-enums` feature, turn the enums of `NEWTYPE_ENUMS` in the
+/// binding in `OUT_DIR` into newtypes. Done after bindgen rather than by its
+/// `newtype_enum()`, so that prebuilt and vendored bindings get them too. The
+/// `FfiEnum` impls of the newtypes are written to `ffi_enums.rs`.
+fn make_enums_newtype(env_vars: &EnvVars) {
+    let out_dir = env_vars.out_dir.as_ref().unwrap();
+    let binding_path = format!("{}/binding.rs", out_dir);
+    let binding = fs::read_to_string(&binding_path).expect("Cannot read binding file.");
+    let binding = newtype_enums::make_newtype_enums(&binding, &newtype_enums::NEWTYPE_ENUMS);
+    let newtypes: Vec<&str> = newtype_enums::NEWTYPE_ENUMS
+        .iter()
+        .copied()
+        .filter(|name| binding.contains(&format!("\npub struct {}(", name)))
+        .collect();
+    fs::write(&binding_path, binding).expect("Cannot write binding to file.");
+    fs::write(
+        format!("{}/ffi_enums.rs", out_dir),
+        format!("impl_ffi_enum!({});\n", newtypes.join(", ")),
+    )
+    .expect("Cannot write the FfiEnum impls to file.");
+}
+
+/// Attach serde derives behind `cfg_attr` to the structs in `SERDE_TYPES`.
+/// This is done to the binding in `OUT_DIR` instead of in bindgen, so that
+/// generated and prebuilt bindings behave the same with or without the
+/// `serde` feature.
+fn add_serde_derives(env_vars: &EnvVars) {
+    let binding_path = format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    let mut binding = fs::read_to_string(&binding_path).expect("Cannot read binding file.");
+    for name in SERDE_TYPES.iter() {
+        binding = binding.replace(
+            &format!("\npub struct {} {{", name),
+            &format!(
+                "\n#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\npub struct {} {{",
+                name
+            ),
+        );
+    }
+    fs::write(&binding_path, binding).expect("Cannot write binding to file.");
+}
+
+/// Find the library namespace an item of the binding belongs to. Items that
+/// aren't claimed by any other library but still look like FFmpeg's end up in
+/// `avutil`, while libc and system items stay only in the flat `ffi` module.
+fn classify_item(name: &str) -> Option<&'static str> {
+    NAMESPACE_PREFIXES
+        .iter()
+        .find(|(_, prefixes)| prefixes.iter().any(|prefix| name.starts_with(prefix)))
+        .map(|(namespace, _)| *namespace)
+        .or_else(|| {
+            if name.starts_with("av") || name.starts_with("AV") || name.starts_with("LIBAVUTIL_") {
+                Some("avutil")
             } else {
-                panic!("No binding generation method is set!");
+                None
             }
-        } else {
-            panic!("No linking method set!");
-        };
+        })
+}
+
+/// Strip the comments and string literals of a binding and collapse its
+/// whitespace, so items can be found no matter how the binding is formatted.
+fn normalize_binding(binding: &str) -> String {
+    let mut normalized = String::with_capacity(binding.len());
+    let mut chars = binding.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                normalized.push_str("\"\"");
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+                normalized.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                normalized.push(' ');
+            }
+            c if c.is_whitespace() => {
+                if !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+            }
+            c => normalized.push(c),
+        }
     }
-    #[cfg(target_os = "windows")]
-    {
-        use windows::static_linking_inner;
-        let include_paths = static_linking_inner(env_vars, &*LIBS);
-        if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
-            use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
-        } else {
-            generate_bindings(Some(&include_paths[0]), HEADERS.iter().cloned())
-                .expect("Binding generation failed.")
-                .write_to_file(output_binding_path)
-                .expect("Cannot write binding to file.");
+    normalized
+}
+
+/// Collect the kind and name of the top level items (and the items of
+/// `extern` blocks) of a binding generated by bindgen.
+fn binding_items(binding: &str) -> Vec<(&'static str, String)> {
+    let normalized = normalize_binding(binding);
+    let mut tokens = Vec::new();
+    let mut token_start = None;
+    for (i, c) in normalized.char_indices() {
+        let is_ident = c.is_ascii_alphanumeric() || c == '_';
+        match token_start {
+            Some(start) if !is_ident => {
+                tokens.push(&normalized[start..i]);
+                token_start = None;
+            }
+            None if is_ident => token_start = Some(i),
+            _ => {}
+        }
+        if !is_ident && c != ' ' {
+            tokens.push(&normalized[i..i + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = token_start {
+        tokens.push(&normalized[start..]);
+    }
+
+    // Whether each of the blocks we are in is an `extern` block.
+    let mut blocks = Vec::new();
+    let mut items = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            "{" => blocks.push(i >= 3 && tokens[i - 3..i] == ["extern", "\"", "\""]),
+            "}" => {
+                blocks.pop();
+            }
+            "pub" if blocks.is_empty() || blocks == [true] => {
+                let mut rest = tokens[i + 1..].iter();
+                let kind = match rest.next() {
+                    Some(&"fn") => "function",
+                    Some(&"static") => "static",
+                    Some(&"const") => "constant",
+                    Some(&"struct") | Some(&"union") | Some(&"type") => "type",
+                    _ => continue,
+                };
+                let name = match rest.next() {
+                    Some(&"mut") => rest.next(),
+                    name => name,
+                };
+                if let Some(name) = name {
+                    items.push((kind, name.to_string()));
+                }
+            }
+            _ => {}
         }
     }
+    items
 }
 
-fn docs_rs_linking(env_vars: &EnvVars) {
+/// Fail early with a targeted message if the binding in `OUT_DIR` misses items
+/// every binding has, rather than with hundreds of errors in the crate.
+fn validate_binding(env_vars: &EnvVars, generated: bool) {
+    let binding = fs::read_to_string(format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap()))
+        .expect("Cannot read binding file.");
+    let items: HashSet<_> = binding_items(&binding).into_iter().collect();
+    // Items of disabled libs aren't bound.
+    let enabled_libs = enabled_libs();
+    let missing: Vec<_> = SENTINEL_ITEMS
+        .iter()
+        .filter(|(_, name)| classify_item(name).map_or(true, |lib| enabled_libs.contains(&lib)))
+        .filter(|(kind, name)| !items.contains(&(*kind, name.to_string())))
+        .map(|(kind, name)| format!("{} {}", kind, name))
+        .collect();
+    if !missing.is_empty() {
+        panic!(
+            "The binding appears incomplete, missing: {}. {}",
+            missing.join(", "),
+            if generated {
+                format!(
+                    "Check FFMPEG_INCLUDE_DIR (or the pkg-config/vcpkg include dirs) points at FFmpeg >= {} headers.",
+                    ffmpeg_version::MIN_RELEASE
+                )
+            } else {
+                format!(
+                    "Check FFMPEG_BINDING_PATH points at a binding generated from FFmpeg >= {} headers.",
+                    ffmpeg_version::MIN_RELEASE
+                )
+            }
+        );
+    }
+}
+
+/// Compare the items of the generated binding with the ones of the vendored
+/// binding of the selected FFmpeg version, and fail with a summary if they
+/// differ.
+fn check_binding_drift(env_vars: &EnvVars) {
+    let generated =
+        fs::read_to_string(format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap()))
+            .expect("Cannot read binding file.");
+    let (_, committed_path, _) = vendored_binding();
+    let committed =
+        fs::read_to_string(committed_path).expect("Cannot read committed binding file.");
+    let generated: BTreeSet<_> = binding_items(&generated).into_iter().collect();
+    let committed: BTreeSet<_> = binding_items(&committed).into_iter().collect();
+
+    let added: Vec<_> = generated.difference(&committed).collect();
+    let removed: Vec<_> = committed.difference(&generated).collect();
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    let mut summary = format!("The generated binding drifted from {}:\n", committed_path);
+    for (kind, name) in &added {
+        summary.push_str(&format!("  + {} {}\n", kind, name));
+    }
+    for (kind, name) in &removed {
+        summary.push_str(&format!("  - {} {}\n", kind, name));
+    }
+    summary.push_str(&format!(
+        "{} added, {} removed. Copy the generated binding in OUT_DIR to {} to update it.",
+        added.len(),
+        removed.len(),
+        committed_path
+    ));
+    panic!("{}", summary);
+}
+
+/// Generate the per-library re-export lists of the namespaced modules from
+/// the binding in `OUT_DIR`, so they are always in sync with it.
+fn generate_namespaces(env_vars: &EnvVars) {
+    let out_dir = env_vars.out_dir.as_ref().unwrap();
+    let binding =
+        fs::read_to_string(format!("{}/binding.rs", out_dir)).expect("Cannot read binding file.");
+
+    let mut namespaces: BTreeMap<&str, BTreeSet<String>> = NAMESPACE_PREFIXES
+        .iter()
+        .map(|(namespace, _)| (*namespace, BTreeSet::new()))
+        .collect();
+    for (_, name) in binding_items(&binding) {
+        if let Some(namespace) = classify_item(&name) {
+            namespaces.get_mut(namespace).unwrap().insert(name);
+        }
+    }
+
+    let namespace_dir = format!("{}/namespace", out_dir);
+    fs::create_dir_all(&namespace_dir).expect("Cannot create namespace directory.");
+    for (namespace, names) in namespaces {
+        let mut content = String::from("pub use crate::ffi::{\n");
+        for name in names {
+            content.push_str(&format!("    {},\n", name));
+        }
+        content.push_str("};\n");
+        fs::write(format!("{}/{}.rs", namespace_dir, namespace), content)
+            .expect("Cannot write namespace file.");
+    }
+}
+
+/// Compile a small C file for the target that fails if the errno values the
+/// binding has, which the crate's `AVERROR(ffi::E*)` constants are built from,
+/// aren't the target's. A prebuilt binding has the values of the platform it
+/// was generated on, e.g. `EAGAIN` is 11 on Linux but 35 on macOS. With the
+/// FFmpeg headers, `AVERROR()` and `AVERROR_EOF` are checked as well.
+#[cfg(feature = "check-averror")]
+fn check_averror_values(env_vars: &EnvVars, include_dir: Option<&str>) {
+    let out_dir = env_vars.out_dir.as_ref().unwrap();
+    let binding =
+        fs::read_to_string(format!("{}/binding.rs", out_dir)).expect("Cannot read binding file.");
+    let errno = |name: &str| -> i64 {
+        let prefix = format!("pub const {}: ", name);
+        binding
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .and_then(|rest| rest.split(" = ").nth(1))
+            .and_then(|value| value.trim_end_matches(';').parse().ok())
+            .unwrap_or_else(|| panic!("{} not found in the binding.", name))
+    };
+    let include_dir = include_dir.or(env_vars.ffmpeg_include_dir.as_deref());
+
+    let mut checks = Vec::new();
+    for name in ["EAGAIN", "ENOMEM", "EINVAL"].iter() {
+        let value = errno(name);
+        checks.push((name.to_lowercase(), name.to_string(), value));
+        if include_dir.is_some() {
+            checks.push((
+                format!("averror_{}", name.to_lowercase()),
+                format!("AVERROR({})", name),
+                -value,
+            ));
+        }
+    }
+    if include_dir.is_some() {
+        checks.push((
+            "averror_eof".to_string(),
+            "AVERROR_EOF".to_string(),
+            -i64::from(u32::from_le_bytes(*b"EOF ")),
+        ));
+    }
+
+    let mut content = String::from("#include <errno.h>\n");
+    if include_dir.is_some() {
+        content.push_str("#include <libavutil/error.h>\n");
+    }
+    for (name, expr, value) in &checks {
+        // A negative array size makes the compilation fail on mismatch.
+        content.push_str(&format!(
+            "typedef char {}_matches_rust_constant[({}) == ({}) ? 1 : -1];\n",
+            name, expr, value
+        ));
+    }
+    let source_path = format!("{}/averror_check.c", out_dir);
+    fs::write(&source_path, content).expect("Cannot write AVERROR check file.");
+
+    let mut build = cc::Build::new();
+    if let Some(include_dir) = include_dir {
+        build.includes(include_roots(include_dir));
+    }
+    build
+        .file(&source_path)
+        .cargo_metadata(false)
+        .try_compile("averror_check")
+        .unwrap_or_else(|e| {
+            panic!(
+                "The errno based AVERROR constants of the binding don't match the target, generate the binding for it: {}",
+                e
+            )
+        });
+}
+
+/// The roots of an include dir, which is a path list when `FFMPEG_INCLUDE_DIR`
+/// has several (e.g. split dev packages).
+fn include_roots(include_dir: &str) -> Vec<String> {
+    normalize_path::split_path_list(include_dir, cfg!(windows))
+}
+
+/// The include dir of several roots, see `include_roots()`. Joined like
+/// `env::join_paths()`, so `env::split_paths()` gives the roots back.
+fn join_include_roots(roots: &[String]) -> String {
+    env::join_paths(roots)
+        .unwrap_or_else(|e| panic!("Include dirs {:?}: {}", roots, e))
+        .into_string()
+        .unwrap()
+}
+
+/// The path of `relative` (e.g. `libavutil/avutil.h`) in the first root of the
+/// include dir that has it.
+fn find_include(include_dir: &str, relative: &str) -> Option<PathBuf> {
+    include_roots(include_dir)
+        .into_iter()
+        .map(|root| Path::new(&root).join(relative))
+        .find(|path| path.is_file())
+}
+
+/// Parse the version int of a library from its `version.h` (and
+/// `version_major.h` for newer FFmpeg) in the include dir, the same way as
+/// `AV_VERSION_INT` does.
+fn parse_header_version(include_dir: &str, library_name: &str) -> Option<u32> {
+    let contents: Vec<String> = ["version.h", "version_major.h"]
+        .iter()
+        .filter_map(|header| find_include(include_dir, &format!("lib{}/{}", library_name, header)))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .collect();
+    let contents: Vec<&str> = contents.iter().map(String::as_str).collect();
+    version_match::parse_version(&contents, library_name)
+}
+
+/// The library whose version is compared by `check_libs_version()`,
+/// libavcodec, or libavutil without it.
+fn version_lib() -> &'static str {
+    if enabled_libs().contains(&"avcodec") {
+        "avcodec"
+    } else {
+        "avutil"
+    }
+}
+
+/// Record the majors of `version_lib()` found for the libraries linked,
+/// described by `libraries` for the error, if any is.
+fn record_libs_version(majors: Vec<u32>, libraries: String) {
+    if !majors.is_empty() {
+        *LIBS_VERSION.lock().unwrap() = Some((majors, libraries));
+    }
+}
+
+/// Record the version of the libraries in `dir`: the one of the `.pc` file in
+/// its `pkgconfig` dir, otherwise the ones in the names of the versioned shared
+/// libraries (see `dylib_name::dylib_majors()`).
+fn record_libs_dir_version(dir: &Path, libraries: String) {
+    let libname = version_lib();
+    let pc_major = fs::read_to_string(dir.join("pkgconfig").join(format!("lib{}.pc", libname)))
+        .ok()
+        .and_then(|content| version_match::version_major(version_match::pc_version(&content)?));
+    let majors = match pc_major {
+        Some(major) => vec![major],
+        None => {
+            let file_names: Vec<String> = fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(Result::ok)
+                        .filter_map(|entry| entry.file_name().into_string().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+            dylib_name::dylib_majors(&file_names, libname)
+        }
+    };
+    record_libs_version(majors, libraries);
+}
+
+#[cfg(feature = "bindgen")]
+/// Fail if the headers the binding is generated from and the libraries linked
+/// are of different FFmpeg releases, which builds but crashes at runtime on
+/// the struct layouts that differ. Nothing is checked if the version of either
+/// isn't found. Only warn with `FFMPEG_SKIP_VERSION_CHECK`.
+fn check_libs_version(env_vars: &EnvVars, include_dir: &str) {
+    let libname = version_lib();
+    let (lib_majors, libraries) = match LIBS_VERSION.lock().unwrap().clone() {
+        Some(libs_version) => libs_version,
+        None => return,
+    };
+    let header_major = match parse_header_version(include_dir, libname) {
+        Some(version) => version >> 16,
+        None => return,
+    };
+    if !version_match::is_mismatch(header_major, &lib_majors) {
+        return;
+    }
+    let message = format!(
+        "The FFmpeg headers in {} are of {}, but {} are of {}. A binding of these headers doesn't match the libraries, the program would crash at runtime.",
+        include_dir,
+        version_match::describe(libname, header_major),
+        libraries,
+        lib_majors
+            .iter()
+            .map(|major| version_match::describe(libname, *major))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if env_vars.ffmpeg_skip_version_check.is_some() {
+        println!(
+            "cargo:warning={} Going on since FFMPEG_SKIP_VERSION_CHECK is set.",
+            message
+        );
+    } else {
+        panic!(
+            "{} Point the settings at the headers and the libraries of the same FFmpeg, or set FFMPEG_SKIP_VERSION_CHECK=1 to build anyway.",
+            message
+        );
+    }
+}
+
+/// Emit the `ffmpeg_X_Y` cfg of every release of `ffmpeg_version::api_cfgs()`
+/// the binding is at least, and pass the newest of them (`X.Y`, `unknown` if
+/// older than all) as `FFMPEG_VERSION` and the libavutil version as
+/// `FFMPEG_LIBAVUTIL_VERSION` to the crate, the former to dependents too. The
+/// version comes from the `LIBAVUTIL_VERSION_*` constants of the binding, so
+/// this also works with a prebuilt binding.
+fn emit_api_cfgs(env_vars: &EnvVars) {
+    let binding = fs::read_to_string(format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap()))
+        .expect("Cannot read binding file.");
+    let constant = |name: &str| binding_constant(&binding, name);
+    let version = (
+        constant("LIBAVUTIL_VERSION_MAJOR").unwrap_or(0),
+        constant("LIBAVUTIL_VERSION_MINOR").unwrap_or(0),
+    );
+    let mut release = "unknown".to_string();
+    for (cfg, since) in ffmpeg_version::api_cfgs() {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+        if version >= since {
+            println!("cargo:rustc-cfg={}", cfg);
+            release = cfg.trim_start_matches("ffmpeg_").replace('_', ".");
+        }
+    }
+    println!("cargo:rustc-env=FFMPEG_VERSION={}", release);
+    // `DEP_FFMPEG_VERSION` of dependent build scripts.
+    println!("cargo:version={}", release);
+    println!(
+        "cargo:rustc-env=FFMPEG_LIBAVUTIL_VERSION={}.{}.{}",
+        version.0,
+        version.1,
+        constant("LIBAVUTIL_VERSION_MICRO").unwrap_or(0)
+    );
+}
+
+/// The value of a `u32` constant of the binding, like `LIBAVUTIL_VERSION_MAJOR`.
+fn binding_constant(binding: &str, name: &str) -> Option<u32> {
+    let prefix = format!("pub const {}: u32 = ", name);
+    binding.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix(&prefix)?
+            .strip_suffix(';')?
+            .parse::<u32>()
+            .ok()
+    })
+}
+
+/// Write `build_meta.rs` to `OUT_DIR`, which records how FFmpeg is linked and
+/// where the binding comes from. When a prebuilt or vendored binding is used,
+/// the header versions are the `LIB*_VERSION_*` constants of the binding, and
+/// the headers and include dirs, which can't be known, are left empty.
+fn generate_build_meta(
+    env_vars: &EnvVars,
+    link_mode: &str,
+    include_dir: Option<&str>,
+    dll_dir: Option<&Path>,
+) {
+    println!(
+        "cargo:rustc-check-cfg=cfg(ffmpeg_link_mode, values(\"static\", \"dynamic\", \"unknown\", \"none\"))"
+    );
+    println!("cargo:rustc-cfg=ffmpeg_link_mode=\"{}\"", link_mode);
+
+    let binding = fs::read_to_string(format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap()))
+        .expect("Cannot read binding file.");
+    let binding_versions = || {
+        enabled_libs()
+            .into_iter()
+            .filter_map(|libname| {
+                let constant = |part: &str| {
+                    binding_constant(
+                        &binding,
+                        &format!("LIB{}_VERSION_{}", libname.to_uppercase(), part),
+                    )
+                };
+                let version =
+                    constant("MAJOR")? << 16 | constant("MINOR")? << 8 | constant("MICRO")?;
+                Some(format!("(\"{}\", {})", libname, version))
+            })
+            .collect::<Vec<_>>()
+    };
+    let (header_versions, bound_headers, include_dirs) = match include_dir {
+        Some(include_dir) => (
+            enabled_libs()
+                .into_iter()
+                .filter_map(|libname| {
+                    parse_header_version(include_dir, libname)
+                        .map(|version| format!("(\"{}\", {})", libname, version))
+                })
+                .collect(),
+            env_vars
+                .headers()
+                .iter()
+                .map(|header| format!("{:?}", header))
+                .collect(),
+            include_roots(include_dir)
+                .iter()
+                .map(|root| format!("{:?}", root))
+                .collect(),
+        ),
+        None => (binding_versions(), vec![], vec![]),
+    };
+
+    let dll_dir = match dll_dir {
+        Some(dll_dir) if link_mode == "dynamic" => format!("Some({:?})", dll_dir.to_str().unwrap()),
+        _ => "None".to_string(),
+    };
+
+    let content = format!(
+        "pub const LINK_MODE: &str = {:?};\n\
+         pub const FFMPEG_HEADER_VERSIONS: &[(&str, u32)] = &[{}];\n\
+         pub const BOUND_HEADERS: &[&str] = &[{}];\n\
+         pub const INCLUDE_DIRS: &[&str] = &[{}];\n\
+         pub const DLL_DIR: Option<&str> = {};\n\
+         pub const LIBS: &[&str] = &{:?};\n",
+        link_mode,
+        header_versions.join(", "),
+        bound_headers.join(", "),
+        include_dirs.join(", "),
+        dll_dir,
+        enabled_libs(),
+    );
+    fs::write(
+        format!("{}/build_meta.rs", env_vars.out_dir.as_ref().unwrap()),
+        content,
+    )
+    .expect("Cannot write build metadata to file.");
+}
+
+/// Pass what the build resolved to the build scripts of dependent crates, as
+/// `DEP_FFMPEG_LINK_MODE`, `DEP_FFMPEG_LIBS` (the linked libraries, separated
+/// by `,`) and `DEP_FFMPEG_INCLUDE` (all the roots of the include dir the
+/// binding is generated from, joined by the path separator of the platform,
+/// only set if it's generated or `FFMPEG_INCLUDE_DIR` is set).
+fn emit_dependent_metadata(env_vars: &EnvVars, link_mode: &str, include_dir: Option<&str>) {
+    println!("cargo:link_mode={}", link_mode);
+    if link_mode == "none" {
+        println!("cargo:libs=");
+    } else {
+        println!("cargo:libs={}", enabled_libs().join(","));
+    }
+    if let Some(include_dir) = include_dir.or(env_vars.ffmpeg_include_dir.as_deref()) {
+        println!(
+            "cargo:include={}",
+            join_include_roots(&include_roots(include_dir))
+        );
+    }
+}
+
+/// Read the keys of a table of the build config file into `config` as the
+/// values of the env vars they mirror. Lists are joined with `;`, and `true`
+/// becomes "1" while `false` leaves the env var unset.
+fn read_build_config_table(
+    path: &str,
+    key_prefix: &str,
+    table: &toml::value::Table,
+    config: &mut HashMap<&'static str, String>,
+) {
+    for (key, value) in table {
+        if key_prefix.is_empty() && key == "target" {
+            continue;
+        }
+        let env_var = BUILD_CONFIG_KEYS
+            .iter()
+            .find(|(config_key, _)| config_key == key)
+            .map(|(_, env_var)| *env_var)
+            .unwrap_or_else(|| panic!("{}: unknown key `{}{}`.", path, key_prefix, key));
+        let value = match value {
+            toml::Value::String(value) => Some(value.clone()),
+            toml::Value::Boolean(value) => {
+                if *value {
+                    Some("1".to_string())
+                } else {
+                    None
+                }
+            }
+            toml::Value::Array(values) => Some(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => value.as_str(),
+                        _ => panic!(
+                            "{}: `{}{}` should only contain strings.",
+                            path, key_prefix, key
+                        ),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+            _ => panic!(
+                "{}: `{}{}` should be a string, a list of strings or a boolean.",
+                path, key_prefix, key
+            ),
+        };
+        match value {
+            Some(value) => config.insert(env_var, value),
+            None => config.remove(env_var),
+        };
+    }
+}
+
+/// Read the `FFMPEG_BUILD_CONFIG` file, an alternative to the env vars. The
+/// values of the `[target.<target triple>]` table of the current target
+/// override the top level ones.
+fn read_build_config(path: &str) -> HashMap<&'static str, String> {
+    println!("cargo:rerun-if-changed={}", path);
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Cannot read build config {}: {}", path, e));
+    let root = content
+        .parse::<toml::Value>()
+        .unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let root = root
+        .as_table()
+        .unwrap_or_else(|| panic!("{}: should be a table.", path));
+
+    let mut config = HashMap::new();
+    read_build_config_table(path, "", root, &mut config);
+
+    let target = env::var("TARGET").unwrap();
+    let target_table = root
+        .get("target")
+        .map(|targets| {
+            targets
+                .as_table()
+                .unwrap_or_else(|| panic!("{}: `target` should be a table.", path))
+        })
+        .and_then(|targets| targets.get(&target));
+    if let Some(target_table) = target_table {
+        let target_table = target_table
+            .as_table()
+            .unwrap_or_else(|| panic!("{}: `target.{}` should be a table.", path, target));
+        read_build_config_table(
+            path,
+            &format!("target.{}.", target),
+            target_table,
+            &mut config,
+        );
+    }
+    config
+}
+
+/// Read an env var the build depends on, and have cargo rerun the build
+/// script when it changes. The ones cargo sets (`OUT_DIR`, `CARGO_*`) don't
+/// need this.
+fn tracked_env_var(name: &str) -> Option<String> {
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var(name).ok()
+}
+
+/// Read one of the build's own env vars, preferring its target scoped names
+/// (`FFMPEG_LIBS_DIR_aarch64_unknown_linux_gnu`), see `target_env::lookup()`.
+/// The names looked up until one is set are tracked, the less specific ones
+/// don't matter then.
+fn scoped_env_var(name: &str) -> Option<String> {
+    let target = env::var("TARGET").unwrap_or_default();
+    target_env::lookup(name, &target, tracked_env_var).map(|(_, value)| value)
+}
+
+#[allow(dead_code)]
+pub struct EnvVars {
+    docs_rs: Option<String>,
+    out_dir: Option<String>,
+    ffmpeg_include_dir: Option<String>,
+    /// What set `ffmpeg_include_dir`, for diagnostics.
+    ffmpeg_include_dir_origin: &'static str,
+    ffmpeg_dll_path: Option<String>,
+    ffmpeg_implib_dir: Option<String>,
+    ffmpeg_pkg_config_path: Option<String>,
+    ffmpeg_conan_build_info: Option<String>,
+    ffmpeg_libs_dir: Option<String>,
+    ffmpeg_binding_path: Option<String>,
+    ffmpeg_binding_output_path: Option<String>,
+    ffmpeg_binding_cache_dir: Option<String>,
+    ffmpeg_no_binding_cache: Option<String>,
+    ffmpeg_extra_headers: Option<String>,
+    ffmpeg_extra_link_libs: Option<String>,
+    ffmpeg_extra_link_args: Option<String>,
+    ffmpeg_extra_link_args_bins: Option<String>,
+    cargo_feature_std: Option<String>,
+    cargo_feature_no_deprecated: Option<String>,
+    cargo_feature_cstr_constants: Option<String>,
+    cargo_feature_newtype_enums: Option<String>,
+    ffmpeg_bindgen_defines: Option<String>,
+    ffmpeg_bindgen_clang_args: Option<String>,
+    ffmpeg_whole_archive: Option<String>,
+    ffmpeg_dynamic_linking: Option<String>,
+    ffmpeg_link_mode: Option<String>,
+    ffmpeg_link_by_path: Option<String>,
+    ffmpeg_check_binding_drift: Option<String>,
+    ffmpeg_check_reproducible: Option<String>,
+    ffmpeg_no_layout_tests: Option<String>,
+    ffmpeg_vcpkg_triplet: Option<String>,
+    ffmpeg_vcpkg_features: Option<String>,
+    ffmpeg_sysroot: Option<String>,
+    ffmpeg_macos_frameworks: Option<String>,
+    ffmpeg_no_brew: Option<String>,
+    cargo_feature_build: Option<String>,
+    cargo_feature_build_gpl: Option<String>,
+    ffmpeg_source_dir: Option<String>,
+    ffmpeg_build_version: Option<String>,
+    ffmpeg_configure_args: Option<String>,
+    ffmpeg_cross_prefix: Option<String>,
+    cargo_feature_prebuilt: Option<String>,
+    ffmpeg_prebuilt_url: Option<String>,
+    ffmpeg_prebuilt_sha256: Option<String>,
+    ffmpeg_allow_older: Option<String>,
+    ffmpeg_skip_version_check: Option<String>,
+    ffmpeg_no_link: Option<String>,
+    ffmpeg_android_api: Option<String>,
+}
+
+impl EnvVars {
+    fn init() -> Self {
+        // Once a `rerun-if` is printed, cargo stops rerunning the build script
+        // on any change of the package, so the script itself is listed too.
+        println!("cargo:rerun-if-changed=build.rs");
+        println!("cargo:rerun-if-changed=build");
+        // Env vars take precedence over the build config file.
+        let build_config = scoped_env_var("FFMPEG_BUILD_CONFIG")
+            .map(|path| read_build_config(&path))
+            .unwrap_or_default();
+        let config_var =
+            |name: &str| scoped_env_var(name).or_else(|| build_config.get(name).cloned());
+        let path_var = |name: &str| {
+            config_var(name).map(|path| normalize_path::normalize_path(&path, cfg!(windows)))
+        };
+        if let Some(ffmpeg_libs) = config_var("FFMPEG_LIBS") {
+            override_libs(&ffmpeg_libs);
+        }
+        let mut env_vars = Self {
+            docs_rs: tracked_env_var("DOCS_RS"),
+            out_dir: env::var("OUT_DIR").ok(),
+            // A path list, see `include_roots()`.
+            ffmpeg_include_dir: config_var("FFMPEG_INCLUDE_DIR")
+                .map(|list| normalize_path::normalize_path_list(&list, cfg!(windows))),
+            ffmpeg_include_dir_origin: "FFMPEG_INCLUDE_DIR",
+            ffmpeg_dll_path: config_var("FFMPEG_DLL_PATH"),
+            ffmpeg_implib_dir: path_var("FFMPEG_IMPLIB_DIR"),
+            ffmpeg_pkg_config_path: config_var("FFMPEG_PKG_CONFIG_PATH"),
+            ffmpeg_conan_build_info: path_var("FFMPEG_CONAN_BUILD_INFO"),
+            ffmpeg_libs_dir: path_var("FFMPEG_LIBS_DIR"),
+            ffmpeg_binding_path: path_var("FFMPEG_BINDING_PATH"),
+            ffmpeg_binding_output_path: path_var("FFMPEG_BINDING_OUTPUT_PATH"),
+            ffmpeg_binding_cache_dir: path_var("FFMPEG_BINDING_CACHE_DIR"),
+            ffmpeg_no_binding_cache: config_var("FFMPEG_NO_BINDING_CACHE"),
+            ffmpeg_extra_headers: config_var("FFMPEG_EXTRA_HEADERS"),
+            // `FFMPEG_EXTRA_LIBS` is an alias, the libs of both are linked.
+            ffmpeg_extra_link_libs: match (
+                config_var("FFMPEG_EXTRA_LINK_LIBS"),
+                scoped_env_var("FFMPEG_EXTRA_LIBS"),
+            ) {
+                (Some(libs), Some(extra_libs)) => Some(format!("{};{}", libs, extra_libs)),
+                (libs, extra_libs) => libs.or(extra_libs),
+            },
+            ffmpeg_extra_link_args: scoped_env_var("FFMPEG_EXTRA_LINK_ARGS"),
+            ffmpeg_extra_link_args_bins: scoped_env_var("FFMPEG_EXTRA_LINK_ARGS_BINS"),
+            cargo_feature_std: env::var("CARGO_FEATURE_STD").ok(),
+            cargo_feature_no_deprecated: env::var("CARGO_FEATURE_NO_DEPRECATED").ok(),
+            cargo_feature_cstr_constants: env::var("CARGO_FEATURE_CSTR_CONSTANTS").ok(),
+            cargo_feature_newtype_enums: env::var("CARGO_FEATURE_NEWTYPE_ENUMS").ok(),
+            ffmpeg_bindgen_defines: config_var("FFMPEG_BINDGEN_DEFINES"),
+            ffmpeg_bindgen_clang_args: scoped_env_var("FFMPEG_BINDGEN_CLANG_ARGS"),
+            ffmpeg_whole_archive: config_var("FFMPEG_WHOLE_ARCHIVE"),
+            ffmpeg_dynamic_linking: config_var("FFMPEG_DYNAMIC_LINKING"),
+            ffmpeg_link_mode: config_var("FFMPEG_LINK_MODE"),
+            ffmpeg_link_by_path: config_var("FFMPEG_LINK_BY_PATH"),
+            ffmpeg_check_binding_drift: scoped_env_var("FFMPEG_CHECK_BINDING_DRIFT"),
+            ffmpeg_check_reproducible: scoped_env_var("FFMPEG_CHECK_REPRODUCIBLE"),
+            ffmpeg_no_layout_tests: scoped_env_var("FFMPEG_NO_LAYOUT_TESTS"),
+            ffmpeg_vcpkg_triplet: config_var("FFMPEG_VCPKG_TRIPLET"),
+            ffmpeg_vcpkg_features: config_var("FFMPEG_VCPKG_FEATURES"),
+            ffmpeg_sysroot: path_var("FFMPEG_SYSROOT"),
+            ffmpeg_macos_frameworks: config_var("FFMPEG_MACOS_FRAMEWORKS"),
+            ffmpeg_no_brew: config_var("FFMPEG_NO_BREW"),
+            cargo_feature_build: env::var("CARGO_FEATURE_BUILD").ok(),
+            cargo_feature_build_gpl: env::var("CARGO_FEATURE_BUILD_GPL").ok(),
+            ffmpeg_source_dir: path_var("FFMPEG_SOURCE_DIR"),
+            ffmpeg_build_version: scoped_env_var("FFMPEG_BUILD_VERSION"),
+            ffmpeg_configure_args: scoped_env_var("FFMPEG_CONFIGURE_ARGS"),
+            ffmpeg_cross_prefix: scoped_env_var("FFMPEG_CROSS_PREFIX"),
+            cargo_feature_prebuilt: env::var("CARGO_FEATURE_PREBUILT").ok(),
+            ffmpeg_prebuilt_url: scoped_env_var("FFMPEG_PREBUILT_URL"),
+            ffmpeg_prebuilt_sha256: scoped_env_var("FFMPEG_PREBUILT_SHA256"),
+            ffmpeg_allow_older: config_var("FFMPEG_ALLOW_OLDER"),
+            ffmpeg_skip_version_check: config_var("FFMPEG_SKIP_VERSION_CHECK"),
+            ffmpeg_no_link: config_var("FFMPEG_NO_LINK"),
+            ffmpeg_android_api: config_var("FFMPEG_ANDROID_API"),
+        };
+        if let Some(ffmpeg_dir) = path_var("FFMPEG_DIR") {
+            env_vars.apply_ffmpeg_dir(&ffmpeg_dir);
+        }
+        env_vars.apply_conan_pkg_config_dir();
+        env_vars
+    }
+
+    /// When `FFMPEG_CONAN_BUILD_INFO` is the output dir of Conan's
+    /// `PkgConfigDeps` generator rather than a `conanbuildinfo.json` or the
+    /// output dir of `CMakeDeps`, link through the `.pc` files in it as
+    /// `FFMPEG_PKG_CONFIG_PATH`. They include the ones of FFmpeg's
+    /// dependencies, and say whether it's shared.
+    fn apply_conan_pkg_config_dir(&mut self) {
+        let conan_dir = match self.ffmpeg_conan_build_info.as_ref() {
+            Some(conan_dir) if Path::new(conan_dir).is_dir() => conan_dir.clone(),
+            _ => return,
+        };
+        if !Path::new(&conan_dir).join("libavutil.pc").is_file() {
+            if conan_cmake_data_files(&conan_dir).is_empty() {
+                panic!(
+                    "FFMPEG_CONAN_BUILD_INFO: {} has neither libavutil.pc nor *-data.cmake files. Set it to the output dir of the PkgConfigDeps or CMakeDeps generator of Conan, or to the conanbuildinfo.json of its json generator.",
+                    conan_dir
+                );
+            }
+            return;
+        }
+        if self.ffmpeg_pkg_config_path.is_some() {
+            println!("cargo:warning=FFMPEG_CONAN_BUILD_INFO is ignored since FFMPEG_PKG_CONFIG_PATH is set.");
+        } else {
+            self.ffmpeg_pkg_config_path = Some(conan_dir);
+        }
+        self.ffmpeg_conan_build_info = None;
+    }
+
+    /// Derive the paths of an installation prefix given by `FFMPEG_DIR`:
+    /// `include` as `FFMPEG_INCLUDE_DIR`, and `lib/pkgconfig` as
+    /// `FFMPEG_PKG_CONFIG_PATH` if it has the `.pc` files (not on Windows),
+    /// `lib` as `FFMPEG_LIBS_DIR` otherwise. Paths set explicitly win. Panics
+    /// naming a derived dir that doesn't exist.
+    fn apply_ffmpeg_dir(&mut self, ffmpeg_dir: &str) {
+        let ffmpeg_dir = Path::new(ffmpeg_dir);
+        if !ffmpeg_dir.is_dir() {
+            panic!("FFMPEG_DIR: {} isn't a directory.", ffmpeg_dir.display());
+        }
+        if self.ffmpeg_include_dir.is_none() && self.ffmpeg_binding_path.is_none() {
+            let include_dir = ffmpeg_dir.join("include");
+            if !include_dir.join("libavutil").is_dir() {
+                panic!(
+                    "FFMPEG_DIR: {} has no libavutil dir. Set FFMPEG_INCLUDE_DIR to the dir of the FFmpeg headers, or FFMPEG_BINDING_PATH to a prebuilt binding.",
+                    include_dir.display()
+                );
+            }
+            self.ffmpeg_include_dir = Some(include_dir.to_str().unwrap().to_string());
+            self.ffmpeg_include_dir_origin = "FFMPEG_DIR";
+        }
+        if self.ffmpeg_pkg_config_path.is_some()
+            || self.ffmpeg_libs_dir.is_some()
+            || self.ffmpeg_dll_path.is_some()
+        {
+            return;
+        }
+        let lib_dir = ffmpeg_dir.join("lib");
+        let pkg_config_dir = lib_dir.join("pkgconfig");
+        if !cfg!(target_os = "windows") && pkg_config_dir.join("libavutil.pc").is_file() {
+            self.ffmpeg_pkg_config_path = Some(pkg_config_dir.to_str().unwrap().to_string());
+        } else if lib_dir.is_dir() {
+            self.ffmpeg_libs_dir = Some(lib_dir.to_str().unwrap().to_string());
+        } else {
+            panic!(
+                "FFMPEG_DIR: {} doesn't exist. Set FFMPEG_LIBS_DIR or FFMPEG_PKG_CONFIG_PATH to where the FFmpeg libraries are.",
+                lib_dir.display()
+            );
+        }
+    }
+
+    /// The shared libraries to link, from `FFMPEG_DLL_PATH`: a list of files
+    /// (see `dylib_name::split_dll_paths()`), where a dir stands for the
+    /// enabled libraries found in it. Panics if a file doesn't exist.
+    fn dll_paths(&self) -> Vec<PathBuf> {
+        let entries = match self.ffmpeg_dll_path.as_ref() {
+            Some(ffmpeg_dll_path) => dylib_name::split_dll_paths(ffmpeg_dll_path, cfg!(windows)),
+            None => return Vec::new(),
+        };
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = PathBuf::from(normalize_path::normalize_path(entry, cfg!(windows)));
+            if path.is_dir() {
+                let file_names: Vec<String> = fs::read_dir(&path)
+                    .unwrap_or_else(|e| panic!("FFMPEG_DLL_PATH: cannot read {}: {}", entry, e))
+                    .filter_map(|dir_entry| dir_entry.ok()?.file_name().into_string().ok())
+                    .collect();
+                let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+                let found: Vec<PathBuf> = enabled_libs()
+                    .into_iter()
+                    .filter_map(|libname| {
+                        check_dylib_major(&file_names, libname, self.header_major(libname), entry);
+                        dylib_name::pick_dylib(
+                            &file_names,
+                            libname,
+                            self.header_major(libname),
+                            self.ffmpeg_link_by_path.is_some(),
+                        )
+                        .map(|file_name| path.join(file_name))
+                    })
+                    .collect();
+                if found.is_empty() {
+                    panic!(
+                        "FFMPEG_DLL_PATH: no FFmpeg shared library found in {}.",
+                        entry
+                    );
+                }
+                paths.extend(found);
+            } else if path.is_file() {
+                paths.push(path);
+            } else {
+                panic!("FFMPEG_DLL_PATH: {} does not exist.", entry);
+            }
+        }
+        paths
+    }
+
+    /// The major version of `libname` in the headers of `FFMPEG_INCLUDE_DIR`,
+    /// the one of the shared library to pick when there are several.
+    fn header_major(&self, libname: &str) -> Option<u32> {
+        let include_dir = self.ffmpeg_include_dir.as_ref()?;
+        parse_header_version(include_dir, libname).map(|version| version >> 16)
+    }
+
+    #[cfg(feature = "bindgen")]
+    /// The settings `include_dir` comes from, for diagnostics.
+    fn include_dir_origin(&self, include_dir: &str) -> String {
+        if self.ffmpeg_include_dir.as_deref() == Some(include_dir) {
+            return self.ffmpeg_include_dir_origin.to_string();
+        }
+        if self.cargo_feature_build.is_some() {
+            return "the `build` feature".to_string();
+        }
+        if self.ffmpeg_conan_build_info.is_some() {
+            return "the `include_paths` of FFMPEG_CONAN_BUILD_INFO".to_string();
+        }
+        if system_include::system_include_dirs(
+            &env::var("TARGET").unwrap(),
+            &env::var("HOST").unwrap(),
+        )
+        .iter()
+        .any(|dir| dir == include_dir)
+        {
+            return "the system include dirs".to_string();
+        }
+        let vars: Vec<&str> = [
+            (
+                "FFMPEG_PKG_CONFIG_PATH",
+                self.ffmpeg_pkg_config_path.is_some(),
+            ),
+            (
+                "PKG_CONFIG_PATH",
+                self.ffmpeg_pkg_config_path.is_none() && env::var_os("PKG_CONFIG_PATH").is_some(),
+            ),
+            ("FFMPEG_LIBS_DIR", self.ffmpeg_libs_dir.is_some()),
+            ("FFMPEG_VCPKG_TRIPLET", self.ffmpeg_vcpkg_triplet.is_some()),
+            ("VCPKG_ROOT", env::var_os("VCPKG_ROOT").is_some()),
+        ]
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| *name)
+        .collect();
+        if vars.is_empty() {
+            "the default search path of pkg-config or vcpkg".to_string()
+        } else {
+            format!("pkg-config or vcpkg, searching {}", vars.join(", "))
+        }
+    }
+
+    /// Headers to generate the binding from, the ones of `HEADERS` from enabled
+    /// libs plus the ones in `FFMPEG_EXTRA_HEADERS` (separated by `;`), in this
+    /// fixed order and each once, so the same settings always give the same
+    /// wrapper header.
+    fn headers(&self) -> Vec<String> {
+        let enabled_libs = enabled_libs();
+        let mut headers: Vec<String> = Vec::new();
+        for header in HEADERS
+            .iter()
+            .filter(|header| {
+                let libname = header.split('/').next().unwrap().trim_start_matches("lib");
+                !LIBS.contains(&libname) || enabled_libs.contains(&libname)
+            })
+            .map(|header| header.to_string())
+            .chain(
+                self.ffmpeg_extra_headers
+                    .iter()
+                    .flat_map(|headers| headers.split(';'))
+                    .map(str::trim)
+                    .filter(|header| !header.is_empty())
+                    .map(str::to_string),
+            )
+        {
+            if !headers.contains(&header) {
+                headers.push(header);
+            }
+        }
+        headers
+    }
+}
+
+/// Link the FFmpeg static libs as whole archives, so objects that aren't
+/// referenced directly (e.g. components registered by constructors) are
+/// kept. The other libs (dependencies of FFmpeg) are linked normally after
+/// them. Everything is emitted as link args to keep this order.
+fn emit_whole_archive_link_args(
+    ffmpeg_libs: &[&str],
+    link_paths: &[PathBuf],
+    other_libs: &[String],
+) {
+    if env::var("CARGO_CFG_TARGET_VENDOR").as_deref() == Ok("apple") {
+        // Apple ld has no whole archive group, it needs the path of every archive.
+        for libname in ffmpeg_libs {
+            let archive = link_paths
+                .iter()
+                .map(|link_path| link_path.join(format!("lib{}.a", libname)))
+                .find(|archive| archive.exists())
+                .unwrap_or_else(|| panic!("Static library of {} not found!", libname));
+            println!("cargo:rustc-link-arg=-Wl,-force_load,{}", archive.display());
+        }
+    } else {
+        println!("cargo:rustc-link-arg=-Wl,--whole-archive");
+        for libname in ffmpeg_libs {
+            println!("cargo:rustc-link-arg=-l{}", libname);
+        }
+        println!("cargo:rustc-link-arg=-Wl,--no-whole-archive");
+    }
+    for libname in other_libs {
+        println!("cargo:rustc-link-arg=-l{}", libname);
+    }
+}
+
+/// Link the system libraries of the enabled `link-<name>` features and the
+/// ones in `FFMPEG_EXTRA_LINK_LIBS` and `FFMPEG_EXTRA_LIBS` (`[KIND=]NAME`
+/// separated by `;`, a `KIND` may have `,` separated modifiers), after the
+/// FFmpeg libraries. Features whose libs are already in `provided_libs`
+/// (found by pkg-config) are skipped, and the kind given to a feature lib in
+/// `FFMPEG_EXTRA_LINK_LIBS` overrides the default. `FFMPEG_<NAME>_DIR` (e.g.
+/// `FFMPEG_ZLIB_DIR`) adds a search path for a dependency.
+fn emit_dependency_libs(env_vars: &EnvVars, provided_libs: &[String], whole_archive: bool) {
+    let extra_libs: Vec<(Option<&str>, &str)> = env_vars
+        .ffmpeg_extra_link_libs
+        .iter()
+        .flat_map(|libs| libs.split(';'))
+        .map(str::trim)
+        .filter(|lib| !lib.is_empty())
+        .map(|lib| match lib.split_once('=') {
+            Some((kind, name)) => (Some(kind), name),
+            None => (None, lib),
+        })
+        .collect();
+
+    let mut libs: Vec<(Option<&str>, &str)> = Vec::new();
+    for (name, dependency_libs) in SYSTEM_DEPENDENCIES.iter() {
+        let feature = format!("CARGO_FEATURE_LINK_{}", name.to_uppercase());
+        if env::var(feature).is_err()
+            || dependency_libs
+                .iter()
+                .any(|lib| provided_libs.iter().any(|provided| provided == lib))
+        {
+            continue;
+        }
+        if let Some(dir) = scoped_env_var(&format!("FFMPEG_{}_DIR", name.to_uppercase())) {
+            println!("cargo:rustc-link-search=native={}", dir);
+        }
+        libs.extend(dependency_libs.iter().map(|lib| (None, *lib)));
+    }
+    for (kind, name) in extra_libs {
+        // A feature lib given again keeps its position, with the given kind.
+        match libs.iter_mut().find(|(_, lib)| *lib == name) {
+            Some(lib) => lib.0 = kind,
+            None => libs.push((kind, name)),
+        }
+    }
+
+    for (kind, name) in libs {
+        if whole_archive && kind != Some("framework") {
+            // Link args come after the libs, so the dependencies of the
+            // whole archives have to be link args too.
+            println!("cargo:rustc-link-arg=-l{}", name);
+        } else if let Some(kind) = kind {
+            println!("cargo:rustc-link-lib={}={}", kind, name);
+        } else {
+            println!("cargo:rustc-link-lib={}", name);
+        }
+    }
+}
+
+mod pkg_config_ffmpeg {
+    use super::*;
+
+    /// Whether pkg-config finds a library, without side effect.
+    fn pkg_config_found(libname: &str) -> bool {
+        pkg_config::Config::new()
+            // Remove side effect by disable metadata emitting
+            .cargo_metadata(false)
+            .probe(&format!("lib{}", libname))
+            .is_ok()
+    }
+
+    /// Probe every lib of `library_names` with `config`. Panics listing all the
+    /// ones pkg-config can't find, with its error for each, the
+    /// `PKG_CONFIG_PATH` searched and `hint`, rather than only the first one.
+    fn probe_libs(
+        config: &pkg_config::Config,
+        library_names: &[&str],
+        hint: &str,
+    ) -> Vec<pkg_config::Library> {
+        let mut libraries = Vec::new();
+        let mut failures = Vec::new();
+        for libname in library_names {
+            match config.probe(&format!("lib{}", libname)) {
+                Ok(library) => libraries.push(library),
+                Err(e) => failures.push((libname, e)),
+            }
+        }
+        if failures.is_empty() {
+            if let Some(library) = library_names
+                .iter()
+                .position(|libname| *libname == version_lib())
+                .map(|index| &libraries[index])
+            {
+                let libdir = match library.link_paths.first() {
+                    Some(link_path) => link_path.display().to_string(),
+                    None => pkg_config::get_variable(&format!("lib{}", version_lib()), "libdir")
+                        .unwrap_or_default(),
+                };
+                record_libs_version(
+                    version_match::version_major(&library.version)
+                        .into_iter()
+                        .collect(),
+                    format!("the libraries pkg-config finds in {}", libdir),
+                );
+            }
+            return libraries;
+        }
+        let mut message = format!(
+            "pkg-config can't find {}, {}.",
+            failures
+                .iter()
+                .map(|(libname, _)| format!("lib{}", libname))
+                .collect::<Vec<_>>()
+                .join(", "),
+            match targeted_env_var("PKG_CONFIG_PATH") {
+                Some((name, path)) => format!("{} is `{}`", name, path),
+                None => "PKG_CONFIG_PATH is unset".to_string(),
+            }
+        );
+        for (libname, e) in &failures {
+            message.push_str(&format!("\n\nlib{}: {}", libname, e.to_string().trim()));
+        }
+        message.push_str("\n\n");
+        message.push_str(hint);
+        if failures
+            .iter()
+            .any(|(_, e)| matches!(e, pkg_config::Error::CrossCompilation))
+        {
+            let target = env::var("TARGET").unwrap();
+            message.push_str(&format!(
+                "\n\npkg-config isn't set up for cross compiling to {}. Set FFMPEG_PKG_CONFIG_PATH to the .pc files of an FFmpeg built for it (cross probing is allowed then), or PKG_CONFIG_SYSROOT_DIR and PKG_CONFIG_PATH_{} for a sysroot with one.",
+                target, target
+            ));
+        }
+        panic!("{}", message);
+    }
+
+    /// Try probing ffmpeg installed in system with no side effect. Return unfound Err(library name) when failed.
+    /// The libs of `SKIPPABLE_LIBS` aren't needed, and a missing libpostproc is
+    /// reported by `skip_missing_libs()`.
+    pub fn try_probe_system_ffmpeg(library_names: &[&str]) -> Result<(), String> {
+        match library_names.iter().find(|libname| {
+            !SKIPPABLE_LIBS.contains(libname)
+                && **libname != "postproc"
+                && !pkg_config_found(libname)
+        }) {
+            Some(&libname) => Err(libname.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// The include dir of libavutil pkg-config reports (see `cflags()`),
+    /// without linking anything.
+    pub fn include_dir() -> Option<String> {
+        let library = pkg_config::Config::new()
+            .cargo_metadata(false)
+            .probe("libavutil")
+            .ok()?;
+        if library.include_paths.is_empty() {
+            pkg_config::get_variable("libavutil", "includedir").ok()?;
+        }
+        Some(join_include_roots(&cflags(
+            &[library],
+            msys_root().as_deref(),
+        )))
+    }
+
+    /// The include paths of the Cflags of `libraries`, in order and without
+    /// duplicates, with the `includedir` of libavutil if pkg-config leaves out
+    /// a system dir. Their defines are recorded in `PKG_CONFIG_DEFINES`.
+    fn cflags(libraries: &[pkg_config::Library], msys_root: Option<&str>) -> Vec<String> {
+        let mut paths = Vec::new();
+        let mut defines = PKG_CONFIG_DEFINES.lock().unwrap();
+        for library in libraries {
+            for new_path in &library.include_paths {
+                let new_path = pkg_config_path(new_path, msys_root);
+                if !paths.contains(&new_path) {
+                    paths.push(new_path);
+                }
+            }
+            // The defines of a library come in a `HashMap`.
+            let mut library_defines: Vec<String> = library
+                .defines
+                .iter()
+                .map(|(name, value)| match value {
+                    Some(value) => format!("{}={}", name, value),
+                    None => name.clone(),
+                })
+                .collect();
+            library_defines.sort();
+            for define in library_defines {
+                if !defines.contains(&define) {
+                    defines.push(define);
+                }
+            }
+        }
+        if paths.is_empty() {
+            let includedir = pkg_config::get_variable("libavutil", "includedir")
+                .expect("Cannot get the include dir of libavutil from pkg-config.");
+            paths.push(pkg_config_path(Path::new(&includedir), msys_root));
+        }
+        paths
+    }
+
+    /// Link the FFmpeg found by pkg-config in its default search path, as
+    /// shared libraries if there are any. Returns the include paths (see
+    /// `cflags()`). Panics naming all the libraries that aren't found.
+    pub fn system_linking_with_pkg_config(library_names: &[&str]) -> Vec<String> {
+        let library_names = skip_missing_libs(library_names, pkg_config_found, "by pkg-config");
+        let libraries = probe_libs(
+            &pkg_config::Config::new(),
+            &library_names,
+            "No linking method is set, so the FFmpeg of the system is looked for. Install its dev packages (e.g. libavcodec-dev), set FFMPEG_PKG_CONFIG_PATH or FFMPEG_LIBS_DIR to link FFmpeg statically, or FFMPEG_DLL_PATH to link it dynamically.",
+        );
+        cflags(&libraries, msys_root().as_deref())
+    }
+
+    /// Whether the `libdir` pkg-config gives for libavutil has its shared
+    /// library (or the import library of MinGW) but not its static one, as the
+    /// dev packages of distros do.
+    pub fn only_shared_libs() -> bool {
+        let libdir = match pkg_config::get_variable("libavutil", "libdir") {
+            Ok(libdir) => {
+                PathBuf::from(pkg_config_path(Path::new(&libdir), msys_root().as_deref()))
+            }
+            Err(_) => return false,
+        };
+        !libdir.join("libavutil.a").exists()
+            && (libdir.join("libavutil.dll.a").exists()
+                || fs::read_dir(&libdir).is_ok_and(|entries| {
+                    entries.filter_map(Result::ok).any(|entry| {
+                        entry
+                            .file_name()
+                            .to_str()
+                            .is_some_and(|name| dylib_name::is_dylib_of(name, "avutil"))
+                    })
+                }))
+    }
+
+    /// The root of MSYS2 (e.g. `C:\\msys64`), which the absolute paths in its `.pc`
+    /// files are relative to. `None` if `cygpath` isn't found, or not on Windows.
+    fn msys_root() -> Option<String> {
+        if !cfg!(windows) {
+            return None;
+        }
+        let output = std::process::Command::new("cygpath")
+            .args(["-w", "/"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+    }
+
+    /// A path reported by pkg-config, as a Windows path on Windows, and under
+    /// `PKG_CONFIG_SYSROOT_DIR` elsewhere if that's set.
+    fn pkg_config_path(path: &Path, msys_root: Option<&str>) -> String {
+        let path = path.to_str().unwrap();
+        if cfg!(windows) {
+            normalize_path::msys_path(path, msys_root)
+        } else {
+            let sysroot = targeted_env_var("PKG_CONFIG_SYSROOT_DIR").map(|(_, sysroot)| sysroot);
+            normalize_path::sysroot_path(path, sysroot.as_deref())
+        }
+    }
+
+    /// Link what a static FFmpeg of MSYS2 needs on windows-gnu besides the
+    /// libraries its `.pc` files list, `WINDOWS_GNU_LIBS`, skipping the ones in
+    /// `provided_libs`. Returns the libs linked.
+    fn emit_windows_gnu_dependencies(provided_libs: &[String], whole_archive: bool) -> Vec<String> {
+        if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows")
+            || env::var("CARGO_CFG_TARGET_ENV").as_deref() != Ok("gnu")
+        {
+            return Vec::new();
+        }
+        let libs: Vec<String> = WINDOWS_GNU_LIBS
+            .iter()
+            .filter(|lib| !provided_libs.iter().any(|provided| provided == *lib))
+            .map(|lib| lib.to_string())
+            .collect();
+        for lib in &libs {
+            if whole_archive {
+                println!("cargo:rustc-link-arg=-l{}", lib);
+            } else {
+                println!("cargo:rustc-link-lib={}", lib);
+            }
+        }
+        libs
+    }
+
+    /// Link the FFmpeg found in `ffmpeg_pkg_config_path`, as static libraries
+    /// if `statik`, as shared ones otherwise. Returns the include paths (see
+    /// `cflags()`).
+    pub fn linking_with_pkg_config(
+        env_vars: &EnvVars,
+        library_names: &[&str],
+        ffmpeg_pkg_config_path: &str,
+        statik: bool,
+        whole_archive: bool,
+    ) -> Vec<String> {
+        prepend_pkg_config_path(ffmpeg_pkg_config_path);
+        let library_names = &skip_missing_libs(
+            library_names,
+            pkg_config_found,
+            "through FFMPEG_PKG_CONFIG_PATH",
+        )[..];
+        let msys_root = msys_root();
+        let musl = statik && env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("musl");
+        // Link args are emitted by ourselves for whole archive linking, for
+        // musl where the static libs have to come in dependency order, and on
+        // Windows where the paths of MSYS2 have to be translated first.
+        let emit_metadata = !whole_archive && !musl && !cfg!(windows);
+        let mut link_paths = Vec::new();
+        let mut other_libs: Vec<String> = Vec::new();
+        let mut provided_libs: Vec<String> = Vec::new();
+        let libraries = probe_libs(
+            pkg_config::Config::new()
+                .statik(statik)
+                .cargo_metadata(emit_metadata),
+            library_names,
+            "Check that FFMPEG_PKG_CONFIG_PATH is the dir of the .pc files of an FFmpeg built for the target, or set FFMPEG_LIBS_DIR to the dir of its libraries instead.",
+        );
+        let paths = cflags(&libraries, msys_root.as_deref());
+        for library in libraries {
+            provided_libs.extend(library.libs.iter().cloned());
+            if !emit_metadata {
+                for link_path in library.link_paths {
+                    let link_path =
+                        PathBuf::from(pkg_config_path(&link_path, msys_root.as_deref()));
+                    if !link_paths.contains(&link_path) {
+                        println!("cargo:rustc-link-search=native={}", link_path.display());
+                        link_paths.push(link_path);
+                    }
+                }
+                for framework in library.frameworks {
+                    println!("cargo:rustc-link-lib=framework={}", framework);
+                }
+                for lib in library.libs {
+                    if !library_names.contains(&lib.as_str()) {
+                        // Keep the last occurrence, it's the one that satisfies
+                        // every lib before it.
+                        other_libs.retain(|x| x != &lib);
+                        other_libs.push(lib);
+                    }
+                }
+            }
+        }
+        if whole_archive {
+            emit_whole_archive_link_args(
+                &static_link_order(library_names),
+                &link_paths,
+                &other_libs,
+            );
+        } else if !emit_metadata {
+            for libname in static_link_order(library_names) {
+                if statik {
+                    println!("cargo:rustc-link-lib=static={}", libname);
+                } else {
+                    println!("cargo:rustc-link-lib={}", libname);
+                }
+            }
+            for lib in &other_libs {
+                println!("cargo:rustc-link-lib={}", lib);
+            }
+        }
+        if statik {
+            provided_libs.extend(emit_windows_gnu_dependencies(&provided_libs, whole_archive));
+        }
+        emit_dependency_libs(env_vars, &provided_libs, whole_archive);
+        if statik {
+            emit_emscripten_dependencies();
+            emit_musl_dependencies(whole_archive);
+            let libdir = pkg_config::get_variable("libavcodec", "libdir")
+                .ok()
+                .map(|libdir| pkg_config_path(Path::new(&libdir), msys_root.as_deref()));
+            emit_android_dependencies(libdir.as_deref().map(Path::new), whole_archive);
+        }
+        paths
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod non_windows {
+    use super::*;
+
+    /// Link what a static FFmpeg needs on macOS, iOS and tvOS, which
+    /// `FFMPEG_LIBS_DIR` can't discover: the frameworks of `MACOS_FRAMEWORKS`,
+    /// or the ones in `FFMPEG_MACOS_FRAMEWORKS` (separated by `;`, empty for
+    /// none) instead, and `MACOS_LIBS`. Returns the libs linked.
+    fn emit_macos_dependencies(env_vars: &EnvVars, whole_archive: bool) -> Vec<String> {
+        if !matches!(
+            env::var("CARGO_CFG_TARGET_OS").as_deref(),
+            Ok("macos") | Ok("ios") | Ok("tvos")
+        ) {
+            return Vec::new();
+        }
+        let frameworks: Vec<&str> = match env_vars.ffmpeg_macos_frameworks.as_ref() {
+            Some(frameworks) => frameworks
+                .split(';')
+                .map(str::trim)
+                .filter(|framework| !framework.is_empty())
+                .collect(),
+            None => MACOS_FRAMEWORKS.to_vec(),
+        };
+        for framework in frameworks {
+            println!("cargo:rustc-link-lib=framework={}", framework);
+        }
+        for lib in MACOS_LIBS.iter() {
+            if whole_archive {
+                println!("cargo:rustc-link-arg=-l{}", lib);
+            } else {
+                println!("cargo:rustc-link-lib={}", lib);
+            }
+        }
+        MACOS_LIBS.iter().map(|lib| lib.to_string()).collect()
+    }
+
+    pub fn static_linking_with_libs_dir(
+        env_vars: &EnvVars,
+        library_names: &[&str],
+        ffmpeg_libs_dir: &str,
+        whole_archive: bool,
+    ) {
+        let library_names = &skip_missing_libs(
+            library_names,
+            |libname| {
+                Path::new(ffmpeg_libs_dir)
+                    .join(format!("lib{}.a", libname))
+                    .exists()
+            },
+            "in FFMPEG_LIBS_DIR",
+        )[..];
+        println!("cargo:rustc-link-search=native={}", ffmpeg_libs_dir);
+        let library_names = static_link_order(library_names);
+        if whole_archive {
+            emit_whole_archive_link_args(&library_names, &[PathBuf::from(ffmpeg_libs_dir)], &[]);
+        } else {
+            for library_name in library_names {
+                println!("cargo:rustc-link-lib=static={}", library_name);
+            }
+        }
+        let provided_libs = emit_macos_dependencies(env_vars, whole_archive);
+        emit_dependency_libs(env_vars, &provided_libs, whole_archive);
+        emit_linux_gnu_dependencies(whole_archive);
+        emit_bsd_dependencies(whole_archive);
+        emit_emscripten_dependencies();
+        emit_musl_dependencies(whole_archive);
+        emit_android_dependencies(Some(Path::new(ffmpeg_libs_dir)), whole_archive);
+    }
+
+    /// Link the shared libraries in `ffmpeg_libs_dir` (`libavcodec.so`, or
+    /// `libavcodec.dylib` on Apple platforms), the versioned ones (see
+    /// `dylib_name::pick_dylib()`) when the unversioned symlinks aren't there.
+    /// Panics naming the required ones that aren't there.
+    pub fn dynamic_linking_with_libs_dir(
+        env_vars: &EnvVars,
+        library_names: &[&str],
+        ffmpeg_libs_dir: &str,
+        reason: &str,
+    ) {
+        let extension = if env::var("CARGO_CFG_TARGET_VENDOR").as_deref() == Ok("apple") {
+            "dylib"
+        } else {
+            "so"
+        };
+        let shared_lib = |libname: &str| format!("lib{}.{}", libname, extension);
+        let file_names: Vec<String> = fs::read_dir(ffmpeg_libs_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|file_name| file_name.contains(extension))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+        let pick = |libname: &str| {
+            check_dylib_major(
+                &file_names,
+                libname,
+                env_vars.header_major(libname),
+                ffmpeg_libs_dir,
+            );
+            dylib_name::pick_dylib(
+                &file_names,
+                libname,
+                env_vars.header_major(libname),
+                env_vars.ffmpeg_link_by_path.is_some(),
+            )
+        };
+        let found = |libname: &str| pick(libname).is_some();
+        let library_names = skip_missing_libs(library_names, found, "in FFMPEG_LIBS_DIR");
+        let missing: Vec<String> = library_names
+            .iter()
+            .filter(|libname| !found(libname))
+            .map(|libname| shared_lib(libname))
+            .collect();
+        if !missing.is_empty() {
+            let only_static = library_names.iter().all(|libname| {
+                Path::new(ffmpeg_libs_dir)
+                    .join(format!("lib{}.a", libname))
+                    .exists()
+            });
+            panic!(
+                "{}, but {} isn't in FFMPEG_LIBS_DIR {}.{}",
+                reason,
+                missing.join(", "),
+                ffmpeg_libs_dir,
+                if only_static {
+                    " It only has the static libraries, link them with FFMPEG_LINK_MODE=static."
+                } else {
+                    ""
+                }
+            );
+        }
+        println!("cargo:rustc-link-search=native={}", ffmpeg_libs_dir);
+        for library_name in library_names {
+            let file_name = pick(library_name).unwrap();
+            emit_unix_dylib(
+                &Path::new(ffmpeg_libs_dir).join(file_name),
+                env_vars.ffmpeg_link_by_path.is_some(),
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use super::*;
+
+    /// The vcpkg name of a Rust target arch.
+    fn vcpkg_arch(target_arch: &str) -> &'static str {
+        match target_arch {
+            "x86_64" => "x64",
+            "x86" => "x86",
+            "aarch64" => "arm64",
+            "arm" => "arm",
+            _ => panic!("vcpkg has no Windows triplet for {}.", target_arch),
+        }
+    }
+
+    /// The CRT the static libraries of a triplet were built with: `Some(true)`
+    /// for `/MT` (`-static`), `Some(false)` for `/MD` (`-static-md`), `None`
+    /// for dll triplets, which link with either.
+    fn static_crt_of(triplet: &str) -> Option<bool> {
+        if triplet.ends_with("-static") {
+            Some(true)
+        } else if triplet.ends_with("-static-md") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// How to fix linking the libraries of `triplet` with the CRT of the crate.
+    fn crt_mismatch_help(triplet: &str, arch: &str, crt_static: bool) -> String {
+        if crt_static {
+            format!(
+                "The vcpkg triplet {} is built with the dynamic CRT (/MD), but the crate is built with `crt-static` (/MT). \
+                 Use the {}-windows-static triplet, or remove `-C target-feature=+crt-static` from RUSTFLAGS.",
+                triplet, arch
+            )
+        } else {
+            format!(
+                "The vcpkg triplet {} is built with the static CRT (/MT), but the crate is built with the dynamic CRT (/MD), \
+                 which fails with LNK2038. Install FFmpeg for the {}-windows-static-md triplet, \
+                 or build with RUSTFLAGS=\"-C target-feature=+crt-static\".",
+                triplet, arch
+            )
+        }
+    }
+
+    /// Whether FFmpeg is installed for `triplet` in the vcpkg root.
+    fn ffmpeg_installed(triplet: &str) -> bool {
+        vcpkg::find_vcpkg_root(&vcpkg::Config::new()).is_ok_and(|root| {
+            root.join("installed")
+                .join(triplet)
+                .join("include")
+                .join("libavutil")
+                .is_dir()
+        })
+    }
+
+    /// The triplet to link: `FFMPEG_VCPKG_TRIPLET` or `VCPKGRS_TRIPLET` (the
+    /// one of the vcpkg crate) if set, otherwise the dll triplet
+    /// (`x64-windows`) with `VCPKGRS_DYNAMIC`, or the static triplet matching
+    /// the CRT of the crate, or the dll triplet if FFmpeg is only installed for
+    /// that one. Panics if the CRTs don't match.
+    fn vcpkg_triplet(env_vars: &EnvVars, arch: &str, crt_static: bool) -> String {
+        let triplet = match env_vars
+            .ffmpeg_vcpkg_triplet
+            .clone()
+            .or_else(|| tracked_env_var("VCPKGRS_TRIPLET"))
+        {
+            Some(triplet) => triplet,
+            None if tracked_env_var("VCPKGRS_DYNAMIC").is_some() => format!("{}-windows", arch),
+            None => {
+                let static_triplet = if crt_static {
+                    format!("{}-windows-static", arch)
+                } else {
+                    format!("{}-windows-static-md", arch)
+                };
+                let dll_triplet = format!("{}-windows", arch);
+                if !ffmpeg_installed(&static_triplet) && ffmpeg_installed(&dll_triplet) {
+                    dll_triplet
+                } else {
+                    static_triplet
+                }
+            }
+        };
+        if static_crt_of(&triplet) == Some(!crt_static) {
+            panic!("{}", crt_mismatch_help(&triplet, arch, crt_static));
+        }
+        triplet
+    }
+
+    /// The features of the vcpkg `ffmpeg` port the build needs: the ones named
+    /// like the libs of the enabled cargo features, and the ones in
+    /// `FFMPEG_VCPKG_FEATURES` (separated by `;`, e.g. `x264;nonfree`).
+    fn required_port_features(env_vars: &EnvVars) -> Vec<&str> {
+        let mut features: Vec<&str> = enabled_libs()
+            .into_iter()
+            .filter(|libname| *libname != "avutil" && LIBS.contains(libname))
+            .collect();
+        for feature in env_vars
+            .ffmpeg_vcpkg_features
+            .iter()
+            .flat_map(|features| features.split(';'))
+            .map(str::trim)
+        {
+            if !feature.is_empty() && !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+        features
+    }
+
+    /// Print the version of the `ffmpeg` port installed for `triplet`, and
+    /// panic with the `vcpkg install` command to run if it lacks one of
+    /// `required_port_features()`, or the library of an enabled lib.
+    fn check_port_features(env_vars: &EnvVars, library: &vcpkg::Library, triplet: &str) {
+        let required = required_port_features(env_vars);
+        let status = vcpkg::find_vcpkg_root(&vcpkg::Config::new())
+            .ok()
+            .and_then(|root| fs::read_to_string(root.join("installed/vcpkg/status")).ok())
+            .and_then(|status| vcpkg_status::port_status(&status, "ffmpeg", triplet));
+        let mut missing: Vec<&str> = Vec::new();
+        if let Some(status) = status.as_ref() {
+            println!(
+                "Linking FFmpeg {} of the vcpkg triplet {}, with the port features {}.",
+                status.version,
+                triplet,
+                status.features.join(", ")
+            );
+            record_libs_version(
+                version_match::release_lib_major(&status.version, version_lib())
+                    .into_iter()
+                    .collect(),
+                format!(
+                    "the libraries of the vcpkg triplet {} (FFmpeg {})",
+                    triplet, status.version
+                ),
+            );
+            missing.extend(
+                required
+                    .iter()
+                    .filter(|feature| !status.features.iter().any(|x| x == *feature)),
+            );
+        } else {
+            println!("Linking FFmpeg of the vcpkg triplet {}.", triplet);
+        }
+        for libname in enabled_libs() {
+            let found = library.link_paths.iter().any(|link_path| {
+                link_path.join(format!("{}.lib", libname)).is_file()
+                    || link_path.join(format!("lib{}.a", libname)).is_file()
+            });
+            if !LIBS.contains(&libname) {
+                // A lib of `FFMPEG_LIBS` the port doesn't link by itself.
+                if !found {
+                    panic!(
+                        "FFMPEG_LIBS: {} isn't in the vcpkg triplet {}.",
+                        libname, triplet
+                    );
+                }
+                println!("cargo:rustc-link-lib={}", libname);
+                continue;
+            }
+            if !found && libname != "avutil" && !missing.contains(&libname) {
+                missing.push(libname);
+            }
+        }
+        if !missing.is_empty() {
+            panic!(
+                "The FFmpeg of the vcpkg triplet {} lacks the port features {}. Install them with `{}`.",
+                triplet,
+                missing.join(", "),
+                vcpkg_status::install_command("ffmpeg", &required, triplet)
+            );
+        }
+    }
+
+    /// Link FFmpeg from vcpkg, statically or as dlls depending on the triplet.
+    /// The dlls are copied to `OUT_DIR`, which cargo puts in `PATH` for
+    /// `cargo run` and `cargo test`. Returns whether FFmpeg is linked
+    /// statically, the include paths and the dir of the dlls.
+    pub fn vcpkg_linking_inner(env_vars: &EnvVars) -> (bool, Vec<String>, Option<PathBuf>) {
+        let crt_static = env::var("CARGO_CFG_TARGET_FEATURE")
+            .is_ok_and(|features| features.split(',').any(|f| f == "crt-static"));
+        let arch = vcpkg_arch(&env::var("CARGO_CFG_TARGET_ARCH").unwrap());
+        let triplet = vcpkg_triplet(env_vars, arch, crt_static);
+        println!("cargo:rerun-if-env-changed=VCPKG_ROOT");
+        let mut config = vcpkg::Config::new();
+        config.target_triplet(&triplet).copy_dlls(true);
+        let library = config.find_package("ffmpeg").unwrap_or_else(|e| {
+            // The static-md triplet is the default without `crt-static`, but
+            // FFmpeg may only be installed for the /MT one.
+            let other = format!("{}-windows-static", arch);
+            let other_installed =
+                env_vars.ffmpeg_vcpkg_triplet.is_none() && !crt_static && ffmpeg_installed(&other);
+            if other_installed {
+                panic!("{}", crt_mismatch_help(&other, arch, crt_static));
+            }
+            panic!(
+                "FFmpeg not found for the vcpkg triplet {}: {}\nInstall it with `{}`.",
+                triplet,
+                e,
+                vcpkg_status::install_command(
+                    "ffmpeg",
+                    &required_port_features(env_vars),
+                    &triplet
+                )
+            )
+        });
+        check_port_features(env_vars, &library, &triplet);
+        if !library.is_static {
+            println!("Linking the FFmpeg dlls of the vcpkg triplet {}:", triplet);
+            for dll in library.found_dlls.iter() {
+                println!("    {}", dll.display());
+            }
+            println!(
+                "cargo:rustc-link-search=native={}",
+                env_vars.out_dir.as_ref().unwrap()
+            );
+        }
+        let include_paths = library
+            .include_paths
+            .into_iter()
+            .map(|x| x.to_str().unwrap().to_string())
+            .collect();
+        (
+            library.is_static,
+            include_paths,
+            library.dll_paths.into_iter().next(),
+        )
+    }
+}
+
+/// Find the import library of a dll. It's searched in `implib_dir` if given,
+/// otherwise next to the dll and in `../lib` of the dll dir (where MSVC builds
+/// put it). Both `avcodec-60.lib` and `avcodec.lib` are accepted for
+/// `avcodec-60.dll`. Returns the library name to link and the dir it's in,
+/// falling back to the dll name and dir.
+fn find_import_lib(dll_name: &str, dll_dir: &str, implib_dir: Option<&str>) -> (String, String) {
+    let dirs = match implib_dir {
+        Some(implib_dir) => vec![PathBuf::from(implib_dir)],
+        None => vec![
+            PathBuf::from(dll_dir),
+            PathBuf::from(dll_dir).join("..").join("lib"),
+        ],
+    };
+    // `avcodec-60` -> `avcodec`
+    let unversioned_name = match dll_name.rsplit_once('-') {
+        Some((name, version))
+            if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            name
+        }
+        _ => dll_name,
+    };
+    let import_lib = dirs
+        .iter()
+        .flat_map(|dir| [dll_name, unversioned_name].map(|name| (name, dir)))
+        .find(|(name, dir)| dir.join(format!("{}.lib", name)).is_file())
+        .map(|(name, dir)| (name.to_string(), dir.to_str().unwrap().to_string()))
+        .unwrap_or_else(|| {
+            (
+                dll_name.to_string(),
+                implib_dir.unwrap_or(dll_dir).to_string(),
+            )
+        });
+    import_lib
+}
+
+/// The file and dir of the import library GNU ld links a dll named `dll_name`
+/// through, searched like in `find_import_lib()`. Without one, GNU ld links
+/// the dll itself.
+fn find_gnu_import_lib(
+    dll_name: &str,
+    dll_dir: &str,
+    implib_dir: Option<&str>,
+) -> Option<(String, String)> {
+    let dirs = match implib_dir {
+        Some(implib_dir) => vec![PathBuf::from(implib_dir)],
+        None => vec![
+            PathBuf::from(dll_dir),
+            PathBuf::from(dll_dir).join("..").join("lib"),
+        ],
+    };
+    let import_libs = dylib_name::gnu_import_lib_names(dll_name);
+    dirs.iter()
+        .flat_map(|dir| import_libs.iter().map(move |import_lib| (import_lib, dir)))
+        .find(|(import_lib, dir)| dir.join(import_lib).is_file())
+        .map(|(import_lib, dir)| (import_lib.clone(), dir.to_str().unwrap().to_string()))
+}
+
+/// Panic if `file_names` (of `dir`) has versioned shared libraries of
+/// `libname`, but none of the `major` of its headers. A library of another
+/// major version has another ABI, so the binary would crash at runtime.
+fn check_dylib_major(file_names: &[&str], libname: &str, major: Option<u32>, dir: &str) {
+    let majors = dylib_name::dylib_majors(file_names, libname);
+    match major {
+        Some(major) if !majors.is_empty() && !majors.contains(&major) => panic!(
+            "{} has lib{} {}, but the headers are of lib{} {}. Link the libraries of the headers, or point FFMPEG_INCLUDE_DIR at the headers of the libraries.",
+            dir,
+            libname,
+            majors
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            libname,
+            major
+        ),
+        _ => {}
+    }
+}
+
+/// Link a shared library on Unix by the `-l` shorthand if the unversioned
+/// symlink of the dev packages is next to it and `by_path` isn't set.
+/// Otherwise (e.g. only the runtime packages are installed), the file is
+/// linked verbatim (`-l:libavcodec.so.60`), or by its path for ld64, which has
+/// no `-l:`.
+fn emit_unix_dylib(path: &Path, by_path: bool) {
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    let link_name = dylib_name::dylib_link_name(file_name);
+    let unversioned = if file_name.ends_with(".dylib") {
+        format!("lib{}.dylib", link_name)
+    } else {
+        format!("lib{}.so", link_name)
+    };
+    if !by_path && (file_name == unversioned || path.with_file_name(&unversioned).exists()) {
+        println!("cargo:rustc-link-lib=dylib={}", link_name);
+    } else if file_name.ends_with(".dylib") {
+        println!("cargo:rustc-link-arg={}", path.display());
+    } else {
+        println!("cargo:rustc-link-lib=dylib:+verbatim={}", file_name);
+    }
+}
+
+/// Returns the include dir the binding is generated from, `None` if a prebuilt
+/// binding is used.
+fn dynamic_linking(env_vars: &EnvVars) -> Option<String> {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+
+    // The dlls are often all in the same dir, search it once.
+    let mut search_dirs: Vec<String> = Vec::new();
+    for ffmpeg_dll_path in env_vars.dll_paths() {
+        // Extract dll file name, the name to link and the dir the dll is in.
+        let ffmpeg_dll_filename = ffmpeg_dll_path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let ffmpeg_dll_name = dylib_name::dylib_link_name(&ffmpeg_dll_filename);
+        let ffmpeg_dll_dir = ffmpeg_dll_path
+            .parent()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let windows_gnu = env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows")
+            && env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("gnu");
+        let search_dir = if windows_gnu {
+            // The shared FFmpeg zips have no `.dll.a`, but GNU ld links a dll
+            // directly. The files are linked verbatim (`-l:`), as
+            // `avcodec-60.dll` isn't found by `-lavcodec-60` or `-lavcodec`.
+            let (link_file, link_dir) = find_gnu_import_lib(
+                &ffmpeg_dll_name,
+                &ffmpeg_dll_dir,
+                env_vars.ffmpeg_implib_dir.as_deref(),
+            )
+            .unwrap_or_else(|| (ffmpeg_dll_filename.clone(), ffmpeg_dll_dir.clone()));
+            println!("cargo:rustc-link-lib=dylib:+verbatim={}", link_file);
+            link_dir
+        } else if cfg!(target_os = "windows") {
+            // The linker needs the import library rather than the dll itself.
+            let (ffmpeg_implib_name, ffmpeg_implib_dir) = find_import_lib(
+                &ffmpeg_dll_name,
+                &ffmpeg_dll_dir,
+                env_vars.ffmpeg_implib_dir.as_deref(),
+            );
+            println!("cargo:rustc-link-lib=dylib={}", ffmpeg_implib_name);
+            ffmpeg_implib_dir
+        } else {
+            emit_unix_dylib(&ffmpeg_dll_path, env_vars.ffmpeg_link_by_path.is_some());
+            ffmpeg_dll_dir
+        };
+        if !search_dirs.contains(&search_dir) {
+            search_dirs.push(search_dir);
+        }
+    }
+    for search_dir in search_dirs {
+        println!("cargo:rustc-link-search=native={}", search_dir);
+    }
+    let dll_paths = env_vars.dll_paths();
+    let file_names: Vec<&str> = dll_paths
+        .iter()
+        .filter_map(|dll_path| dll_path.file_name()?.to_str())
+        .collect();
+    record_libs_version(
+        dylib_name::dylib_majors(&file_names, version_lib()),
+        format!(
+            "the libraries of FFMPEG_DLL_PATH {}",
+            env_vars.ffmpeg_dll_path.as_ref().unwrap()
+        ),
+    );
+
+    prebuilt_or_generated_binding(env_vars, output_binding_path)
+}
+
+/// Use `FFMPEG_BINDING_PATH`, or generate the binding from
+/// `FFMPEG_INCLUDE_DIR` or the headers of the system. Returns the include dir
+/// the binding is generated from, `None` if a prebuilt binding is used.
+fn prebuilt_or_generated_binding(env_vars: &EnvVars, output_binding_path: &str) -> Option<String> {
+    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        None
+    } else {
+        let include_dir = env_vars
+            .ffmpeg_include_dir
+            .clone()
+            .or_else(system_include_dir)
+            .unwrap_or_else(|| panic!("{}", NO_BINDING_SOURCE));
+        write_generated_binding(env_vars, &include_dir, output_binding_path);
+        Some(include_dir)
+    }
+}
+
+/// The prebuilt FFmpeg archives of the `prebuilt` feature: target, URL and
+/// SHA-256. An archive has `include` and `lib` dirs, and `bin` with the dlls of
+/// a shared Windows build, possibly in a single top-level dir. Only archives
+/// whose checksum was verified by hand are listed.
+static PREBUILT_FFMPEG: [(&str, &str, &str); 0] = [];
+
+/// The dir containing `include/libavutil` among `dir` and its subdirs.
+fn find_prebuilt_root(dir: &Path) -> Option<PathBuf> {
+    let is_root = |dir: &Path| dir.join("include").join("libavutil").is_dir();
+    if is_root(dir) {
+        return Some(dir.to_path_buf());
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| is_root(path))
+}
+
+/// Download and extract the prebuilt FFmpeg of the `prebuilt` feature into
+/// `OUT_DIR`, and point the env vars at it so the usual linking and binding
+/// generation use it: `FFMPEG_DLL_PATH` for shared libraries on Unix,
+/// `FFMPEG_LIBS_DIR` otherwise, and `FFMPEG_INCLUDE_DIR` unless it's set. The
+/// archive is `FFMPEG_PREBUILT_URL` (any URL curl understands, `file://` for
+/// air-gapped environments) or the one of `PREBUILT_FFMPEG` for the target,
+/// and its SHA-256 has to be `FFMPEG_PREBUILT_SHA256` or the pinned one.
+fn fetch_prebuilt(env_vars: &mut EnvVars) {
+    let target = env::var("TARGET").unwrap();
+    let pinned = PREBUILT_FFMPEG.iter().find(|(t, _, _)| *t == target);
+    let url = match (env_vars.ffmpeg_prebuilt_url.as_deref(), pinned) {
+        (Some(url), _) => url.to_string(),
+        (None, Some((_, url, _))) => url.to_string(),
+        (None, None) => panic!(
+            "No prebuilt FFmpeg is known for {}. Set FFMPEG_PREBUILT_URL and FFMPEG_PREBUILT_SHA256 to an archive of one, \
+             or disable the `prebuilt` feature and set FFMPEG_PKG_CONFIG_PATH, FFMPEG_LIBS_DIR or FFMPEG_DLL_PATH (vcpkg on Windows).",
+            target
+        ),
+    };
+    let sha256 = match (env_vars.ffmpeg_prebuilt_sha256.as_deref(), pinned) {
+        (Some(sha256), _) => sha256.to_lowercase(),
+        (None, Some((_, pinned_url, sha256))) if *pinned_url == url => sha256.to_string(),
+        _ => panic!(
+            "FFMPEG_PREBUILT_SHA256 has to be set to the SHA-256 of {}.",
+            url
+        ),
+    };
+
+    let prebuilt_dir = Path::new(env_vars.out_dir.as_ref().unwrap())
+        .join("ffmpeg-prebuilt")
+        .join(&sha256);
+    let extract_dir = prebuilt_dir.join("extracted");
+    let root = match find_prebuilt_root(&extract_dir) {
+        Some(root) => {
+            println!("Reusing the prebuilt FFmpeg in {}.", root.display());
+            root
+        }
+        None => {
+            fs::create_dir_all(&prebuilt_dir).expect("Cannot create the prebuilt FFmpeg dir.");
+            let file_name = url.rsplit('/').next().unwrap_or("ffmpeg");
+            let archive = prebuilt_dir.join(file_name);
+            let downloaded = run(
+                std::process::Command::new("curl")
+                    .args([
+                        "--fail",
+                        "--location",
+                        "--silent",
+                        "--show-error",
+                        "--output",
+                    ])
+                    .arg(&archive)
+                    .arg(&url),
+                "curl",
+            );
+            if !downloaded {
+                panic!("Cannot download {}.", url);
+            }
+            let data = fs::read(&archive).expect("Cannot read the downloaded archive.");
+            let actual = sha256::sha256_hex(&data);
+            if actual != sha256 {
+                let _ = fs::remove_file(&archive);
+                panic!("The SHA-256 of {} is {}, expected {}.", url, actual, sha256);
+            }
+            let _ = fs::remove_dir_all(&extract_dir);
+            fs::create_dir_all(&extract_dir).expect("Cannot create the extraction dir.");
+            // The tar of Windows 10+ and macOS extracts zips too.
+            let extracted = if file_name.ends_with(".zip") && !cfg!(target_os = "windows") {
+                run(
+                    std::process::Command::new("unzip")
+                        .arg("-q")
+                        .arg(&archive)
+                        .arg("-d")
+                        .arg(&extract_dir),
+                    "unzip",
+                )
+            } else {
+                run(
+                    std::process::Command::new("tar")
+                        .arg("-xf")
+                        .arg(&archive)
+                        .arg("-C")
+                        .arg(&extract_dir),
+                    "tar",
+                )
+            };
+            if !extracted {
+                panic!("Cannot extract {}.", archive.display());
+            }
+            find_prebuilt_root(&extract_dir)
+                .unwrap_or_else(|| panic!("{} has no include/libavutil dir.", url))
+        }
+    };
+
+    let lib_dir = root.join("lib");
+    let has_dylib = fs::read_dir(&lib_dir).is_ok_and(|entries| {
+        entries.filter_map(Result::ok).any(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                !name.ends_with(".dll") && dylib_name::is_dylib_of(name, "avutil")
+            })
+        })
+    });
+    env_vars.ffmpeg_pkg_config_path = None;
+    if has_dylib && !cfg!(target_os = "windows") {
+        env_vars.ffmpeg_dll_path = Some(lib_dir.to_str().unwrap().to_string());
+        env_vars.ffmpeg_libs_dir = None;
+    } else {
+        env_vars.ffmpeg_dll_path = None;
+        env_vars.ffmpeg_libs_dir = Some(lib_dir.to_str().unwrap().to_string());
+    }
+    if env_vars.ffmpeg_include_dir.is_none() && env_vars.ffmpeg_binding_path.is_none() {
+        env_vars.ffmpeg_include_dir = Some(root.join("include").to_str().unwrap().to_string());
+        env_vars.ffmpeg_include_dir_origin = "the `prebuilt` feature";
+    }
+}
+
+/// The prefix Homebrew reports through `brew --prefix`, `None` if brew isn't
+/// on `PATH`.
+fn brew_prefix() -> Option<String> {
+    let output = std::process::Command::new("brew")
+        .arg("--prefix")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let prefix = String::from_utf8(output.stdout).ok()?;
+    Some(prefix.trim().to_string())
+}
+
+/// When building for macOS on macOS without any linking method set, point
+/// `FFMPEG_PKG_CONFIG_PATH` at the FFmpeg installed by Homebrew, so it's linked
+/// through pkg-config: `<prefix>/opt/ffmpeg` (what `brew --prefix ffmpeg`
+/// reports), or the newest versioned keg (`ffmpeg@7`...) if the plain formula
+/// isn't installed. Homebrew only ships the shared libraries, so they're
+/// linked dynamically. `FFMPEG_NO_BREW` opts out.
+fn detect_homebrew_ffmpeg(env_vars: &mut EnvVars) {
+    if !cfg!(target_os = "macos")
+        || env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("macos")
+        || env_vars.ffmpeg_no_brew.is_some()
+        || env_vars.ffmpeg_link_mode.as_deref() == Some("static")
+        || env_vars.ffmpeg_no_link.is_some()
+        || env_vars.cargo_feature_build.is_some()
+        || env_vars.ffmpeg_pkg_config_path.is_some()
+        || env_vars.ffmpeg_conan_build_info.is_some()
+        || env_vars.ffmpeg_libs_dir.is_some()
+        || env_vars.ffmpeg_dll_path.is_some()
+    {
+        return;
+    }
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    for prefix in homebrew::prefixes(brew_prefix().as_deref(), &target_arch) {
+        let opt_dir = Path::new(&prefix).join("opt");
+        let names: Vec<String> = match fs::read_dir(&opt_dir) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+            Err(_) => continue,
+        };
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        for keg in homebrew::ffmpeg_kegs(&names) {
+            let pkg_config_dir = opt_dir.join(keg).join("lib").join("pkgconfig");
+            if pkg_config_dir.join("libavutil.pc").is_file() {
+                println!(
+                    "Using the Homebrew FFmpeg in {}, set FFMPEG_NO_BREW to opt out.",
+                    opt_dir.join(keg).display()
+                );
+                env_vars.ffmpeg_pkg_config_path =
+                    Some(pkg_config_dir.to_str().unwrap().to_string());
+                env_vars
+                    .ffmpeg_dynamic_linking
+                    .get_or_insert_with(|| "1".to_string());
+                return;
+            }
+        }
+    }
+}
+
+/// Make the linking branches follow `FFMPEG_LINK_MODE` (`static` or
+/// `dynamic`) instead of inferring the mode from which env vars are set:
+/// `dynamic` acts as `FFMPEG_DYNAMIC_LINKING`, and `static` ignores
+/// `FFMPEG_DLL_PATH` when there is a static FFmpeg to link. Fails with a
+/// targeted message on settings that can't be linked that way.
+fn apply_link_mode(env_vars: &mut EnvVars) {
+    if env_vars.docs_rs.is_some() || env_vars.ffmpeg_no_link.is_some() {
+        return;
+    }
+    match env_vars.ffmpeg_link_mode.as_deref() {
+        None => {}
+        Some("dynamic") => {
+            if env_vars.cargo_feature_build.is_some() {
+                panic!(
+                    "FFMPEG_LINK_MODE is dynamic, but the `build` feature builds a static FFmpeg."
+                );
+            }
+            env_vars
+                .ffmpeg_dynamic_linking
+                .get_or_insert_with(|| "1".to_string());
+        }
+        Some("static") => {
+            if env_vars.ffmpeg_dynamic_linking.is_some() {
+                panic!("FFMPEG_LINK_MODE is static, but FFMPEG_DYNAMIC_LINKING is set. Unset one of them.");
+            }
+            let static_source = env_vars.ffmpeg_pkg_config_path.is_some()
+                || env_vars.ffmpeg_conan_build_info.is_some()
+                || env_vars.ffmpeg_libs_dir.is_some()
+                || env_vars.cargo_feature_build.is_some();
+            if env_vars.ffmpeg_dll_path.is_some() {
+                if !static_source {
+                    panic!("FFMPEG_LINK_MODE is static, but only FFMPEG_DLL_PATH is set, which links shared libraries. Set FFMPEG_PKG_CONFIG_PATH or FFMPEG_LIBS_DIR to a static FFmpeg.");
+                }
+                println!(
+                    "cargo:warning=FFMPEG_DLL_PATH is ignored since FFMPEG_LINK_MODE is static."
+                );
+                env_vars.ffmpeg_dll_path = None;
+            }
+            if !static_source && cfg!(not(target_os = "windows")) {
+                panic!("FFMPEG_LINK_MODE is static, but no static FFmpeg is set, and the one of the system found by pkg-config is linked dynamically. Set FFMPEG_PKG_CONFIG_PATH or FFMPEG_LIBS_DIR, or enable the `build` feature.");
+            }
+        }
+        Some(link_mode) => panic!(
+            "FFMPEG_LINK_MODE is {:?}, it has to be `static` or `dynamic`.",
+            link_mode
+        ),
+    }
+}
+
+/// The FFmpeg release the `build` feature downloads without `FFMPEG_SOURCE_DIR`.
+const DEFAULT_BUILD_VERSION: &str = "7.1";
+
+/// Run a command of the source build and return whether it succeeded.
+fn run(command: &mut std::process::Command, what: &str) -> bool {
+    println!("Running {:?}", command);
+    command
+        .status()
+        .unwrap_or_else(|e| panic!("Cannot run {}: {}", what, e))
+        .success()
+}
+
+/// Build static FFmpeg from source into `OUT_DIR/ffmpeg` for the `build`
+/// feature, and return the dir of its pkg-config files. The source is
+/// `FFMPEG_SOURCE_DIR`, or the `FFMPEG_BUILD_VERSION` release (by default
+/// `DEFAULT_BUILD_VERSION`) cloned from git. The libraries of the enabled
+/// features are built, along with `FFMPEG_CONFIGURE_ARGS`. Nothing is rebuilt
+/// if the previous build had the same source and configure arguments.
+fn build_ffmpeg_from_source(env_vars: &EnvVars) -> String {
+    if cfg!(target_os = "windows") {
+        panic!("The `build` feature needs a Unix host to run FFmpeg's configure script.");
+    }
+    if let Some(libname) = enabled_libs()
+        .into_iter()
+        .find(|libname| !LIBS.contains(libname))
+    {
+        panic!(
+            "FFMPEG_LIBS: lib{} isn't part of FFmpeg, so the `build` feature can't build it.",
+            libname
+        );
+    }
+    if enabled_libs().contains(&"postproc") && env_vars.cargo_feature_build_gpl.is_none() {
+        panic!("libpostproc is GPL, enable the `build-gpl` feature to build it with the `postproc` feature.");
+    }
+    let ffmpeg_dir = Path::new(env_vars.out_dir.as_ref().unwrap()).join("ffmpeg");
+    let install_dir = ffmpeg_dir.join("install");
+    let build_dir = ffmpeg_dir.join("build");
+    let pkg_config_dir = install_dir.join("lib").join("pkgconfig");
+
+    let mut args = configure_args::configure_args(
+        install_dir.to_str().unwrap(),
+        &enabled_libs(),
+        env_vars.cargo_feature_build_gpl.is_some(),
+    );
+    let target = env::var("TARGET").unwrap();
+    if target != env::var("HOST").unwrap() {
+        let cross_prefix = env_vars
+            .ffmpeg_cross_prefix
+            .clone()
+            .unwrap_or_else(|| configure_args::cross_prefix(&target));
+        args.extend(configure_args::cross_args(
+            &env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
+            &env::var("CARGO_CFG_TARGET_OS").unwrap(),
+            &cross_prefix,
+            env_vars.ffmpeg_sysroot.as_deref(),
+        ));
+    }
+    if let Some(configure_args) = env_vars.ffmpeg_configure_args.as_ref() {
+        args.extend(shlex::split(configure_args).unwrap_or_else(|| {
+            panic!(
+                "FFMPEG_CONFIGURE_ARGS has unbalanced quotes: {}",
+                configure_args
+            )
+        }));
+    }
+
+    let version = env_vars
+        .ffmpeg_build_version
+        .as_deref()
+        .unwrap_or(DEFAULT_BUILD_VERSION);
+    let source = match env_vars.ffmpeg_source_dir.as_ref() {
+        Some(source_dir) => format!("source dir {}", source_dir),
+        None => format!("release {}", version),
+    };
+
+    // Rebuilding FFmpeg takes minutes, so reuse the previous build when
+    // nothing changed.
+    let stamp_path = ffmpeg_dir.join("configure_args");
+    let stamp = format!("{}\n{}", source, args.join("\n"));
+    if pkg_config_dir.join("libavutil.pc").exists()
+        && fs::read_to_string(&stamp_path).is_ok_and(|previous| previous == stamp)
+    {
+        println!("Reusing the FFmpeg built in {}.", install_dir.display());
+        return pkg_config_dir.to_str().unwrap().to_string();
+    }
+
+    let source_dir = match env_vars.ffmpeg_source_dir.as_ref() {
+        Some(source_dir) => PathBuf::from(source_dir),
+        None => {
+            let source_dir = ffmpeg_dir.join("src");
+            // The release the source was cloned at, it's cloned again when
+            // FFMPEG_BUILD_VERSION changes.
+            let version_path = ffmpeg_dir.join("src_version");
+            if !source_dir.join("configure").exists()
+                || fs::read_to_string(&version_path).ok().as_deref() != Some(version)
+            {
+                let _ = fs::remove_dir_all(&source_dir);
+                let cloned = run(
+                    std::process::Command::new("git")
+                        .args(["clone", "--depth", "1", "--branch"])
+                        .arg(format!("n{}", version))
+                        .arg("https://git.ffmpeg.org/ffmpeg.git")
+                        .arg(&source_dir),
+                    "git",
+                );
+                if !cloned {
+                    panic!("Cannot download the source of FFmpeg {}. Set FFMPEG_SOURCE_DIR to a source tree.", version);
+                }
+                fs::write(&version_path, version).expect("Cannot write the FFmpeg source version.");
+            }
+            source_dir
+        }
+    };
+
+    let _ = fs::remove_dir_all(&build_dir);
+    fs::create_dir_all(&build_dir).expect("Cannot create the FFmpeg build dir.");
+    let configured = run(
+        std::process::Command::new("sh")
+            .arg(source_dir.join("configure"))
+            .args(&args)
+            .current_dir(&build_dir),
+        "configure",
+    );
+    if !configured {
+        let log =
+            fs::read_to_string(build_dir.join("ffbuild").join("config.log")).unwrap_or_default();
+        panic!(
+            "Configuring FFmpeg failed, the end of ffbuild/config.log:\n{}",
+            configure_args::log_tail(&log, 30)
+        );
+    }
+    let jobs = env::var("NUM_JOBS").unwrap_or_else(|_| "1".to_string());
+    let built = run(
+        std::process::Command::new("make")
+            .arg(format!("-j{}", jobs))
+            .arg("install")
+            .current_dir(&build_dir),
+        "make",
+    );
+    if !built {
+        panic!("Building FFmpeg failed, see the build script output above.");
+    }
+    fs::write(&stamp_path, stamp).expect("Cannot write the FFmpeg build stamp.");
+    pkg_config_dir.to_str().unwrap().to_string()
+}
+
+/// Link the libraries in `FFMPEG_LIBS_DIR` on Windows, e.g. the `lib` dir of the
+/// shared builds of gyan.dev or BtbN: `avcodec.lib`... (`libavcodec.dll.a`...
+/// for the GNU toolchain), with the dlls in `../bin`. Without the dlls there,
+/// the libraries are assumed to be static. Returns the link mode, the include
+/// dir the binding is generated from (`FFMPEG_INCLUDE_DIR`, otherwise
+/// `../include`), `None` if a prebuilt binding is used, and the dll dir.
+fn windows_libs_dir_linking(env_vars: &EnvVars) -> (&'static str, Option<String>, Option<PathBuf>) {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    let ffmpeg_libs_dir = env_vars.ffmpeg_libs_dir.as_ref().unwrap();
+    let gnu = env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("gnu");
+
+    let file_names: Vec<String> = fs::read_dir(ffmpeg_libs_dir)
+        .unwrap_or_else(|e| panic!("Cannot read FFMPEG_LIBS_DIR {}: {}", ffmpeg_libs_dir, e))
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    println!("cargo:rustc-link-search=native={}", ffmpeg_libs_dir);
+    for libname in enabled_libs() {
+        let link_name = file_names
+            .iter()
+            .filter_map(|file_name| {
+                dylib_name::windows_lib_link_name(file_name, libname, gnu)
+                    .map(|link_name| (file_name.len(), link_name))
+            })
+            .min()
+            .map(|(_, link_name)| link_name)
+            .unwrap_or_else(|| {
+                panic!(
+                    "{}.lib isn't found in FFMPEG_LIBS_DIR {}.",
+                    libname, ffmpeg_libs_dir
+                )
+            });
+        println!("cargo:rustc-link-lib=dylib={}", link_name);
+    }
+
+    // Both are next to the libs dir.
+    let ffmpeg_dir = Path::new(ffmpeg_libs_dir)
+        .parent()
+        .unwrap_or(Path::new(".."));
+    let dll_dir = ffmpeg_dir.join("bin");
+    let dynamic = fs::read_dir(&dll_dir).is_ok_and(|mut entries| {
+        entries.any(|entry| {
+            entry.is_ok_and(|entry| {
+                entry.file_name().to_str().is_some_and(|name| {
+                    name.ends_with(".dll") && dylib_name::is_dylib_of(name, "avutil")
+                })
+            })
+        })
+    });
+    let (link_mode, dll_dir) = if dynamic {
+        ("dynamic", Some(dll_dir))
+    } else {
+        ("static", None)
+    };
+    record_libs_dir_version(
+        dll_dir.as_deref().unwrap_or(Path::new(ffmpeg_libs_dir)),
+        format!("the libraries in FFMPEG_LIBS_DIR {}", ffmpeg_libs_dir),
+    );
+
+    let include_dir = if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        None
+    } else {
+        let include_dir = env_vars.ffmpeg_include_dir.clone().unwrap_or_else(|| {
+            let include_dir = ffmpeg_dir.join("include");
+            if !include_dir.is_dir() {
+                panic!("No binding generation method is set!");
+            }
+            include_dir.to_str().unwrap().to_string()
+        });
+        write_generated_binding(env_vars, &include_dir, output_binding_path);
+        Some(include_dir)
+    };
+    (link_mode, include_dir, dll_dir)
+}
+
+/// Without any linking method set, link the FFmpeg of the system found by
+/// pkg-config. Returns the include dir the binding is generated from, `None`
+/// if a prebuilt binding is used.
+fn system_linking(env_vars: &EnvVars) -> Option<String> {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+
+    let include_paths = pkg_config_ffmpeg::system_linking_with_pkg_config(&enabled_libs());
+    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        None
+    } else {
+        let include_dir = env_vars
+            .ffmpeg_include_dir
+            .clone()
+            .unwrap_or_else(|| join_include_roots(&include_paths));
+        write_generated_binding(env_vars, &include_dir, output_binding_path);
+        Some(include_dir)
+    }
+}
+
+/// Whether to link the FFmpeg of the system found by pkg-config when no
+/// linking method is set. Always on Windows but for windows-gnu, where the one
+/// of MSYS2 is used if pkg-config finds it, and vcpkg otherwise.
+fn system_pkg_config_linking() -> bool {
+    if cfg!(not(target_os = "windows")) {
+        return true;
+    }
+    env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("gnu")
+        && pkg_config_ffmpeg::try_probe_system_ffmpeg(&enabled_libs()).is_ok()
+}
+
+/// An env var the way the pkg-config crate reads it: the first set of
+/// `<var>_<target>`, `<var>_<target with underscores>`, `TARGET_<var>` (or
+/// `HOST_<var>` when not cross compiling) and `<var>`. Returns its name and
+/// value.
+fn targeted_env_var(var_base: &str) -> Option<(String, String)> {
+    let target = env::var("TARGET").unwrap();
+    let kind = if target == env::var("HOST").unwrap() {
+        "HOST"
+    } else {
+        "TARGET"
+    };
+    vec![
+        format!("{}_{}", var_base, target),
+        format!("{}_{}", var_base, target.replace('-', "_")),
+        format!("{}_{}", kind, var_base),
+        var_base.to_string(),
+    ]
+    .into_iter()
+    .find_map(|name| tracked_env_var(&name).map(|value| (name, value)))
+}
+
+/// Search `ffmpeg_pkg_config_path` first in the pkg-config probes, keeping the
+/// `PKG_CONFIG_PATH` exported already (e.g. by Nix or for cross builds) after
+/// it, so the `.pc` files of FFmpeg's dependencies are still found there. The
+/// target specific variable (`PKG_CONFIG_PATH_<target>`...) pkg-config reads
+/// is the one extended if set. When cross compiling, pkg-config is allowed to
+/// probe, since the given dir is the one of the target.
+fn prepend_pkg_config_path(ffmpeg_pkg_config_path: &str) {
+    let (name, existing) = match targeted_env_var("PKG_CONFIG_PATH") {
+        Some((name, existing)) => (name, Some(existing)),
+        None => ("PKG_CONFIG_PATH".to_string(), None),
+    };
+    env::set_var(
+        name,
+        normalize_path::prepend_path_list(
+            ffmpeg_pkg_config_path,
+            existing.as_deref(),
+            cfg!(windows),
+        ),
+    );
+    if env::var("TARGET").unwrap() != env::var("HOST").unwrap()
+        && targeted_env_var("PKG_CONFIG_ALLOW_CROSS").is_none()
+    {
+        env::set_var("PKG_CONFIG_ALLOW_CROSS", "1");
+    }
+}
+
+/// Whether to link the FFmpeg found through `FFMPEG_PKG_CONFIG_PATH` as shared
+/// libraries: if `FFMPEG_DYNAMIC_LINKING` is set, or if only the shared ones
+/// are installed.
+fn pkg_config_dynamic(env_vars: &EnvVars) -> bool {
+    let ffmpeg_pkg_config_path = match env_vars.ffmpeg_pkg_config_path.as_ref() {
+        Some(ffmpeg_pkg_config_path) => ffmpeg_pkg_config_path,
+        None => return false,
+    };
+    if env_vars.ffmpeg_dynamic_linking.is_some() {
+        return true;
+    }
+    prepend_pkg_config_path(ffmpeg_pkg_config_path);
+    let only_shared = pkg_config_ffmpeg::only_shared_libs();
+    if only_shared && env_vars.ffmpeg_link_mode.as_deref() == Some("static") {
+        panic!("FFMPEG_LINK_MODE is static, but only the shared FFmpeg libraries are found through FFMPEG_PKG_CONFIG_PATH {}.", ffmpeg_pkg_config_path);
+    }
+    if only_shared {
+        println!("cargo:warning=Only the shared FFmpeg libraries are found through FFMPEG_PKG_CONFIG_PATH, linking them dynamically.");
+    }
+    only_shared
+}
+
+/// Link the FFmpeg found through `ffmpeg_pkg_config_path`, statically if
+/// `statik`. Returns the include dir the binding is generated from, `None` if
+/// a prebuilt binding is used.
+fn pkg_config_linking(
+    env_vars: &EnvVars,
+    ffmpeg_pkg_config_path: &str,
+    statik: bool,
+) -> Option<String> {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+
+    println!(
+        "Linking FFmpeg {} through pkg-config.",
+        if statik { "statically" } else { "dynamically" }
+    );
+    let whole_archive = env_vars.ffmpeg_whole_archive.is_some();
+    if whole_archive && !statik {
+        println!("cargo:warning=FFMPEG_WHOLE_ARCHIVE is ignored when linking dynamically.");
+    }
+    // Probe libraries(enable emitting cargo metadata)
+    let include_paths = pkg_config_ffmpeg::linking_with_pkg_config(
+        env_vars,
+        &enabled_libs(),
+        ffmpeg_pkg_config_path,
+        statik,
+        whole_archive && statik,
+    );
+    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        None
+    } else if let Some(ffmpeg_include_dir) = env_vars.ffmpeg_include_dir.as_ref() {
+        // If use ffmpeg_pkg_config_path with ffmpeg_include_dir, prefer using the user given dir rather than pkg_config_path.
+        write_generated_binding(env_vars, ffmpeg_include_dir, output_binding_path);
+        Some(ffmpeg_include_dir.clone())
+    } else {
+        let include_dir = join_include_roots(&include_paths);
+        write_generated_binding(env_vars, &include_dir, output_binding_path);
+        Some(include_dir)
+    }
+}
+
+/// Link FFmpeg from vcpkg. Returns the link mode of the triplet, the include
+/// dir the binding is generated from (`None` if a prebuilt binding is used) and
+/// the dir of the dlls of a dll triplet.
+fn vcpkg_linking(env_vars: &EnvVars) -> (&'static str, Option<String>, Option<PathBuf>) {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows::vcpkg_linking_inner;
+        let (is_static, include_paths, dll_dir) = vcpkg_linking_inner(env_vars);
+        if is_static && env_vars.ffmpeg_whole_archive.is_some() {
+            println!("cargo:warning=FFMPEG_WHOLE_ARCHIVE is ignored when linking with vcpkg.");
+        }
+        let link_mode = if is_static { "static" } else { "dynamic" };
+        if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+            use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+            (link_mode, None, dll_dir)
+        } else {
+            let include_dir = join_include_roots(&include_paths);
+            write_generated_binding(env_vars, &include_dir, output_binding_path);
+            (link_mode, Some(include_dir), dll_dir)
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (env_vars, output_binding_path);
+        unreachable!("vcpkg is only used on Windows.");
+    }
+}
+
+/// Returns the include dir the binding is generated from, `None` if a prebuilt
+/// binding is used.
+fn static_linking(env_vars: &EnvVars) -> Option<String> {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+
+    if let Some(ffmpeg_pkg_config_path) = env_vars.ffmpeg_pkg_config_path.as_ref() {
+        return pkg_config_linking(env_vars, ffmpeg_pkg_config_path, true);
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use non_windows::*;
+        if let Some(ffmpeg_libs_dir) = env_vars.ffmpeg_libs_dir.as_ref() {
+            static_linking_with_libs_dir(
+                env_vars,
+                &enabled_libs(),
+                ffmpeg_libs_dir,
+                env_vars.ffmpeg_whole_archive.is_some(),
+            );
+            record_libs_dir_version(
+                Path::new(ffmpeg_libs_dir),
+                format!("the libraries in FFMPEG_LIBS_DIR {}", ffmpeg_libs_dir),
+            );
+            prebuilt_or_generated_binding(env_vars, output_binding_path)
+        } else {
+            unreachable!("The system FFmpeg is linked when no linking method is set.");
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = output_binding_path;
+        unreachable!("FFMPEG_LIBS_DIR and vcpkg are linked on their own on Windows.");
+    }
+}
+
+/// The `*-data.cmake` files Conan's `CMakeDeps` generator wrote in `dir`.
+fn conan_cmake_data_files(dir: &str) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.ends_with("-data.cmake"))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Link the FFmpeg Conan installed, from the `conanbuildinfo.json` in
+/// `FFMPEG_CONAN_BUILD_INFO`, or the `*-data.cmake` files of the `CMakeDeps`
+/// output dir in it: the libraries of the ffmpeg package, then the
+/// ones of its dependencies and the system libraries, searched in the lib dirs
+/// of all. The binding is generated from their include dirs with their
+/// defines. Returns the link mode of the package, the include dir the binding
+/// is generated from (`None` if a prebuilt binding is used) and the dir of its
+/// shared libraries.
+fn conan_linking(env_vars: &mut EnvVars) -> (&'static str, Option<String>, Option<PathBuf>) {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    let build_info_path = env_vars.ffmpeg_conan_build_info.clone().unwrap();
+    println!("cargo:rerun-if-changed={}", build_info_path);
+    let build_info = if Path::new(&build_info_path).is_dir() {
+        conan_cmake_data_files(&build_info_path)
+            .into_iter()
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                fs::read_to_string(&path)
+                    .map(|cmake| (name, cmake))
+                    .map_err(|e| format!("{}: {}", path.display(), e))
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .and_then(|files| conan::parse_cmake_deps(&files))
+    } else {
+        fs::read_to_string(&build_info_path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| conan::parse_build_info(&json))
+    }
+    .unwrap_or_else(|e| panic!("FFMPEG_CONAN_BUILD_INFO {}: {}", build_info_path, e));
+
+    // Without the `shared` option, a package with the shared libraries is
+    // taken as shared.
+    let shared = build_info.shared.unwrap_or_else(|| {
+        build_info
+            .lib_dirs
+            .iter()
+            .chain(&build_info.bin_dirs)
+            .any(|dir| {
+                fs::read_dir(dir).is_ok_and(|entries| {
+                    entries.filter_map(Result::ok).any(|entry| {
+                        entry.file_name().to_str().is_some_and(|name| {
+                            !name.ends_with(".dll.a") && dylib_name::is_dylib_of(name, "avutil")
+                        })
+                    })
+                })
+            })
+    });
+    let whole_archive = env_vars.ffmpeg_whole_archive.is_some() && !shared;
+    if env_vars.ffmpeg_whole_archive.is_some() && shared {
+        println!("cargo:warning=FFMPEG_WHOLE_ARCHIVE is ignored when linking dynamically.");
+    }
+    let libs = skip_missing_libs(
+        &enabled_libs(),
+        |libname| build_info.ffmpeg_libs.iter().any(|lib| lib == libname),
+        &format!("in the ffmpeg package of {}", build_info_path),
+    );
+    for libname in &libs {
+        if !build_info.ffmpeg_libs.iter().any(|lib| lib == libname) {
+            panic!(
+                "FFMPEG_CONAN_BUILD_INFO {}: the ffmpeg package has no {} library, enable its `{}` option.",
+                build_info_path, libname, libname
+            );
+        }
+    }
+    let libs = static_link_order(&libs);
+
+    for dir in &build_info.lib_dirs {
+        println!("cargo:rustc-link-search=native={}", dir);
+    }
+    for dir in &build_info.framework_dirs {
+        println!("cargo:rustc-link-search=framework={}", dir);
+    }
+    if whole_archive {
+        let link_paths: Vec<PathBuf> = build_info.lib_dirs.iter().map(PathBuf::from).collect();
+        emit_whole_archive_link_args(&libs, &link_paths, &build_info.dependency_libs);
+    } else {
+        for libname in &libs {
+            let kind = if shared { "dylib" } else { "static" };
+            println!("cargo:rustc-link-lib={}={}", kind, libname);
+        }
+        for lib in &build_info.dependency_libs {
+            println!("cargo:rustc-link-lib={}", lib);
+        }
+    }
+    for framework in &build_info.frameworks {
+        println!("cargo:rustc-link-lib=framework={}", framework);
+    }
+    if !shared {
+        emit_dependency_libs(env_vars, &build_info.dependency_libs, whole_archive);
+    }
+
+    // The defines of the packages come first, so the ones of
+    // `FFMPEG_BINDGEN_DEFINES` win.
+    if !build_info.defines.is_empty() {
+        env_vars.ffmpeg_bindgen_defines = Some(
+            build_info
+                .defines
+                .iter()
+                .cloned()
+                .chain(env_vars.ffmpeg_bindgen_defines.take())
+                .collect::<Vec<String>>()
+                .join(";"),
+        );
+    }
+    let link_mode = if shared { "dynamic" } else { "static" };
+    let dll_dir = if !shared {
+        None
+    } else if cfg!(target_os = "windows") {
+        build_info.bin_dirs.first().map(PathBuf::from)
+    } else {
+        build_info.lib_dirs.first().map(PathBuf::from)
+    };
+    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        return (link_mode, None, dll_dir);
+    }
+    let include_dir = env_vars.ffmpeg_include_dir.clone().unwrap_or_else(|| {
+        if build_info.include_dirs.is_empty() {
+            panic!(
+                "FFMPEG_CONAN_BUILD_INFO {}: no package has `include_paths`.",
+                build_info_path
+            );
+        }
+        join_include_roots(&build_info.include_dirs)
+    });
+    write_generated_binding(env_vars, &include_dir, output_binding_path);
+    (link_mode, Some(include_dir), dll_dir)
+}
+
+/// Link the shared libraries in `FFMPEG_LIBS_DIR` with
+/// `FFMPEG_DYNAMIC_LINKING`. Returns the include dir the binding is generated
+/// from, `None` if a prebuilt binding is used.
+fn libs_dir_dynamic_linking(env_vars: &EnvVars) -> Option<String> {
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    if env_vars.ffmpeg_whole_archive.is_some() {
+        println!("cargo:warning=FFMPEG_WHOLE_ARCHIVE is ignored when linking dynamically.");
+    }
+    #[cfg(not(target_os = "windows"))]
+    non_windows::dynamic_linking_with_libs_dir(
+        env_vars,
+        &enabled_libs(),
+        env_vars.ffmpeg_libs_dir.as_ref().unwrap(),
+        if env_vars.ffmpeg_link_mode.is_some() {
+            "FFMPEG_LINK_MODE is dynamic"
+        } else {
+            "FFMPEG_DYNAMIC_LINKING is set"
+        },
+    );
+    let ffmpeg_libs_dir = env_vars.ffmpeg_libs_dir.as_ref().unwrap();
+    record_libs_dir_version(
+        Path::new(ffmpeg_libs_dir),
+        format!("the libraries in FFMPEG_LIBS_DIR {}", ffmpeg_libs_dir),
+    );
+    prebuilt_or_generated_binding(env_vars, output_binding_path)
+}
+
+/// Pass `FFMPEG_EXTRA_LINK_ARGS` to the linker of every artifact and
+/// `FFMPEG_EXTRA_LINK_ARGS_BINS` to the one of binaries only. Both are split
+/// like a shell would and passed verbatim.
+fn emit_extra_link_args(env_vars: &EnvVars) {
+    for (name, value, directive) in [
+        (
+            "FFMPEG_EXTRA_LINK_ARGS",
+            &env_vars.ffmpeg_extra_link_args,
+            "rustc-link-arg",
+        ),
+        (
+            "FFMPEG_EXTRA_LINK_ARGS_BINS",
+            &env_vars.ffmpeg_extra_link_args_bins,
+            "rustc-link-arg-bins",
+        ),
+    ] {
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        let args = shlex::split(value)
+            .unwrap_or_else(|| panic!("{} has unbalanced quotes: {}", name, value));
+        // Shown by `cargo build -vv`.
+        println!("{}: {:?}", name, args);
+        for arg in args {
+            println!("cargo:{}={}", directive, arg);
+        }
+    }
+}
+
+/// With `FFMPEG_NO_LINK`, only provide the binding, without any link
+/// directive: the one of `FFMPEG_BINDING_PATH`, or one generated from
+/// `FFMPEG_INCLUDE_DIR` or from the include dir pkg-config reports (through
+/// `FFMPEG_PKG_CONFIG_PATH`, or in its default search path). Returns the
+/// include dir the binding is generated from, `None` if a prebuilt binding is
+/// used.
+fn no_link_binding(env_vars: &EnvVars) -> Option<String> {
+    println!("cargo:warning=FFMPEG_NO_LINK is set, so FFmpeg isn't linked. Link it yourself (e.g. with `build.rustflags`), otherwise expect undefined symbols.");
+    let output_binding_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
+    if let Some(ffmpeg_binding_path) = env_vars.ffmpeg_binding_path.as_ref() {
+        use_prebuilt_binding(ffmpeg_binding_path, output_binding_path);
+        return None;
+    }
+    let include_dir = match env_vars.ffmpeg_include_dir.clone() {
+        Some(ffmpeg_include_dir) => ffmpeg_include_dir,
+        None => {
+            if let Some(ffmpeg_pkg_config_path) = env_vars.ffmpeg_pkg_config_path.as_ref() {
+                prepend_pkg_config_path(ffmpeg_pkg_config_path);
+            }
+            system_include_dir().unwrap_or_else(|| {
+                panic!("FFMPEG_NO_LINK is set, but there is no binding source: set FFMPEG_BINDING_PATH, FFMPEG_INCLUDE_DIR, or FFMPEG_PKG_CONFIG_PATH to an FFmpeg pkg-config finds.")
+            })
+        }
+    };
+    write_generated_binding(env_vars, &include_dir, output_binding_path);
+    Some(include_dir)
+}
+
+/// The panic message when the binding can be neither generated nor copied.
+const NO_BINDING_SOURCE: &str = "No binding generation method is set! Set FFMPEG_INCLUDE_DIR, FFMPEG_BINDING_PATH, or install the FFmpeg dev headers (e.g. libavcodec-dev) in a system include dir.";
+
+/// The include dir of the FFmpeg headers of the system, for when
+/// `FFMPEG_INCLUDE_DIR` isn't set: the one pkg-config reports (without
+/// linking anything), otherwise the first of
+/// `system_include::system_include_dirs()` with `libavutil/avutil.h`.
+fn system_include_dir() -> Option<String> {
+    if let Some(include_dir) = pkg_config_ffmpeg::include_dir() {
+        return Some(include_dir);
+    }
+    let include_dir = system_include::system_include_dirs(
+        &env::var("TARGET").unwrap(),
+        &env::var("HOST").unwrap(),
+    )
+    .into_iter()
+    .find(|dir| Path::new(dir).join("libavutil/avutil.h").is_file())?;
+    println!(
+        "cargo:warning=FFMPEG_INCLUDE_DIR isn't set, the FFmpeg headers in {} are used.",
+        include_dir
+    );
+    Some(include_dir)
+}
+
+fn docs_rs_linking(env_vars: &EnvVars) {
     // If it's a documentation generation from docs.rs, just copy the bindings
     // generated locally to `OUT_DIR`. We do this because the building
     // environment of docs.rs doesn't have an network connection, so we cannot
@@ -357,17 +4374,142 @@ fn docs_rs_linking(env_vars: &EnvVars) {
     // thing we can do is copying the locally generated binding files to the
     // `OUT_DIR`.
     let binding_file_path = &format!("{}/binding.rs", env_vars.out_dir.as_ref().unwrap());
-    use_prebuilt_binding("src/binding.rs", binding_file_path);
+    use_prebuilt_binding(vendored_binding().1, binding_file_path);
+    // The committed binding is generated without libpostproc.
+    MISSING_LIBS.lock().unwrap().push("postproc".to_string());
 }
 
 fn main() {
-    let env_vars = EnvVars::init();
-    if env_vars.docs_rs.is_some() {
+    let mut env_vars = EnvVars::init();
+    check_mobile_apple_settings(&env_vars);
+    check_emscripten_settings(&env_vars);
+    let vendored = selected_vendored_binding();
+    if cfg!(not(feature = "bindgen")) && env_vars.ffmpeg_binding_path.is_none() {
+        // Offline: without bindgen, the binding of the selected version is used.
+        if let Some((_, path, _)) = vendored {
+            env_vars.ffmpeg_binding_path = Some(path.to_string());
+        }
+    }
+    if env_vars.cargo_feature_prebuilt.is_some() && env_vars.docs_rs.is_none() {
+        fetch_prebuilt(&mut env_vars);
+    }
+    if env_vars.docs_rs.is_none() {
+        detect_homebrew_ffmpeg(&mut env_vars);
+    }
+    apply_link_mode(&mut env_vars);
+    add_bsd_pkg_config_dirs();
+    // Where the dlls have to be found at runtime, when linked dynamically.
+    let mut dll_dir = env_vars
+        .dll_paths()
+        .first()
+        .and_then(|ffmpeg_dll_path| ffmpeg_dll_path.parent().map(Path::to_path_buf));
+    let (link_mode, include_dir) = if env_vars.docs_rs.is_some() {
         docs_rs_linking(&env_vars);
+        ("unknown", None)
+    } else if env_vars.ffmpeg_no_link.is_some() {
+        dll_dir = None;
+        ("none", no_link_binding(&env_vars))
+    } else if env_vars.cargo_feature_build.is_some() {
+        let ffmpeg_pkg_config_path = build_ffmpeg_from_source(&env_vars);
+        (
+            "static",
+            pkg_config_linking(&env_vars, &ffmpeg_pkg_config_path, true),
+        )
+    } else if env_vars.ffmpeg_conan_build_info.is_some() {
+        let (link_mode, include_dir, conan_dll_dir) = conan_linking(&mut env_vars);
+        dll_dir = conan_dll_dir;
+        (link_mode, include_dir)
     } else if env_vars.ffmpeg_dll_path.is_some() {
-        dynamic_linking(&env_vars);
+        ("dynamic", dynamic_linking(&env_vars))
+    } else if env_vars.ffmpeg_pkg_config_path.is_none()
+        && env_vars.ffmpeg_libs_dir.is_none()
+        && system_pkg_config_linking()
+    {
+        // pkg-config prefers the shared libraries of the system.
+        ("dynamic", system_linking(&env_vars))
+    } else if pkg_config_dynamic(&env_vars) {
+        let ffmpeg_pkg_config_path = env_vars.ffmpeg_pkg_config_path.as_ref().unwrap();
+        (
+            "dynamic",
+            pkg_config_linking(&env_vars, ffmpeg_pkg_config_path, false),
+        )
+    } else if cfg!(not(target_os = "windows"))
+        && env_vars.ffmpeg_libs_dir.is_some()
+        && env_vars.ffmpeg_dynamic_linking.is_some()
+    {
+        dll_dir = env_vars.ffmpeg_libs_dir.as_ref().map(PathBuf::from);
+        ("dynamic", libs_dir_dynamic_linking(&env_vars))
+    } else if cfg!(target_os = "windows") && env_vars.ffmpeg_libs_dir.is_some() {
+        let (link_mode, include_dir, libs_dll_dir) = windows_libs_dir_linking(&env_vars);
+        dll_dir = libs_dll_dir;
+        (link_mode, include_dir)
+    } else if cfg!(target_os = "windows") && env_vars.ffmpeg_pkg_config_path.is_none() {
+        let (link_mode, include_dir, vcpkg_dll_dir) = vcpkg_linking(&env_vars);
+        dll_dir = vcpkg_dll_dir;
+        (link_mode, include_dir)
     } else {
         // fallback to static linking
-        static_linking(&env_vars);
+        ("static", static_linking(&env_vars))
+    };
+    match env_vars.ffmpeg_link_mode.as_deref() {
+        Some(requested)
+            if link_mode != requested && link_mode != "unknown" && link_mode != "none" =>
+        {
+            panic!(
+                "FFMPEG_LINK_MODE is {}, but the FFmpeg found only has the {} libraries.",
+                requested, link_mode
+            )
+        }
+        // Shown by `cargo build -vv`.
+        Some(_) => println!("FFmpeg link mode: {}, from FFMPEG_LINK_MODE.", link_mode),
+        None => println!(
+            "FFmpeg link mode: {}, inferred from the settings. Set FFMPEG_LINK_MODE to choose.",
+            link_mode
+        ),
+    }
+    if env_vars.docs_rs.is_none() {
+        check_vendored_binding_version(&env_vars, include_dir.as_deref());
+    }
+    validate_binding(&env_vars, include_dir.is_some());
+    if env_vars.ffmpeg_binding_output_path.is_some() && include_dir.is_none() {
+        println!("cargo:warning=FFMPEG_BINDING_OUTPUT_PATH is ignored since the binding isn't generated.");
+    }
+    if env_vars.ffmpeg_check_binding_drift.is_some() {
+        if include_dir.is_some() {
+            check_binding_drift(&env_vars);
+        } else {
+            println!("cargo:warning=FFMPEG_CHECK_BINDING_DRIFT is ignored since the binding isn't generated.");
+        }
+    }
+    if env_vars.ffmpeg_check_reproducible.is_some() && include_dir.is_none() {
+        println!(
+            "cargo:warning=FFMPEG_CHECK_REPRODUCIBLE is ignored since the binding isn't generated."
+        );
+    }
+    if env_vars.cargo_feature_cstr_constants.is_some() {
+        make_string_constants_cstr(&env_vars);
     }
+    if env_vars.cargo_feature_newtype_enums.is_some() {
+        make_enums_newtype(&env_vars);
+    }
+    if env_vars.cargo_feature_std.is_none() {
+        make_binding_no_std(&env_vars);
+    }
+    add_serde_derives(&env_vars);
+    #[cfg(feature = "check-averror")]
+    if env_vars.docs_rs.is_none() {
+        check_averror_values(&env_vars, include_dir.as_deref());
+    }
+    generate_namespaces(&env_vars);
+    emit_api_cfgs(&env_vars);
+    emit_lib_cfgs();
+    generate_build_meta(
+        &env_vars,
+        link_mode,
+        include_dir.as_deref(),
+        dll_dir.as_deref(),
+    );
+    emit_dependent_metadata(&env_vars, link_mode, include_dir.as_deref());
+    // Last, so they come after the link directives of the crate.
+    emit_extra_link_args(&env_vars);
 }
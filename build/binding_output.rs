@@ -0,0 +1,103 @@
+//! The canonical form of a generated binding, so that generating it twice from
+//! the same headers gives the same bytes.
+
+/// The binding with `\n` newlines, and the paths of `paths` (e.g. the include
+/// roots) in comment lines replaced by their placeholders, in either slash
+/// style and with the backslashes escaped as in doc attributes. Code is left
+/// alone, a path in it is part of the API.
+pub fn canonicalize(binding: &str, paths: &[(&str, &str)]) -> String {
+    let mut canonical = String::with_capacity(binding.len());
+    for line in binding.lines() {
+        let trimmed = line.trim_start();
+        let is_comment = ["//", "/*", "*", "#[doc"]
+            .iter()
+            .any(|start| trimmed.starts_with(start));
+        if is_comment {
+            let mut line = line.to_string();
+            for (path, placeholder) in paths.iter().filter(|(path, _)| !path.is_empty()) {
+                let forward = path.replace('\\', "/");
+                let backward = path.replace('/', "\\");
+                let escaped = backward.replace('\\', "\\\\");
+                for form in [escaped, backward, forward] {
+                    line = line.replace(&form, placeholder);
+                }
+            }
+            canonical.push_str(&line);
+        } else {
+            canonical.push_str(line);
+        }
+        canonical.push('\n');
+    }
+    canonical
+}
+
+/// The first line two bindings differ at: its number (from 1) and its content
+/// in each with the line ending, so that lines differing only by it (`\r\n`
+/// and `\n`) are found, empty past the end of one. `None` if they're the same.
+pub fn first_difference(a: &str, b: &str) -> Option<(usize, String, String)> {
+    if a == b {
+        return None;
+    }
+    let (mut a_lines, mut b_lines) = (a.split_inclusive('\n'), b.split_inclusive('\n'));
+    let mut number = 1;
+    loop {
+        match (a_lines.next(), b_lines.next()) {
+            (Some(a_line), Some(b_line)) if a_line == b_line => number += 1,
+            (a_line, b_line) => {
+                return Some((
+                    number,
+                    a_line.unwrap_or_default().to_string(),
+                    b_line.unwrap_or_default().to_string(),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let binding = "/* automatically generated by rust-bindgen 0.58.1 */\r\n\
+                       #[doc = \" See /opt/ffmpeg/include/libavutil/log.h\"]\r\n\
+                       pub const PATH: &[u8; 17] = b\"/opt/ffmpeg/include\\0\";\r\n";
+        assert_eq!(
+            canonicalize(binding, &[("/opt/ffmpeg/include", "<include>")]),
+            "/* automatically generated by rust-bindgen 0.58.1 */\n\
+             #[doc = \" See <include>/libavutil/log.h\"]\n\
+             pub const PATH: &[u8; 17] = b\"/opt/ffmpeg/include\\0\";\n"
+        );
+        assert_eq!(
+            canonicalize(
+                "#[doc = \" In C:\\\\ffmpeg\\\\include\\\\libavutil\"]\n\
+                 #[doc = \" In C:/ffmpeg/include/libavutil\"]\n",
+                &[("C:\\ffmpeg\\include", "<include>"), ("", "<empty>")]
+            ),
+            "#[doc = \" In <include>\\\\libavutil\"]\n\
+             #[doc = \" In <include>/libavutil\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference("a\nb\n", "a\nb\n"), None);
+        assert_eq!(
+            first_difference("a\nb\nc\n", "a\nx\nc\n"),
+            Some((2, "b\n".to_string(), "x\n".to_string()))
+        );
+        assert_eq!(
+            first_difference("a\n", "a\nb\n"),
+            Some((2, String::new(), "b\n".to_string()))
+        );
+        assert_eq!(
+            first_difference("a\n", "a\r\n"),
+            Some((1, "a\n".to_string(), "a\r\n".to_string()))
+        );
+        assert_eq!(
+            first_difference("a\nb", "a\nb\n"),
+            Some((2, "b".to_string(), "b\n".to_string()))
+        );
+    }
+}
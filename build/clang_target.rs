@@ -0,0 +1,341 @@
+//! The clang args telling bindgen about the target when cross compiling, the
+//! sysroot of the Android NDK and of Emscripten, and the SDK of iOS and tvOS.
+
+/// The LLVM triple clang understands for a Rust target triple. They're mostly
+/// the same, except for the architecture names Rust extends with target
+/// features (`riscv64gc`) and the Apple ones.
+pub fn clang_target(rust_target: &str) -> String {
+    let (arch, rest) = match rust_target.split_once('-') {
+        Some(parts) => parts,
+        None => return rust_target.to_string(),
+    };
+    let arch = if arch.starts_with("riscv64") {
+        "riscv64"
+    } else if arch.starts_with("riscv32") {
+        "riscv32"
+    } else if arch == "aarch64" && rest.starts_with("apple-") {
+        "arm64"
+    } else {
+        arch
+    };
+    let rest = match rest.strip_suffix("-sim") {
+        Some(rest) => format!("{}-simulator", rest),
+        None => rest.to_string(),
+    };
+    format!("{}-{}", arch, rest)
+}
+
+/// `--target` and `--sysroot` for clang when `target` isn't `host`, so the
+/// types get the sizes of the target and `#include <stdint.h>` finds the
+/// headers of the target. Nothing is needed when building for the host.
+pub fn cross_clang_args(target: &str, host: &str, sysroot: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+    if target != host {
+        args.push(format!("--target={}", clang_target(target)));
+        if let Some(sysroot) = sysroot {
+            args.push(format!("--sysroot={}", sysroot));
+        }
+    }
+    args
+}
+
+/// The dir of the prebuilt NDK toolchain for a host triple, e.g.
+/// `linux-x86_64`. Apple silicon uses the x86_64 one too.
+pub fn ndk_host_tag(host: &str) -> &'static str {
+    if host.contains("-apple-") {
+        "darwin-x86_64"
+    } else if host.contains("-windows-") {
+        "windows-x86_64"
+    } else {
+        "linux-x86_64"
+    }
+}
+
+/// The sysroot of the NDK in `ndk_home` (`ANDROID_NDK_HOME`).
+pub fn ndk_sysroot(ndk_home: &str, host: &str) -> String {
+    format!(
+        "{}/toolchains/llvm/prebuilt/{}/sysroot",
+        ndk_home.trim_end_matches(['/', '\\']),
+        ndk_host_tag(host)
+    )
+}
+
+/// The sysroot and the API level of an NDK clang wrapper like
+/// `<ndk>/toolchains/llvm/prebuilt/linux-x86_64/bin/aarch64-linux-android21-clang`,
+/// as `CC_<target>` usually is. `None` for other compilers.
+pub fn ndk_clang_info(cc: &str) -> Option<(Option<String>, String)> {
+    let (dir, file_name) = match cc.rfind(['/', '\\']) {
+        Some(i) => (Some(&cc[..i]), &cc[i + 1..]),
+        None => (None, cc),
+    };
+    let name = file_name.trim_end_matches(".cmd");
+    let name = name
+        .strip_suffix("-clang++")
+        .or_else(|| name.strip_suffix("-clang"))
+        .unwrap_or(name);
+    let triple = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if triple.len() == name.len()
+        || !(triple.ends_with("android") || triple.ends_with("androideabi"))
+    {
+        return None;
+    }
+    let sysroot = dir
+        .and_then(|dir| dir.strip_suffix("bin"))
+        .map(|prebuilt| format!("{}sysroot", prebuilt));
+    Some((sysroot, name[triple.len()..].to_string()))
+}
+
+/// The SDK of an iOS or tvOS target, device or simulator.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AppleSdk {
+    /// The name `xcrun --sdk` takes.
+    pub name: &'static str,
+    /// The clang flag for the minimum OS version.
+    pub version_min_flag: &'static str,
+    /// The env var with the deployment target, as rustc reads it.
+    pub deployment_target_var: &'static str,
+    /// The deployment target rustc defaults to.
+    pub default_deployment_target: &'static str,
+}
+
+/// The SDK of a `*-apple-ios` or `*-apple-tvos` target, `None` for other
+/// targets (Mac Catalyst included, it uses the macOS SDK). The `-sim` ones and
+/// the x86 ones are simulators.
+pub fn apple_sdk(rust_target: &str) -> Option<AppleSdk> {
+    let simulator = rust_target.ends_with("-sim")
+        || rust_target.starts_with("x86_64-")
+        || rust_target.starts_with("i386-");
+    let os = rust_target.trim_end_matches("-sim");
+    let (name, version_min_flag) = if os.ends_with("-apple-ios") {
+        if simulator {
+            ("iphonesimulator", "-mios-simulator-version-min")
+        } else {
+            ("iphoneos", "-miphoneos-version-min")
+        }
+    } else if os.ends_with("-apple-tvos") {
+        if simulator {
+            ("appletvsimulator", "-mtvos-simulator-version-min")
+        } else {
+            ("appletvos", "-mtvos-version-min")
+        }
+    } else {
+        return None;
+    };
+    let deployment_target_var = if os.ends_with("-apple-ios") {
+        "IPHONEOS_DEPLOYMENT_TARGET"
+    } else {
+        "TVOS_DEPLOYMENT_TARGET"
+    };
+    Some(AppleSdk {
+        name,
+        version_min_flag,
+        deployment_target_var,
+        default_deployment_target: "10.0",
+    })
+}
+
+impl AppleSdk {
+    /// The minimum OS version arg for clang, e.g. `-miphoneos-version-min=12.0`.
+    pub fn version_min_arg(&self, deployment_target: Option<&str>) -> String {
+        format!(
+            "{}={}",
+            self.version_min_flag,
+            deployment_target.unwrap_or(self.default_deployment_target)
+        )
+    }
+}
+
+/// The sysroot of an Emscripten SDK: `<EM_CACHE>/sysroot` if the cache is
+/// moved, otherwise the one in the cache of the emscripten dir, which is
+/// `EMSCRIPTEN_ROOT` of the config file (`EM_CONFIG`, given as path and
+/// content) if it's a plain string, or `upstream/emscripten` of the emsdk dir
+/// (`EMSDK`, or the dir emsdk writes its config file to).
+pub fn emscripten_sysroot(
+    em_cache: Option<&str>,
+    em_config: Option<(&str, &str)>,
+    emsdk: Option<&str>,
+) -> Option<String> {
+    let trim = |dir: &str| dir.trim_end_matches(['/', '\\']).to_string();
+    if let Some(em_cache) = em_cache.filter(|dir| !dir.is_empty()) {
+        return Some(format!("{}/sysroot", trim(em_cache)));
+    }
+    let emscripten_root = em_config.and_then(|(_, content)| {
+        content.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            if name.trim() != "EMSCRIPTEN_ROOT" {
+                return None;
+            }
+            let value = value.trim();
+            ['\'', '"']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                .map(str::to_string)
+        })
+    });
+    let emscripten_root = emscripten_root.or_else(|| {
+        let emsdk = emsdk
+            .filter(|dir| !dir.is_empty())
+            .map(str::to_string)
+            .or_else(|| {
+                let (path, _) = em_config?;
+                let i = path.rfind(['/', '\\'])?;
+                Some(path[..i].to_string())
+            })?;
+        Some(format!("{}/upstream/emscripten", trim(&emsdk)))
+    })?;
+    Some(format!("{}/cache/sysroot", trim(&emscripten_root)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clang_target() {
+        assert_eq!(
+            clang_target("aarch64-unknown-linux-gnu"),
+            "aarch64-unknown-linux-gnu"
+        );
+        assert_eq!(
+            clang_target("armv7-linux-androideabi"),
+            "armv7-linux-androideabi"
+        );
+        assert_eq!(
+            clang_target("riscv64gc-unknown-linux-gnu"),
+            "riscv64-unknown-linux-gnu"
+        );
+        assert_eq!(
+            clang_target("riscv32imac-unknown-none-elf"),
+            "riscv32-unknown-none-elf"
+        );
+        assert_eq!(clang_target("aarch64-apple-darwin"), "arm64-apple-darwin");
+        assert_eq!(
+            clang_target("aarch64-apple-ios-sim"),
+            "arm64-apple-ios-simulator"
+        );
+        assert_eq!(
+            clang_target("x86_64-pc-windows-msvc"),
+            "x86_64-pc-windows-msvc"
+        );
+    }
+
+    #[test]
+    fn test_cross_clang_args() {
+        let host = "x86_64-unknown-linux-gnu";
+        assert!(cross_clang_args(host, host, Some("/sysroot")).is_empty());
+        assert_eq!(
+            cross_clang_args("aarch64-unknown-linux-gnu", host, None),
+            ["--target=aarch64-unknown-linux-gnu"]
+        );
+        assert_eq!(
+            cross_clang_args("aarch64-unknown-linux-gnu", host, Some("/sysroot")),
+            ["--target=aarch64-unknown-linux-gnu", "--sysroot=/sysroot"]
+        );
+    }
+
+    #[test]
+    fn test_emscripten_sysroot() {
+        assert_eq!(
+            emscripten_sysroot(Some("/cache/"), None, Some("/emsdk")).as_deref(),
+            Some("/cache/sysroot")
+        );
+        assert_eq!(
+            emscripten_sysroot(
+                None,
+                Some((
+                    "/home/me/.emscripten",
+                    "LLVM_ROOT = '/usr/lib/llvm/bin'\nEMSCRIPTEN_ROOT = '/usr/lib/emscripten'\n"
+                )),
+                Some("/emsdk")
+            )
+            .as_deref(),
+            Some("/usr/lib/emscripten/cache/sysroot")
+        );
+        assert_eq!(
+            emscripten_sysroot(
+                None,
+                Some((
+                    "/opt/emsdk/.emscripten",
+                    "emsdk_path = os.path.dirname(os.getenv('EM_CONFIG'))\n"
+                )),
+                None
+            )
+            .as_deref(),
+            Some("/opt/emsdk/upstream/emscripten/cache/sysroot")
+        );
+        assert_eq!(
+            emscripten_sysroot(None, None, Some("/opt/emsdk/")).as_deref(),
+            Some("/opt/emsdk/upstream/emscripten/cache/sysroot")
+        );
+        assert_eq!(emscripten_sysroot(None, None, None), None);
+    }
+
+    #[test]
+    fn test_ndk_sysroot() {
+        assert_eq!(
+            ndk_sysroot("/opt/ndk/", "x86_64-unknown-linux-gnu"),
+            "/opt/ndk/toolchains/llvm/prebuilt/linux-x86_64/sysroot"
+        );
+        assert_eq!(
+            ndk_sysroot("/opt/ndk", "aarch64-apple-darwin"),
+            "/opt/ndk/toolchains/llvm/prebuilt/darwin-x86_64/sysroot"
+        );
+        assert_eq!(ndk_host_tag("x86_64-pc-windows-msvc"), "windows-x86_64");
+    }
+
+    #[test]
+    fn test_ndk_clang_info() {
+        assert_eq!(
+            ndk_clang_info(
+                "/opt/ndk/toolchains/llvm/prebuilt/linux-x86_64/bin/aarch64-linux-android21-clang"
+            ),
+            Some((
+                Some("/opt/ndk/toolchains/llvm/prebuilt/linux-x86_64/sysroot".to_string()),
+                "21".to_string()
+            ))
+        );
+        assert_eq!(
+            ndk_clang_info("C:\\ndk\\bin\\armv7a-linux-androideabi24-clang.cmd"),
+            Some((Some("C:\\ndk\\sysroot".to_string()), "24".to_string()))
+        );
+        assert_eq!(
+            ndk_clang_info("aarch64-linux-android33-clang++"),
+            Some((None, "33".to_string()))
+        );
+        assert_eq!(ndk_clang_info("clang"), None);
+        assert_eq!(ndk_clang_info("/usr/bin/cc"), None);
+        assert_eq!(ndk_clang_info("/ndk/bin/aarch64-linux-android-clang"), None);
+    }
+
+    #[test]
+    fn test_apple_sdk() {
+        let sdk = apple_sdk("aarch64-apple-ios").unwrap();
+        assert_eq!(sdk.name, "iphoneos");
+        assert_eq!(sdk.deployment_target_var, "IPHONEOS_DEPLOYMENT_TARGET");
+        assert_eq!(sdk.version_min_arg(None), "-miphoneos-version-min=10.0");
+        assert_eq!(
+            sdk.version_min_arg(Some("14.0")),
+            "-miphoneos-version-min=14.0"
+        );
+        let sdk = apple_sdk("aarch64-apple-ios-sim").unwrap();
+        assert_eq!(sdk.name, "iphonesimulator");
+        assert_eq!(
+            sdk.version_min_arg(Some("14.0")),
+            "-mios-simulator-version-min=14.0"
+        );
+        assert_eq!(
+            apple_sdk("x86_64-apple-ios").unwrap().name,
+            "iphonesimulator"
+        );
+        let sdk = apple_sdk("aarch64-apple-tvos").unwrap();
+        assert_eq!(sdk.name, "appletvos");
+        assert_eq!(sdk.deployment_target_var, "TVOS_DEPLOYMENT_TARGET");
+        assert_eq!(
+            apple_sdk("aarch64-apple-tvos-sim").unwrap().name,
+            "appletvsimulator"
+        );
+        assert_eq!(apple_sdk("aarch64-apple-ios-macabi"), None);
+        assert_eq!(apple_sdk("aarch64-apple-darwin"), None);
+        assert_eq!(apple_sdk("aarch64-linux-android"), None);
+    }
+}
@@ -0,0 +1,498 @@
+//! What Conan installed of FFmpeg, read from the `conanbuildinfo.json` of its
+//! `json` generator, or the `*-data.cmake` files of its `CMakeDeps` generator.
+
+use serde_json::Value;
+
+/// The build info of the ffmpeg package and of the other packages in the
+/// file, its transitive dependencies. Lists are in link order, the ones of
+/// ffmpeg first, without duplicates.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The `shared` option of the ffmpeg package, `None` if the file doesn't
+    /// have it.
+    pub shared: Option<bool>,
+    pub include_dirs: Vec<String>,
+    pub lib_dirs: Vec<String>,
+    /// The dirs of the ffmpeg package with its executables, and its dlls on
+    /// Windows.
+    pub bin_dirs: Vec<String>,
+    pub framework_dirs: Vec<String>,
+    /// The libraries of the ffmpeg package.
+    pub ffmpeg_libs: Vec<String>,
+    /// The libraries of the other packages, then the system libraries.
+    pub dependency_libs: Vec<String>,
+    pub frameworks: Vec<String>,
+    pub defines: Vec<String>,
+}
+
+/// The strings of the `field` array of `dependency`, empty if it has none.
+/// `at` locates `dependency` in the errors.
+fn strings(dependency: &Value, field: &str, at: &str) -> Result<Vec<String>, String> {
+    match dependency.get(field) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(elements)) => elements
+            .iter()
+            .map(|element| {
+                element
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("`{}.{}` isn't an array of strings", at, field))
+            })
+            .collect(),
+        Some(_) => Err(format!("`{}.{}` isn't an array of strings", at, field)),
+    }
+}
+
+fn extend_unique(list: &mut Vec<String>, items: Vec<String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
+
+/// Parse the content of a `conanbuildinfo.json`. The errors name the key that
+/// doesn't have the expected layout.
+pub fn parse_build_info(json: &str) -> Result<BuildInfo, String> {
+    let root: Value = serde_json::from_str(json).map_err(|e| format!("invalid JSON: {}", e))?;
+    let dependencies = match root.get("dependencies") {
+        Some(Value::Array(dependencies)) => dependencies,
+        Some(_) => return Err("`dependencies` isn't an array".to_string()),
+        None => return Err("`dependencies` is missing".to_string()),
+    };
+    let mut named = Vec::new();
+    for (i, dependency) in dependencies.iter().enumerate() {
+        match dependency.get("name") {
+            Some(Value::String(name)) => {
+                named.push((name.as_str(), format!("dependencies[{}]", i), dependency))
+            }
+            _ => return Err(format!("`dependencies[{}].name` isn't a string", i)),
+        }
+    }
+    let ffmpeg = named
+        .iter()
+        .position(|(name, _, _)| *name == "ffmpeg")
+        .ok_or_else(|| "`dependencies` has no `ffmpeg` package".to_string())?;
+    let ffmpeg = named.remove(ffmpeg);
+    let ffmpeg_at = ffmpeg.1.clone();
+
+    let mut info = BuildInfo {
+        shared: match root
+            .get("options")
+            .and_then(|options| options.get("ffmpeg"))
+            .and_then(|options| options.get("shared"))
+        {
+            None => None,
+            Some(Value::Bool(shared)) => Some(*shared),
+            Some(Value::String(shared)) if shared == "True" => Some(true),
+            Some(Value::String(shared)) if shared == "False" => Some(false),
+            Some(_) => return Err("`options.ffmpeg.shared` isn't `True` or `False`".to_string()),
+        },
+        ffmpeg_libs: strings(ffmpeg.2, "libs", &ffmpeg.1)?,
+        bin_dirs: strings(ffmpeg.2, "bin_paths", &ffmpeg.1)?,
+        ..BuildInfo::default()
+    };
+    let mut system_libs = Vec::new();
+    for (_, at, dependency) in std::iter::once(ffmpeg).chain(named) {
+        extend_unique(
+            &mut info.include_dirs,
+            strings(dependency, "include_paths", &at)?,
+        );
+        extend_unique(&mut info.lib_dirs, strings(dependency, "lib_paths", &at)?);
+        extend_unique(
+            &mut info.framework_dirs,
+            strings(dependency, "framework_paths", &at)?,
+        );
+        if at != ffmpeg_at {
+            extend_unique(&mut info.dependency_libs, strings(dependency, "libs", &at)?);
+        }
+        extend_unique(&mut system_libs, strings(dependency, "system_libs", &at)?);
+        extend_unique(
+            &mut info.frameworks,
+            strings(dependency, "frameworks", &at)?,
+        );
+        extend_unique(&mut info.defines, strings(dependency, "defines", &at)?);
+    }
+    extend_unique(&mut info.dependency_libs, system_libs);
+    Ok(info)
+}
+
+/// The variables a CMake file sets, with their list elements, in order.
+type CmakeSets = Vec<(String, Vec<String>)>;
+
+/// The `set()` commands of a CMake file, with the `${}` references to the
+/// variables set before expanded, and the values split into list elements.
+/// Other commands are skipped.
+fn cmake_sets(cmake: &str) -> CmakeSets {
+    let mut sets: CmakeSets = Vec::new();
+    let mut rest = cmake;
+    while let Some(start) = rest.find("set(") {
+        let at_command_start = !rest[..start].ends_with(|c: char| !c.is_whitespace());
+        rest = &rest[start + 4..];
+        if !at_command_start {
+            continue;
+        }
+        let mut args = Vec::new();
+        let mut arg: Option<String> = None;
+        let mut chars = rest.char_indices();
+        let mut end = rest.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                ')' => {
+                    end = i + 1;
+                    break;
+                }
+                '"' => {
+                    let quoted = arg.get_or_insert_with(String::new);
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' => quoted.extend(chars.next().map(|(_, c)| c)),
+                            c => quoted.push(c),
+                        }
+                    }
+                }
+                c if c.is_whitespace() => args.extend(arg.take()),
+                c => arg.get_or_insert_with(String::new).push(c),
+            }
+        }
+        args.extend(arg);
+        rest = &rest[end..];
+        let mut args = args.into_iter();
+        let name = match args.next() {
+            Some(name) => name,
+            None => continue,
+        };
+        let values = args
+            .flat_map(|value| {
+                let value = expand_cmake_vars(&value, &sets);
+                value
+                    .split(';')
+                    .filter(|element| !element.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        sets.push((name, values));
+    }
+    sets
+}
+
+/// `value` with the `${NAME}` references to the variables of `sets` replaced
+/// by their value, the last set one. Others are left as they are.
+fn expand_cmake_vars(value: &str, sets: &[(String, Vec<String>)]) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let name = &rest[start + 2..end];
+        match sets.iter().rev().find(|(set, _)| set == name) {
+            Some((_, values)) => expanded.push_str(&values.join(";")),
+            None => expanded.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Parse the `*-data.cmake` files of Conan's `CMakeDeps` generator, given as
+/// their names and contents. The packages are the ones of the configuration
+/// (`_RELEASE`...) of the ffmpeg package, the first in name order if its files
+/// have several, in the name order of their files.
+pub fn parse_cmake_deps(files: &[(String, String)]) -> Result<BuildInfo, String> {
+    let mut files: Vec<(&str, CmakeSets)> = files
+        .iter()
+        .map(|(name, cmake)| (name.as_str(), cmake_sets(cmake)))
+        .collect();
+    files.sort_by_key(|(name, _)| *name);
+    let suffix = files
+        .iter()
+        .flat_map(|(_, sets)| sets)
+        .find_map(|(name, _)| name.strip_prefix("ffmpeg_PACKAGE_FOLDER"))
+        .ok_or_else(|| "no file sets `ffmpeg_PACKAGE_FOLDER_<CONFIG>`".to_string())?
+        .to_string();
+    let folder = format!("_PACKAGE_FOLDER{}", suffix);
+    let mut packages: Vec<(&str, &str, &CmakeSets)> = Vec::new();
+    for (file, sets) in &files {
+        for (name, _) in sets.iter() {
+            if let Some(package) = name.strip_suffix(&folder) {
+                if !packages.iter().any(|(other, _, _)| *other == package) {
+                    packages.push((package, file, sets));
+                }
+            }
+        }
+    }
+    let ffmpeg = packages
+        .iter()
+        .position(|(package, _, _)| *package == "ffmpeg")
+        .unwrap();
+    let ffmpeg = packages.remove(ffmpeg);
+    let get = |(package, _, sets): &(&str, &str, &CmakeSets), field: &str| {
+        let name = format!("{}_{}{}", package, field, suffix);
+        sets.iter()
+            .rev()
+            .find(|(set, _)| *set == name)
+            .map(|(_, values)| values.clone())
+            .unwrap_or_default()
+    };
+
+    let library_type = get(&ffmpeg, "LIBRARY_TYPE");
+    let mut info = BuildInfo {
+        shared: match library_type.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            [] => None,
+            ["SHARED"] => Some(true),
+            ["STATIC"] => Some(false),
+            _ => {
+                return Err(format!(
+                    "`ffmpeg_LIBRARY_TYPE{}` of {} isn't `SHARED` or `STATIC`",
+                    suffix, ffmpeg.1
+                ))
+            }
+        },
+        ffmpeg_libs: get(&ffmpeg, "LIBS"),
+        bin_dirs: get(&ffmpeg, "BIN_DIRS"),
+        ..BuildInfo::default()
+    };
+    let mut system_libs = Vec::new();
+    for (i, package) in std::iter::once(ffmpeg).chain(packages).enumerate() {
+        extend_unique(&mut info.include_dirs, get(&package, "INCLUDE_DIRS"));
+        extend_unique(&mut info.lib_dirs, get(&package, "LIB_DIRS"));
+        extend_unique(&mut info.framework_dirs, get(&package, "FRAMEWORK_DIRS"));
+        if i != 0 {
+            extend_unique(&mut info.dependency_libs, get(&package, "LIBS"));
+        }
+        extend_unique(&mut system_libs, get(&package, "SYSTEM_LIBS"));
+        extend_unique(&mut info.frameworks, get(&package, "FRAMEWORKS"));
+        extend_unique(&mut info.defines, get(&package, "COMPILE_DEFINITIONS"));
+    }
+    extend_unique(&mut info.dependency_libs, system_libs);
+    Ok(info)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BUILD_INFO: &str = r#"{
+ "deps_env_info": {},
+ "dependencies": [
+  {
+   "name": "zlib",
+   "version": "1.3.1",
+   "include_paths": ["/conan/zlib/include"],
+   "lib_paths": ["/conan/zlib/lib"],
+   "libs": ["z"],
+   "system_libs": [],
+   "defines": []
+  },
+  {
+   "name": "ffmpeg",
+   "version": "7.0.1",
+   "include_paths": ["/conan/ffmpeg/include"],
+   "lib_paths": ["/conan/ffmpeg/lib"],
+   "bin_paths": ["/conan/ffmpeg/bin"],
+   "libs": ["avformat", "avcodec", "avutil"],
+   "system_libs": ["pthread", "m", "dl"],
+   "frameworks": ["VideoToolbox"],
+   "defines": ["FFMPEG_CONAN=1"],
+   "description": "A \"complete\" solution \u00e0 record\/convert",
+   "cppflags": null
+  },
+  {
+   "name": "openssl",
+   "include_paths": ["/conan/openssl/include"],
+   "lib_paths": ["/conan/openssl/lib", "/conan/zlib/lib"],
+   "libs": ["ssl", "crypto"],
+   "system_libs": ["dl", "rt"]
+  }
+ ],
+ "options": {"ffmpeg": {"shared": "False", "fPIC": "True"}, "zlib": {"shared": "True"}},
+ "settings": {"build_type": "Release", "cppstd": 17}
+}"#;
+
+    #[test]
+    fn test_parse_build_info() {
+        assert_eq!(
+            parse_build_info(BUILD_INFO),
+            Ok(BuildInfo {
+                shared: Some(false),
+                include_dirs: vec![
+                    "/conan/ffmpeg/include".to_string(),
+                    "/conan/zlib/include".to_string(),
+                    "/conan/openssl/include".to_string(),
+                ],
+                lib_dirs: vec![
+                    "/conan/ffmpeg/lib".to_string(),
+                    "/conan/zlib/lib".to_string(),
+                    "/conan/openssl/lib".to_string(),
+                ],
+                bin_dirs: vec!["/conan/ffmpeg/bin".to_string()],
+                framework_dirs: Vec::new(),
+                ffmpeg_libs: vec![
+                    "avformat".to_string(),
+                    "avcodec".to_string(),
+                    "avutil".to_string(),
+                ],
+                dependency_libs: vec![
+                    "z".to_string(),
+                    "ssl".to_string(),
+                    "crypto".to_string(),
+                    "pthread".to_string(),
+                    "m".to_string(),
+                    "dl".to_string(),
+                    "rt".to_string(),
+                ],
+                frameworks: vec!["VideoToolbox".to_string()],
+                defines: vec!["FFMPEG_CONAN=1".to_string()],
+            })
+        );
+        let shared = BUILD_INFO.replace("\"shared\": \"False\"", "\"shared\": true");
+        assert_eq!(parse_build_info(&shared).unwrap().shared, Some(true));
+    }
+
+    #[test]
+    fn test_cmake_sets() {
+        assert_eq!(
+            cmake_sets(
+                "set(a_FOLDER \"/conan/a b\")\n\
+                 if(NOT a_INCLUDE_DIRS)\n    \
+                     set(a_INCLUDE_DIRS \"${a_FOLDER}/include\" \"${a_FOLDER}/x;${b}\")\n\
+                 endif()\n\
+                 list(APPEND a_LIBS z)\n\
+                 set(a_LIBS avformat\n    avutil)\n\
+                 set(a_EMPTY \"\")\n\
+                 set(a_FLAGS \"$<$<CONFIG:Release>:-O2>\" \"a\\\"b\")\n"
+            ),
+            vec![
+                ("a_FOLDER".to_string(), vec!["/conan/a b".to_string()]),
+                (
+                    "a_INCLUDE_DIRS".to_string(),
+                    vec![
+                        "/conan/a b/include".to_string(),
+                        "/conan/a b/x".to_string(),
+                        "${b}".to_string(),
+                    ]
+                ),
+                (
+                    "a_LIBS".to_string(),
+                    vec!["avformat".to_string(), "avutil".to_string()]
+                ),
+                ("a_EMPTY".to_string(), Vec::new()),
+                (
+                    "a_FLAGS".to_string(),
+                    vec!["$<$<CONFIG:Release>:-O2>".to_string(), "a\"b".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_cmake_deps() {
+        let files = vec![
+            (
+                "ffmpeg-release-x86_64-data.cmake".to_string(),
+                r#"set(ffmpeg_FIND_DEPENDENCY_NAMES ZLIB)
+set(ffmpeg_PACKAGE_FOLDER_RELEASE "/conan/ffmpeg")
+set(ffmpeg_INCLUDE_DIRS_RELEASE "${ffmpeg_PACKAGE_FOLDER_RELEASE}/include")
+set(ffmpeg_LIB_DIRS_RELEASE "${ffmpeg_PACKAGE_FOLDER_RELEASE}/lib")
+set(ffmpeg_BIN_DIRS_RELEASE "${ffmpeg_PACKAGE_FOLDER_RELEASE}/bin")
+set(ffmpeg_LIBRARY_TYPE_RELEASE SHARED)
+set(ffmpeg_COMPILE_DEFINITIONS_RELEASE "FFMPEG_CONAN=1")
+set(ffmpeg_LIBS_RELEASE avformat avcodec avutil)
+set(ffmpeg_SYSTEM_LIBS_RELEASE pthread m)
+set(ffmpeg_FRAMEWORKS_RELEASE )
+set(ffmpeg_ffmpeg_avutil_LIBS_RELEASE avutil)
+"#
+                .to_string(),
+            ),
+            (
+                "ZLIB-release-x86_64-data.cmake".to_string(),
+                r#"set(zlib_PACKAGE_FOLDER_RELEASE "/conan/zlib")
+set(zlib_INCLUDE_DIRS_RELEASE "${zlib_PACKAGE_FOLDER_RELEASE}/include")
+set(zlib_LIB_DIRS_RELEASE "${zlib_PACKAGE_FOLDER_RELEASE}/lib")
+set(zlib_LIBRARY_TYPE_RELEASE STATIC)
+set(zlib_LIBS_RELEASE z)
+set(zlib_SYSTEM_LIBS_RELEASE m)
+"#
+                .to_string(),
+            ),
+            (
+                "ZLIB-debug-x86_64-data.cmake".to_string(),
+                "set(zlib_PACKAGE_FOLDER_DEBUG \"/conan/zlib-debug\")\n\
+                 set(zlib_LIBS_DEBUG zd)\n"
+                    .to_string(),
+            ),
+        ];
+        assert_eq!(
+            parse_cmake_deps(&files),
+            Ok(BuildInfo {
+                shared: Some(true),
+                include_dirs: vec![
+                    "/conan/ffmpeg/include".to_string(),
+                    "/conan/zlib/include".to_string(),
+                ],
+                lib_dirs: vec![
+                    "/conan/ffmpeg/lib".to_string(),
+                    "/conan/zlib/lib".to_string()
+                ],
+                bin_dirs: vec!["/conan/ffmpeg/bin".to_string()],
+                framework_dirs: Vec::new(),
+                ffmpeg_libs: vec![
+                    "avformat".to_string(),
+                    "avcodec".to_string(),
+                    "avutil".to_string(),
+                ],
+                dependency_libs: vec!["z".to_string(), "pthread".to_string(), "m".to_string()],
+                frameworks: Vec::new(),
+                defines: vec!["FFMPEG_CONAN=1".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_cmake_deps(&files[1..]),
+            Err("no file sets `ffmpeg_PACKAGE_FOLDER_<CONFIG>`".to_string())
+        );
+        let mut files = files;
+        files[0].1 = files[0].1.replace("SHARED", "UNKNOWN");
+        assert_eq!(
+            parse_cmake_deps(&files),
+            Err("`ffmpeg_LIBRARY_TYPE_RELEASE` of ffmpeg-release-x86_64-data.cmake isn't `SHARED` or `STATIC`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_build_info_errors() {
+        assert_eq!(
+            parse_build_info("{\"dependencies\": [1,]}"),
+            Err("invalid JSON: trailing comma at line 1 column 21".to_string())
+        );
+        assert_eq!(
+            parse_build_info("{\"deps_env_info\": {}}"),
+            Err("`dependencies` is missing".to_string())
+        );
+        assert_eq!(
+            parse_build_info("{\"dependencies\": {}}"),
+            Err("`dependencies` isn't an array".to_string())
+        );
+        assert_eq!(
+            parse_build_info("{\"dependencies\": [{\"name\": \"zlib\"}]}"),
+            Err("`dependencies` has no `ffmpeg` package".to_string())
+        );
+        assert_eq!(
+            parse_build_info("{\"dependencies\": [{\"version\": \"7.0\"}]}"),
+            Err("`dependencies[0].name` isn't a string".to_string())
+        );
+        assert_eq!(
+            parse_build_info(&BUILD_INFO.replace("\"libs\": [\"z\"]", "\"libs\": \"z\"")),
+            Err("`dependencies[0].libs` isn't an array of strings".to_string())
+        );
+        assert_eq!(
+            parse_build_info(&BUILD_INFO.replace("\"False\"", "\"no\"")),
+            Err("`options.ffmpeg.shared` isn't `True` or `False`".to_string())
+        );
+    }
+}
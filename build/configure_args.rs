@@ -0,0 +1,156 @@
+//! The `./configure` arguments of the `build` feature.
+
+/// The libraries `./configure` can disable, avutil can't be.
+const OPTIONAL_LIBS: [&str; 7] = [
+    "avcodec",
+    "avdevice",
+    "avfilter",
+    "avformat",
+    "swresample",
+    "swscale",
+    "postproc",
+];
+
+/// Arguments building static libraries of the `enabled_libs` into `prefix`,
+/// without the programs and the docs.
+pub fn configure_args(prefix: &str, enabled_libs: &[&str], gpl: bool) -> Vec<String> {
+    let mut args = vec![
+        format!("--prefix={}", prefix),
+        "--enable-static".to_string(),
+        "--disable-shared".to_string(),
+        "--enable-pic".to_string(),
+        "--disable-programs".to_string(),
+        "--disable-doc".to_string(),
+    ];
+    args.extend(
+        OPTIONAL_LIBS
+            .iter()
+            .filter(|libname| !enabled_libs.contains(libname))
+            .map(|libname| format!("--disable-{}", libname)),
+    );
+    if gpl {
+        args.push("--enable-gpl".to_string());
+    }
+    args
+}
+
+/// The `--cross-prefix` of the usual cross toolchain of a Rust target, e.g.
+/// `aarch64-linux-gnu-` for `aarch64-unknown-linux-gnu` or
+/// `x86_64-w64-mingw32-` for `x86_64-pc-windows-gnu`.
+pub fn cross_prefix(target: &str) -> String {
+    let mut parts: Vec<&str> = target.split('-').collect();
+    if parts.len() == 4 && (parts[1] == "unknown" || parts[1] == "pc") {
+        parts.remove(1);
+    }
+    if parts[0].starts_with("armv7") {
+        parts[0] = "arm";
+    }
+    if parts.ends_with(&["windows", "gnu"]) {
+        return format!("{}-w64-mingw32-", parts[0]);
+    }
+    format!("{}-", parts.join("-"))
+}
+
+/// The `--target-os` of a Rust `target_os`.
+fn target_os(target_os: &str) -> &str {
+    match target_os {
+        "macos" | "ios" | "tvos" => "darwin",
+        "windows" => "mingw32",
+        os => os,
+    }
+}
+
+/// Arguments cross compiling for a Rust target, with the `target_arch` and
+/// `target_os` of its cfgs.
+pub fn cross_args(
+    target_arch: &str,
+    target_os_name: &str,
+    cross_prefix: &str,
+    sysroot: Option<&str>,
+) -> Vec<String> {
+    let mut args = vec![
+        "--enable-cross-compile".to_string(),
+        format!("--arch={}", target_arch),
+        format!("--target-os={}", target_os(target_os_name)),
+        format!("--cross-prefix={}", cross_prefix),
+    ];
+    if let Some(sysroot) = sysroot {
+        args.push(format!("--sysroot={}", sysroot));
+    }
+    args
+}
+
+/// The last `lines` lines of `config.log`, which explain why configure failed.
+pub fn log_tail(log: &str, lines: usize) -> String {
+    let all: Vec<&str> = log.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_configure_args() {
+        let args = configure_args("/out/ffmpeg", &["avcodec", "avformat", "avutil"], false);
+        assert_eq!(args[0], "--prefix=/out/ffmpeg");
+        assert!(args.contains(&"--enable-static".to_string()));
+        assert!(args.contains(&"--disable-programs".to_string()));
+        assert!(args.contains(&"--disable-avdevice".to_string()));
+        assert!(args.contains(&"--disable-postproc".to_string()));
+        assert!(!args.contains(&"--disable-avcodec".to_string()));
+        assert!(!args.iter().any(|arg| arg == "--disable-avutil"));
+        assert!(!args.contains(&"--enable-gpl".to_string()));
+
+        let args = configure_args("/out/ffmpeg", &["avutil"], true);
+        assert!(args.contains(&"--disable-avcodec".to_string()));
+        assert!(args.contains(&"--enable-gpl".to_string()));
+    }
+
+    #[test]
+    fn test_cross_prefix() {
+        assert_eq!(
+            cross_prefix("aarch64-unknown-linux-gnu"),
+            "aarch64-linux-gnu-"
+        );
+        assert_eq!(
+            cross_prefix("armv7-unknown-linux-gnueabihf"),
+            "arm-linux-gnueabihf-"
+        );
+        assert_eq!(
+            cross_prefix("aarch64-linux-android"),
+            "aarch64-linux-android-"
+        );
+        assert_eq!(cross_prefix("x86_64-pc-windows-gnu"), "x86_64-w64-mingw32-");
+        assert_eq!(cross_prefix("i686-pc-windows-gnu"), "i686-w64-mingw32-");
+    }
+
+    #[test]
+    fn test_cross_args() {
+        assert_eq!(
+            cross_args("aarch64", "linux", "aarch64-linux-gnu-", Some("/sysroot")),
+            [
+                "--enable-cross-compile",
+                "--arch=aarch64",
+                "--target-os=linux",
+                "--cross-prefix=aarch64-linux-gnu-",
+                "--sysroot=/sysroot",
+            ]
+        );
+        assert_eq!(
+            cross_args("x86_64", "windows", "x86_64-w64-mingw32-", None)[2],
+            "--target-os=mingw32"
+        );
+        assert_eq!(
+            cross_args("aarch64", "macos", "", None)[2],
+            "--target-os=darwin"
+        );
+    }
+
+    #[test]
+    fn test_log_tail() {
+        assert_eq!(log_tail("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(log_tail("a\n", 5), "a");
+        assert_eq!(log_tail("", 5), "");
+    }
+}
@@ -0,0 +1,368 @@
+//! Name derivation and list splitting for `FFMPEG_DLL_PATH` and
+//! `FFMPEG_LIBS_DIR`.
+
+/// Strip trailing `.<number>` components, e.g. `libavcodec.60.31` -> `libavcodec`.
+fn trim_version(name: &str) -> &str {
+    let mut name = name;
+    while let Some((rest, component)) = name.rsplit_once('.') {
+        if component.is_empty() || !component.chars().all(|c| c.is_ascii_digit()) {
+            break;
+        }
+        name = rest;
+    }
+    name
+}
+
+/// Derive the library name to link from the file name of a shared library,
+/// stripping the extension and version components: ELF versions come after
+/// the extension (`libavcodec.so.60.31.102`) while dylib versions come before
+/// it (`libavcodec.60.dylib`). The version of a dll is part of its name
+/// (`avcodec-60.dll` -> `avcodec-60`) and is left to the import library
+/// lookup.
+pub fn dylib_link_name(file_name: &str) -> String {
+    if let Some(name) = file_name.strip_suffix(".dll") {
+        return name.to_string();
+    }
+    let name = if let Some(name) = file_name.strip_suffix(".dylib") {
+        trim_version(name)
+    } else {
+        let name = trim_version(file_name);
+        name.strip_suffix(".so").unwrap_or(name)
+    };
+    name.strip_prefix("lib").unwrap_or(name).to_string()
+}
+
+/// Split the entries of an `FFMPEG_DLL_PATH` list. They're separated by `;`,
+/// or also by `:` when not on Windows, where it's part of drive paths.
+pub fn split_dll_paths(value: &str, windows: bool) -> Vec<&str> {
+    value
+        .split(|c| c == ';' || (!windows && c == ':'))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Whether a file is a shared library of `libname`, e.g. `libavcodec.so.60`,
+/// `libavcodec.60.dylib`, or `avcodec-60.dll` and `libavcodec.dll` (of some
+/// MinGW builds) for `avcodec`.
+pub fn is_dylib_of(file_name: &str, libname: &str) -> bool {
+    if let Some(name) = file_name.strip_suffix(".dll") {
+        return is_name_of(name.strip_prefix("lib").unwrap_or(name), libname);
+    }
+    let is_dylib =
+        file_name.ends_with(".dylib") || file_name.ends_with(".so") || file_name.contains(".so.");
+    is_dylib && is_name_of(&dylib_link_name(file_name), libname)
+}
+
+/// The version components in the name of a shared library of `libname`, e.g.
+/// `[60, 31, 102]` for `libavcodec.so.60.31.102`, `[60]` for
+/// `libavcodec.60.dylib` or `avcodec-60.dll`, and none for `libavcodec.so`.
+/// `None` if it isn't one of `libname`.
+pub fn dylib_version(file_name: &str, libname: &str) -> Option<Vec<u32>> {
+    if !is_dylib_of(file_name, libname) {
+        return None;
+    }
+    let name = file_name.strip_prefix("lib").unwrap_or(file_name);
+    let rest = name.strip_prefix(libname).unwrap_or(name);
+    Some(
+        rest.split(|c: char| !c.is_ascii_digit())
+            .filter_map(|component| component.parse().ok())
+            .collect(),
+    )
+}
+
+/// The majors in the names of the versioned shared libraries of `libname`
+/// among `file_names` (`libavcodec.so.60`, `libavcodec.60.dylib`,
+/// `avcodec-60.dll`), sorted and without duplicates.
+pub fn dylib_majors(file_names: &[&str], libname: &str) -> Vec<u32> {
+    let mut majors: Vec<u32> = file_names
+        .iter()
+        .filter_map(|file_name| dylib_version(file_name, libname)?.first().copied())
+        .collect();
+    majors.sort_unstable();
+    majors.dedup();
+    majors
+}
+
+/// The shared library of `libname` to link among `file_names`: the
+/// unversioned one (the symlink of the dev packages) if it's there and
+/// `versioned` isn't set, otherwise a versioned one, of the `major` version if
+/// given, else of the highest, with the shortest name (the SONAME
+/// `libavcodec.so.60` rather than `libavcodec.so.60.31.102`). Versioned ones
+/// of another major aren't picked, their ABI differs.
+pub fn pick_dylib<'a>(
+    file_names: &[&'a str],
+    libname: &str,
+    major: Option<u32>,
+    versioned: bool,
+) -> Option<&'a str> {
+    let candidates: Vec<(&str, Vec<u32>)> = file_names
+        .iter()
+        .filter_map(|file_name| Some((*file_name, dylib_version(file_name, libname)?)))
+        .collect();
+    let unversioned = candidates
+        .iter()
+        .find(|(_, version)| version.is_empty())
+        .map(|(file_name, _)| *file_name);
+    if !versioned && unversioned.is_some() {
+        return unversioned;
+    }
+    candidates
+        .iter()
+        .filter(|(_, version)| match major {
+            Some(major) => version.first() == Some(&major),
+            None => !version.is_empty(),
+        })
+        .max_by_key(|(file_name, version)| (version[0], std::cmp::Reverse(file_name.len())))
+        .map(|(file_name, _)| *file_name)
+        .or(unversioned)
+}
+
+/// Whether `name` is `libname`, optionally with the `-<major>` version of
+/// Windows builds.
+fn is_name_of(name: &str, libname: &str) -> bool {
+    name == libname
+        || name
+            .strip_prefix(libname)
+            .and_then(|version| version.strip_prefix('-'))
+            .is_some_and(|version| {
+                !version.is_empty() && version.chars().all(|c| c.is_ascii_digit())
+            })
+}
+
+/// The import libraries GNU ld could link a dll named `dll_name` (as
+/// `dylib_link_name()` returns it) through, in the order they're looked for:
+/// MinGW's `.dll.a`, then MSVC's `.lib`, versioned and unversioned.
+pub fn gnu_import_lib_names(dll_name: &str) -> Vec<String> {
+    let name = dll_name.strip_prefix("lib").unwrap_or(dll_name);
+    // `avcodec-60` -> `avcodec`
+    let unversioned = match name.rsplit_once('-') {
+        Some((unversioned, version))
+            if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            unversioned
+        }
+        _ => name,
+    };
+    let mut import_libs = Vec::new();
+    for import_lib in [
+        format!("lib{}.dll.a", name),
+        format!("lib{}.dll.a", unversioned),
+        format!("{}.lib", name),
+        format!("{}.lib", unversioned),
+    ] {
+        if !import_libs.contains(&import_lib) {
+            import_libs.push(import_lib);
+        }
+    }
+    import_libs
+}
+
+/// The name to link a library of `libname` in a Windows `FFMPEG_LIBS_DIR` by,
+/// if `file_name` is one: `avcodec.lib` or `avcodec-60.lib` of MSVC builds,
+/// and for `gnu` also `libavcodec.dll.a` or `libavcodec.a` of MinGW builds.
+pub fn windows_lib_link_name(file_name: &str, libname: &str, gnu: bool) -> Option<String> {
+    if let Some(name) = file_name.strip_suffix(".lib") {
+        return Some(name.to_string()).filter(|name| is_name_of(name, libname));
+    }
+    if !gnu {
+        return None;
+    }
+    file_name
+        .strip_suffix(".dll.a")
+        .or_else(|| file_name.strip_suffix(".a"))
+        .and_then(|name| name.strip_prefix("lib"))
+        .filter(|name| *name == libname)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dylib_link_name() {
+        assert_eq!(dylib_link_name("libavcodec.so"), "avcodec");
+        assert_eq!(dylib_link_name("libavcodec.so.60"), "avcodec");
+        assert_eq!(dylib_link_name("libavcodec.so.60.31.102"), "avcodec");
+        assert_eq!(dylib_link_name("libavcodec.60.dylib"), "avcodec");
+        assert_eq!(dylib_link_name("libavcodec.dylib"), "avcodec");
+        assert_eq!(dylib_link_name("avcodec-60.dll"), "avcodec-60");
+        assert_eq!(dylib_link_name("avcodec.dll"), "avcodec");
+    }
+
+    #[test]
+    fn test_split_dll_paths() {
+        assert_eq!(
+            split_dll_paths("/a/libavcodec.so:/a/libavutil.so;", false),
+            ["/a/libavcodec.so", "/a/libavutil.so"]
+        );
+        assert_eq!(
+            split_dll_paths(
+                "C:\\ffmpeg\\avcodec-60.dll; C:\\ffmpeg\\avutil-58.dll",
+                true
+            ),
+            ["C:\\ffmpeg\\avcodec-60.dll", "C:\\ffmpeg\\avutil-58.dll"]
+        );
+        assert_eq!(
+            split_dll_paths("C:\\ffmpeg\\bin", true),
+            ["C:\\ffmpeg\\bin"]
+        );
+    }
+
+    #[test]
+    fn test_dylib_version() {
+        assert_eq!(
+            dylib_version("libavcodec.so.60.31.102", "avcodec"),
+            Some(vec![60, 31, 102])
+        );
+        assert_eq!(
+            dylib_version("libavcodec.60.dylib", "avcodec"),
+            Some(vec![60])
+        );
+        assert_eq!(dylib_version("avcodec-60.dll", "avcodec"), Some(vec![60]));
+        assert_eq!(dylib_version("libavcodec.so", "avcodec"), Some(vec![]));
+        assert_eq!(dylib_version("libavcodec.a", "avcodec"), None);
+        assert_eq!(dylib_version("libavutil.so.58", "avcodec"), None);
+    }
+
+    #[test]
+    fn test_dylib_majors() {
+        assert_eq!(
+            dylib_majors(
+                &[
+                    "libavcodec.so",
+                    "libavcodec.so.60",
+                    "libavcodec.so.60.31.102",
+                    "libavcodec.so.58",
+                    "libavcodec.a",
+                    "libavutil.so.58"
+                ],
+                "avcodec"
+            ),
+            [58, 60]
+        );
+        assert_eq!(
+            dylib_majors(&["avcodec-61.dll", "avcodec.lib"], "avcodec"),
+            [61]
+        );
+        assert!(dylib_majors(&["libavcodec.a", "libavcodec.so"], "avcodec").is_empty());
+    }
+
+    #[test]
+    fn test_pick_dylib() {
+        let runtime = [
+            "libavcodec.so.58",
+            "libavcodec.so.58.134.100",
+            "libavcodec.so.60",
+            "libavcodec.so.60.31.102",
+            "libavutil.so.58",
+        ];
+        assert_eq!(
+            pick_dylib(&runtime, "avcodec", None, false),
+            Some("libavcodec.so.60")
+        );
+        assert_eq!(
+            pick_dylib(&runtime, "avcodec", Some(58), false),
+            Some("libavcodec.so.58")
+        );
+        assert_eq!(pick_dylib(&runtime, "avcodec", Some(59), false), None);
+        assert_eq!(
+            pick_dylib(&["libavcodec.so.60.31.102"], "avcodec", None, false),
+            Some("libavcodec.so.60.31.102")
+        );
+        let dev = [
+            "libavcodec.so",
+            "libavcodec.so.60",
+            "libavcodec.so.60.31.102",
+        ];
+        assert_eq!(
+            pick_dylib(&dev, "avcodec", None, false),
+            Some("libavcodec.so")
+        );
+        assert_eq!(
+            pick_dylib(&dev, "avcodec", None, true),
+            Some("libavcodec.so.60")
+        );
+        assert_eq!(
+            pick_dylib(&["libavcodec.so"], "avcodec", None, true),
+            Some("libavcodec.so")
+        );
+        assert_eq!(pick_dylib(&runtime, "swscale", None, false), None);
+    }
+
+    #[test]
+    fn test_is_dylib_of() {
+        assert!(is_dylib_of("libavcodec.so", "avcodec"));
+        assert!(is_dylib_of("libavcodec.so.60.31.102", "avcodec"));
+        assert!(is_dylib_of("libavcodec.60.dylib", "avcodec"));
+        assert!(is_dylib_of("avcodec-60.dll", "avcodec"));
+        assert!(is_dylib_of("avcodec.dll", "avcodec"));
+        assert!(is_dylib_of("libavcodec.dll", "avcodec"));
+        assert!(is_dylib_of("libavcodec-60.dll", "avcodec"));
+        assert!(!is_dylib_of("libavcodec.dll.a", "avcodec"));
+        assert!(!is_dylib_of("libavcodec.a", "avcodec"));
+        assert!(!is_dylib_of("avcodec-60.lib", "avcodec"));
+        assert!(!is_dylib_of("avcodec-60.def", "avcodec"));
+        assert!(!is_dylib_of("libavcodec.so", "avutil"));
+        assert!(!is_dylib_of("libavcodec-extra.so", "avcodec"));
+    }
+
+    #[test]
+    fn test_gnu_import_lib_names() {
+        assert_eq!(
+            gnu_import_lib_names("avcodec-60"),
+            [
+                "libavcodec-60.dll.a",
+                "libavcodec.dll.a",
+                "avcodec-60.lib",
+                "avcodec.lib"
+            ]
+        );
+        assert_eq!(
+            gnu_import_lib_names("libavcodec"),
+            ["libavcodec.dll.a", "avcodec.lib"]
+        );
+    }
+
+    #[test]
+    fn test_windows_lib_link_name() {
+        assert_eq!(
+            windows_lib_link_name("avcodec.lib", "avcodec", false).as_deref(),
+            Some("avcodec")
+        );
+        assert_eq!(
+            windows_lib_link_name("avcodec-60.lib", "avcodec", false).as_deref(),
+            Some("avcodec-60")
+        );
+        assert_eq!(
+            windows_lib_link_name("libavcodec.dll.a", "avcodec", false),
+            None
+        );
+        assert_eq!(
+            windows_lib_link_name("libavcodec.dll.a", "avcodec", true).as_deref(),
+            Some("avcodec")
+        );
+        assert_eq!(
+            windows_lib_link_name("libavcodec.a", "avcodec", true).as_deref(),
+            Some("avcodec")
+        );
+        assert_eq!(
+            windows_lib_link_name("avcodec.lib", "avcodec", true).as_deref(),
+            Some("avcodec")
+        );
+        assert_eq!(
+            windows_lib_link_name("avdevice.lib", "avcodec", false),
+            None
+        );
+        assert_eq!(
+            windows_lib_link_name("avcodec-x.lib", "avcodec", false),
+            None
+        );
+        assert_eq!(
+            windows_lib_link_name("libavcodecx.a", "avcodec", true),
+            None
+        );
+        assert_eq!(windows_lib_link_name("avcodec.dll", "avcodec", true), None);
+    }
+}
@@ -0,0 +1,105 @@
+//! The FFmpeg release of a libavutil version, for the minimum version check.
+
+/// FFmpeg releases, with the libavutil version they shipped.
+const RELEASES: [(&str, (u32, u32)); 17] = [
+    ("3.0", (55, 17)),
+    ("3.1", (55, 27)),
+    ("3.2", (55, 34)),
+    ("3.3", (55, 58)),
+    ("3.4", (55, 78)),
+    ("4.0", (56, 14)),
+    ("4.1", (56, 22)),
+    ("4.2", (56, 31)),
+    ("4.3", (56, 51)),
+    ("4.4", (56, 70)),
+    ("5.0", (57, 17)),
+    ("5.1", (57, 28)),
+    ("6.0", (58, 2)),
+    ("6.1", (58, 29)),
+    ("7.0", (59, 8)),
+    ("7.1", (59, 39)),
+    ("8.0", (60, 8)),
+];
+
+/// The oldest FFmpeg release the binding supports.
+pub const MIN_RELEASE: &str = "4.3";
+
+/// The oldest FFmpeg release with an `ffmpeg_X_Y` cfg.
+const FIRST_CFG_RELEASE: &str = "4.4";
+
+/// The `ffmpeg_X_Y` cfgs of the releases from `FIRST_CFG_RELEASE` on, with
+/// the libavutil version they shipped.
+pub fn api_cfgs() -> Vec<(String, (u32, u32))> {
+    RELEASES
+        .iter()
+        .skip_while(|(release, _)| *release != FIRST_CFG_RELEASE)
+        .map(|(release, since)| (format!("ffmpeg_{}", release.replace('.', "_")), *since))
+        .collect()
+}
+
+/// The newest release a libavutil `(major, minor)` is at least, `None` if
+/// older than all of them.
+pub fn release(avutil_version: (u32, u32)) -> Option<&'static str> {
+    RELEASES
+        .iter()
+        .take_while(|(_, since)| *since <= avutil_version)
+        .last()
+        .map(|(release, _)| *release)
+}
+
+/// Whether a libavutil `(major, minor)` is at least `MIN_RELEASE`.
+pub fn is_supported(avutil_version: (u32, u32)) -> bool {
+    RELEASES
+        .iter()
+        .find(|(release, _)| *release == MIN_RELEASE)
+        .is_some_and(|(_, since)| avutil_version >= *since)
+}
+
+/// `FFmpeg X.Y (libavutil M.m)`, or only the libavutil version if it's older
+/// than every known release.
+pub fn describe(avutil_version: (u32, u32)) -> String {
+    match release(avutil_version) {
+        Some(release) => format!(
+            "FFmpeg {} (libavutil {}.{})",
+            release, avutil_version.0, avutil_version.1
+        ),
+        None => format!("libavutil {}.{}", avutil_version.0, avutil_version.1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_release() {
+        assert_eq!(release((54, 31)), None);
+        assert_eq!(release((55, 78)), Some("3.4"));
+        assert_eq!(release((56, 51)), Some("4.3"));
+        assert_eq!(release((56, 69)), Some("4.3"));
+        assert_eq!(release((59, 39)), Some("7.1"));
+        assert_eq!(release((61, 0)), Some("8.0"));
+    }
+
+    #[test]
+    fn test_is_supported() {
+        assert!(!is_supported((55, 78)));
+        assert!(!is_supported((56, 50)));
+        assert!(is_supported((56, 51)));
+        assert!(is_supported((59, 39)));
+    }
+
+    #[test]
+    fn test_api_cfgs() {
+        let cfgs = api_cfgs();
+        assert_eq!(cfgs[0], ("ffmpeg_4_4".to_string(), (56, 70)));
+        assert_eq!(cfgs[6], ("ffmpeg_7_1".to_string(), (59, 39)));
+        assert_eq!(cfgs.len(), RELEASES.len() - 9);
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe((55, 78)), "FFmpeg 3.4 (libavutil 55.78)");
+        assert_eq!(describe((54, 31)), "libavutil 54.31");
+    }
+}
@@ -0,0 +1,91 @@
+//! Where Homebrew installs FFmpeg on macOS.
+
+/// The default Homebrew prefix of a target arch, `/opt/homebrew` on Apple
+/// silicon and `/usr/local` on Intel.
+pub fn default_prefix(target_arch: &str) -> &'static str {
+    if target_arch == "aarch64" {
+        "/opt/homebrew"
+    } else {
+        "/usr/local"
+    }
+}
+
+/// The Homebrew prefixes to look for FFmpeg in, the one `brew --prefix`
+/// reports first. It's skipped if it's the default prefix of the other arch
+/// (e.g. an Intel Homebrew under Rosetta when building for Apple silicon),
+/// whose libraries can't be linked.
+pub fn prefixes(brew_prefix: Option<&str>, target_arch: &str) -> Vec<String> {
+    let native = default_prefix(target_arch);
+    let foreign = default_prefix(if target_arch == "aarch64" {
+        "x86_64"
+    } else {
+        "aarch64"
+    });
+    let mut prefixes = Vec::new();
+    if let Some(brew_prefix) = brew_prefix.map(|prefix| prefix.trim_end_matches('/')) {
+        if brew_prefix != foreign && !brew_prefix.is_empty() {
+            prefixes.push(brew_prefix.to_string());
+        }
+    }
+    if !prefixes.iter().any(|prefix| prefix == native) {
+        prefixes.push(native.to_string());
+    }
+    prefixes
+}
+
+/// The FFmpeg kegs among the entries of `<prefix>/opt`, in the order they're
+/// tried: `ffmpeg`, then the versioned ones (`ffmpeg@6`...) newest first.
+pub fn ffmpeg_kegs<'a>(names: &[&'a str]) -> Vec<&'a str> {
+    let version = |name: &str| -> Option<u32> { name.strip_prefix("ffmpeg@")?.parse().ok() };
+    let mut versioned: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| version(name).is_some())
+        .collect();
+    versioned.sort_by_key(|name| std::cmp::Reverse(version(name)));
+    let mut kegs: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| *name == "ffmpeg")
+        .collect();
+    kegs.extend(versioned);
+    kegs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prefixes() {
+        assert_eq!(
+            prefixes(Some("/opt/homebrew"), "aarch64"),
+            ["/opt/homebrew"]
+        );
+        assert_eq!(prefixes(None, "x86_64"), ["/usr/local"]);
+        assert_eq!(prefixes(Some("/usr/local"), "aarch64"), ["/opt/homebrew"]);
+        assert_eq!(
+            prefixes(Some("/Users/me/homebrew/"), "aarch64"),
+            ["/Users/me/homebrew", "/opt/homebrew"]
+        );
+    }
+
+    #[test]
+    fn test_ffmpeg_kegs() {
+        assert_eq!(
+            ffmpeg_kegs(&[
+                "ffmpeg@4",
+                "x264",
+                "ffmpeg@6",
+                "ffmpeg",
+                "ffmpegthumbnailer"
+            ]),
+            ["ffmpeg", "ffmpeg@6", "ffmpeg@4"]
+        );
+        assert_eq!(
+            ffmpeg_kegs(&["ffmpeg@5", "ffmpeg@7"]),
+            ["ffmpeg@7", "ffmpeg@5"]
+        );
+        assert!(ffmpeg_kegs(&["lame"]).is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! The libraries `FFMPEG_LIBS` links instead of the ones of the cargo features.
+
+/// Parse `FFMPEG_LIBS`, a `,` separated list of library names: `avcodec`, or
+/// the one of a fork (`avcodec_custom`). `known` are the FFmpeg libraries, to
+/// catch `libavcodec` for `avcodec`. Fails on empty entries, paths, file names
+/// and duplicates, and without avutil, which every other library needs.
+pub fn parse_libs(value: &str, known: &[&str]) -> Result<Vec<String>, String> {
+    let mut libs: Vec<String> = Vec::new();
+    for entry in value.split(',').map(str::trim) {
+        if entry.is_empty() {
+            return Err(format!("{:?} has an empty entry", value));
+        }
+        if entry.contains(['/', '\\']) {
+            return Err(format!("`{}` is a path, give the library name", entry));
+        }
+        if entry.contains(char::is_whitespace) {
+            return Err(format!(
+                "`{}` has a space, the libraries are separated by `,`",
+                entry
+            ));
+        }
+        let is_file_name = [".so", ".a", ".lib", ".dylib", ".dll"]
+            .iter()
+            .any(|extension| entry.ends_with(extension))
+            || entry.contains(".so.");
+        if is_file_name {
+            return Err(format!(
+                "`{}` is a file name, give the library name (`avcodec` for `libavcodec.so`)",
+                entry
+            ));
+        }
+        if let Some(name) = entry
+            .strip_prefix("lib")
+            .filter(|name| known.contains(name))
+        {
+            return Err(format!("`{}` has the `lib` prefix, give `{}`", entry, name));
+        }
+        if libs.iter().any(|lib| lib == entry) {
+            return Err(format!("`{}` is listed twice", entry));
+        }
+        libs.push(entry.to_string());
+    }
+    if !libs.iter().any(|lib| lib == "avutil") {
+        return Err("avutil isn't listed, every other library needs it".to_string());
+    }
+    Ok(libs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KNOWN: [&str; 3] = ["avcodec", "avformat", "avutil"];
+
+    #[test]
+    fn test_parse_libs() {
+        assert_eq!(
+            parse_libs("avcodec, avutil,postproc", &KNOWN),
+            Ok(vec![
+                "avcodec".to_string(),
+                "avutil".to_string(),
+                "postproc".to_string()
+            ])
+        );
+        assert_eq!(
+            parse_libs("avcodec_custom,avutil", &KNOWN),
+            Ok(vec!["avcodec_custom".to_string(), "avutil".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_libs_errors() {
+        assert_eq!(
+            parse_libs("avcodec,,avutil", &KNOWN),
+            Err("\"avcodec,,avutil\" has an empty entry".to_string())
+        );
+        assert_eq!(
+            parse_libs("/usr/lib/avcodec,avutil", &KNOWN),
+            Err("`/usr/lib/avcodec` is a path, give the library name".to_string())
+        );
+        assert_eq!(
+            parse_libs("avcodec avutil", &KNOWN),
+            Err("`avcodec avutil` has a space, the libraries are separated by `,`".to_string())
+        );
+        assert!(parse_libs("libavcodec.so.60,avutil", &KNOWN).is_err());
+        assert!(parse_libs("avcodec.lib,avutil", &KNOWN).is_err());
+        assert_eq!(
+            parse_libs("libavcodec,avutil", &KNOWN),
+            Err("`libavcodec` has the `lib` prefix, give `avcodec`".to_string())
+        );
+        assert_eq!(
+            parse_libs("avutil,avcodec,avutil", &KNOWN),
+            Err("`avutil` is listed twice".to_string())
+        );
+        assert_eq!(
+            parse_libs("avcodec", &KNOWN),
+            Err("avutil isn't listed, every other library needs it".to_string())
+        );
+    }
+}
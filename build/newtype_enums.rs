@@ -0,0 +1,183 @@
+//! The newtype form of the FFmpeg enums bindgen gives as a type alias with
+//! prefixed constants, for the `newtype-enums` feature.
+
+/// The enums made newtypes. Flag-like enums are left as constants, since
+/// their values are combined.
+pub const NEWTYPE_ENUMS: [&str; 11] = [
+    "AVChromaLocation",
+    "AVCodecID",
+    "AVColorPrimaries",
+    "AVColorRange",
+    "AVColorSpace",
+    "AVColorTransferCharacteristic",
+    "AVHWDeviceType",
+    "AVMediaType",
+    "AVPictureType",
+    "AVPixelFormat",
+    "AVSampleFormat",
+];
+
+/// The binding with each of `enums` turned into a `#[repr(transparent)]`
+/// newtype of its integer type, as bindgen's `newtype_enum()` would: the
+/// variants are associated constants without the enum name prefix
+/// (`AVPixelFormat::AV_PIX_FMT_YUV420P`). The prefixed constants are kept,
+/// of the newtype. Enums the binding doesn't have are left alone.
+pub fn make_newtype_enums(binding: &str, enums: &[&str]) -> String {
+    let lines = join_enum_items(binding, enums);
+    let mut variants: Vec<Vec<(String, String)>> = vec![Vec::new(); enums.len()];
+    for line in lines.iter() {
+        for (name, variants) in enums.iter().zip(variants.iter_mut()) {
+            if let Some(variant) = enum_constant(line, name) {
+                variants.push(variant);
+            }
+        }
+    }
+
+    let mut newtype = String::with_capacity(binding.len());
+    'lines: for line in lines.iter() {
+        for (name, variants) in enums.iter().zip(variants.iter()) {
+            if let Some((variant, value)) = enum_constant(line, name) {
+                newtype.push_str(&format!(
+                    "pub const {}_{}: {} = {}({});\n",
+                    name, variant, name, name, value
+                ));
+                continue 'lines;
+            }
+            if let Some(repr) = enum_alias(line, name) {
+                newtype.push_str(&format!(
+                    "#[repr(transparent)]\n\
+                     #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]\n\
+                     pub struct {}(pub {});\n",
+                    name, repr
+                ));
+                newtype.push_str(&format!("impl {} {{\n", name));
+                for (variant, value) in variants.iter() {
+                    newtype.push_str(&format!(
+                        "    pub const {}: {} = {}({});\n",
+                        variant, name, name, value
+                    ));
+                }
+                newtype.push_str("}\n");
+                continue 'lines;
+            }
+        }
+        newtype.push_str(line);
+        newtype.push('\n');
+    }
+    newtype
+}
+
+/// The lines of the binding, with the constants and aliases of `enums` that
+/// rustfmt wrapped joined back into one line.
+fn join_enum_items(binding: &str, enums: &[&str]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut open = false;
+    for line in binding.lines() {
+        if open {
+            let last = lines.last_mut().unwrap();
+            last.push(' ');
+            last.push_str(line.trim());
+        } else {
+            lines.push(line.to_string());
+        }
+        let last = lines.last().unwrap();
+        open = !last.ends_with(';')
+            && enums.iter().any(|name| {
+                last.starts_with(&format!("pub const {}_", name))
+                    || last.starts_with(&format!("pub type {} ", name))
+            });
+    }
+    lines
+}
+
+/// The variant and value of a constant of the enum `name`, like
+/// `pub const AVMediaType_AVMEDIA_TYPE_VIDEO: AVMediaType = 0;`.
+fn enum_constant(line: &str, name: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix("pub const ")?.strip_prefix(name)?;
+    let (variant, rest) = rest.strip_prefix('_')?.split_once(':')?;
+    let value = rest
+        .trim_start()
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim()
+        .strip_suffix(';')?;
+    Some((variant.to_string(), value.to_string()))
+}
+
+/// The integer type of the alias of the enum `name`, like
+/// `pub type AVMediaType = ::std::os::raw::c_int;`.
+fn enum_alias<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix("pub type ")?.strip_prefix(name)?;
+    let repr = rest
+        .trim_start()
+        .strip_prefix('=')?
+        .trim()
+        .strip_suffix(';')?;
+    Some(repr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_make_newtype_enums() {
+        let binding = "pub const AVMediaType_AVMEDIA_TYPE_UNKNOWN: AVMediaType = -1;\n\
+                       #[doc = \" Usually treated as AVMEDIA_TYPE_DATA\"]\n\
+                       pub const AVMediaType_AVMEDIA_TYPE_VIDEO: AVMediaType = 0;\n\
+                       pub type AVMediaType = ::std::os::raw::c_int;\n\
+                       pub const AV_LOG_QUIET: i32 = -8;\n\
+                       pub const AVChromaLocation_AVCHROMA_LOC_LEFT: AVChromaLocation = 1;\n\
+                       pub type AVChromaLocation = ::std::os::raw::c_uint;\n";
+        assert_eq!(
+            make_newtype_enums(binding, &["AVMediaType", "AVPixelFormat"]),
+            "pub const AVMediaType_AVMEDIA_TYPE_UNKNOWN: AVMediaType = AVMediaType(-1);\n\
+             #[doc = \" Usually treated as AVMEDIA_TYPE_DATA\"]\n\
+             pub const AVMediaType_AVMEDIA_TYPE_VIDEO: AVMediaType = AVMediaType(0);\n\
+             #[repr(transparent)]\n\
+             #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]\n\
+             pub struct AVMediaType(pub ::std::os::raw::c_int);\n\
+             impl AVMediaType {\n    \
+                 pub const AVMEDIA_TYPE_UNKNOWN: AVMediaType = AVMediaType(-1);\n    \
+                 pub const AVMEDIA_TYPE_VIDEO: AVMediaType = AVMediaType(0);\n\
+             }\n\
+             pub const AV_LOG_QUIET: i32 = -8;\n\
+             pub const AVChromaLocation_AVCHROMA_LOC_LEFT: AVChromaLocation = 1;\n\
+             pub type AVChromaLocation = ::std::os::raw::c_uint;\n"
+        );
+    }
+
+    #[test]
+    fn test_wrapped_constants() {
+        let binding = "pub const AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084:\n    \
+                       AVColorTransferCharacteristic = 16;\n\
+                       pub type AVColorTransferCharacteristic =\n    \
+                       ::std::os::raw::c_uint;\n";
+        assert_eq!(
+            make_newtype_enums(binding, &["AVColorTransferCharacteristic"]),
+            "pub const AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084: \
+             AVColorTransferCharacteristic = AVColorTransferCharacteristic(16);\n\
+             #[repr(transparent)]\n\
+             #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]\n\
+             pub struct AVColorTransferCharacteristic(pub ::std::os::raw::c_uint);\n\
+             impl AVColorTransferCharacteristic {\n    \
+                 pub const AVCOL_TRC_SMPTE2084: AVColorTransferCharacteristic = \
+             AVColorTransferCharacteristic(16);\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_vendored_binding() {
+        let binding = make_newtype_enums(include_str!("../src/binding_4_4.rs"), &NEWTYPE_ENUMS);
+        for name in NEWTYPE_ENUMS.iter() {
+            assert!(binding.contains(&format!("\npub struct {}(pub ::std::os::raw::", name)));
+            assert!(!binding.contains(&format!("\npub type {} =", name)));
+        }
+        assert!(binding
+            .contains("\n    pub const AV_PIX_FMT_YUV420P: AVPixelFormat = AVPixelFormat(0);\n"));
+        assert!(binding
+            .contains("\npub const AVCodecID_AV_CODEC_ID_H264: AVCodecID = AVCodecID(27);\n"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Normalization of the paths given by env vars, before they reach clang,
+//! pkg-config and the linker.
+
+/// Normalize a path given by the user. On Windows, separators are converted to
+/// `\`, verbatim prefixes (`\\?\C:\`, `\\?\UNC\`) are removed since clang
+/// doesn't understand them, and MSYS style drive paths (`/c/ffmpeg`) are
+/// converted to drive paths. Trailing separators are removed everywhere,
+/// except for roots like `/` or `C:\`.
+pub fn normalize_path(path: &str, windows: bool) -> String {
+    if !windows {
+        let trimmed = path.trim_end_matches('/');
+        return if trimmed.is_empty() && !path.is_empty() {
+            "/".to_string()
+        } else {
+            trimmed.to_string()
+        };
+    }
+
+    let mut path = path.replace('/', "\\");
+    if let Some(rest) = path.strip_prefix("\\\\?\\UNC\\") {
+        path = format!("\\\\{}", rest);
+    } else if let Some(rest) = path.strip_prefix("\\\\?\\") {
+        path = rest.to_string();
+    } else {
+        // `\c\ffmpeg` (from `/c/ffmpeg`) -> `C:\ffmpeg`
+        let bytes = path.as_bytes();
+        if bytes.len() >= 2
+            && bytes[0] == b'\\'
+            && bytes[1].is_ascii_alphabetic()
+            && (bytes.len() == 2 || bytes[2] == b'\\')
+        {
+            path = format!("{}:{}", (bytes[1] as char).to_ascii_uppercase(), &path[2..]);
+        }
+    }
+
+    let trimmed = path.trim_end_matches('\\');
+    let is_drive = trimmed.len() == 2 && trimmed.ends_with(':');
+    if is_drive || (trimmed.is_empty() && !path.is_empty()) {
+        format!("{}\\", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// The Windows path of a path in a `.pc` file of MSYS2, where `/mingw64/lib`
+/// is relative to the MSYS2 root `msys_root` (e.g. `C:\\msys64`) if it's known,
+/// and `/c/ffmpeg` is a drive path.
+pub fn msys_path(path: &str, msys_root: Option<&str>) -> String {
+    let bytes = path.as_bytes();
+    let is_drive = bytes.len() >= 2
+        && bytes[0] == b'/'
+        && bytes[1].is_ascii_alphabetic()
+        && (bytes.len() == 2 || bytes[2] == b'/');
+    match msys_root {
+        Some(msys_root) if path.starts_with('/') && !is_drive => normalize_path(
+            &format!("{}{}", msys_root.trim_end_matches(['/', '\\']), path),
+            true,
+        ),
+        _ => normalize_path(path, true),
+    }
+}
+
+/// A path pkg-config reports for a cross target on the host: under `sysroot`
+/// (`PKG_CONFIG_SYSROOT_DIR`) if it isn't already, as `--variable` values
+/// aren't prefixed by pkg-config, unlike `-I` and `-L` flags.
+pub fn sysroot_path(path: &str, sysroot: Option<&str>) -> String {
+    let sysroot = match sysroot.map(|sysroot| sysroot.trim_end_matches('/')) {
+        Some(sysroot) if !sysroot.is_empty() => sysroot,
+        _ => return path.to_string(),
+    };
+    let under_sysroot = path
+        .strip_prefix(sysroot)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    if under_sysroot || !path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}{}", sysroot, path)
+    }
+}
+
+/// Put the entries of the path list `first` (like `PKG_CONFIG_PATH`) before the
+/// ones of `existing`, which are kept unless they're in `first` already. Lists
+/// are separated by `;` on Windows and by `:` elsewhere.
+pub fn prepend_path_list(first: &str, existing: Option<&str>, windows: bool) -> String {
+    let separator = if windows { ';' } else { ':' };
+    let mut entries: Vec<&str> = Vec::new();
+    for entry in first
+        .split(separator)
+        .chain(existing.into_iter().flat_map(|x| x.split(separator)))
+    {
+        if !entry.is_empty() && !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries.join(&separator.to_string())
+}
+
+/// The normalized entries of a path list like `FFMPEG_INCLUDE_DIR`, separated
+/// by `;` on Windows and by `:` elsewhere. Empty entries are dropped.
+pub fn split_path_list(list: &str, windows: bool) -> Vec<String> {
+    list.split(if windows { ';' } else { ':' })
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| normalize_path(entry.trim(), windows))
+        .collect()
+}
+
+/// A path list with every entry normalized.
+pub fn normalize_path_list(list: &str, windows: bool) -> String {
+    split_path_list(list, windows).join(if windows { ";" } else { ":" })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/usr/include/", false), "/usr/include");
+        assert_eq!(normalize_path("/usr/include", false), "/usr/include");
+        assert_eq!(normalize_path("/", false), "/");
+        assert_eq!(normalize_path("include//", false), "include");
+    }
+
+    #[test]
+    fn test_normalize_windows_path() {
+        assert_eq!(
+            normalize_path("C:\\ffmpeg\\include\\", true),
+            "C:\\ffmpeg\\include"
+        );
+        assert_eq!(
+            normalize_path("C:/ffmpeg/include/", true),
+            "C:\\ffmpeg\\include"
+        );
+        assert_eq!(
+            normalize_path("C:\\ffmpeg/include", true),
+            "C:\\ffmpeg\\include"
+        );
+        assert_eq!(normalize_path("C:\\", true), "C:\\");
+        assert_eq!(normalize_path("C:", true), "C:\\");
+        assert_eq!(
+            normalize_path("/c/ffmpeg/include", true),
+            "C:\\ffmpeg\\include"
+        );
+        assert_eq!(
+            normalize_path("\\\\?\\C:\\ffmpeg\\include\\", true),
+            "C:\\ffmpeg\\include"
+        );
+        assert_eq!(
+            normalize_path("\\\\server\\share\\ffmpeg\\", true),
+            "\\\\server\\share\\ffmpeg"
+        );
+        assert_eq!(
+            normalize_path("\\\\?\\UNC\\server\\share\\ffmpeg", true),
+            "\\\\server\\share\\ffmpeg"
+        );
+        assert_eq!(normalize_path("ffmpeg\\include\\", true), "ffmpeg\\include");
+    }
+
+    #[test]
+    fn test_prepend_path_list() {
+        assert_eq!(
+            prepend_path_list("/ffmpeg/lib/pkgconfig", None, false),
+            "/ffmpeg/lib/pkgconfig"
+        );
+        assert_eq!(
+            prepend_path_list("/ffmpeg/lib/pkgconfig", Some("/nix/a:/nix/b"), false),
+            "/ffmpeg/lib/pkgconfig:/nix/a:/nix/b"
+        );
+        // Prepending again changes nothing.
+        assert_eq!(
+            prepend_path_list(
+                "/ffmpeg/lib/pkgconfig",
+                Some("/ffmpeg/lib/pkgconfig:/nix/a"),
+                false
+            ),
+            "/ffmpeg/lib/pkgconfig:/nix/a"
+        );
+        assert_eq!(prepend_path_list("/a:/b", Some(""), false), "/a:/b");
+        assert_eq!(
+            prepend_path_list("C:\\ffmpeg\\pkgconfig", Some("C:\\x;D:\\y"), true),
+            "C:\\ffmpeg\\pkgconfig;C:\\x;D:\\y"
+        );
+    }
+
+    #[test]
+    fn test_split_path_list() {
+        assert_eq!(split_path_list("/usr/include/", false), ["/usr/include"]);
+        assert_eq!(
+            split_path_list("/nix/a/include:/nix/b/include/::", false),
+            ["/nix/a/include", "/nix/b/include"]
+        );
+        assert_eq!(
+            split_path_list("C:/ffmpeg/include;/d/avutil/include\\", true),
+            ["C:\\ffmpeg\\include", "D:\\avutil\\include"]
+        );
+        assert_eq!(normalize_path_list("C:/a/;D:\\b", true), "C:\\a;D:\\b");
+    }
+
+    #[test]
+    fn test_msys_path() {
+        let root = Some("C:\\msys64\\");
+        assert_eq!(
+            msys_path("/mingw64/include", root),
+            "C:\\msys64\\mingw64\\include"
+        );
+        assert_eq!(msys_path("/c/ffmpeg/lib", root), "C:\\ffmpeg\\lib");
+        assert_eq!(msys_path("D:/ffmpeg/lib/", root), "D:\\ffmpeg\\lib");
+        assert_eq!(msys_path("/mingw64/lib", None), "\\mingw64\\lib");
+    }
+
+    #[test]
+    fn test_sysroot_path() {
+        let sysroot = Some("/opt/sysroot/");
+        assert_eq!(
+            sysroot_path("/usr/include", sysroot),
+            "/opt/sysroot/usr/include"
+        );
+        assert_eq!(
+            sysroot_path("/opt/sysroot/usr/include", sysroot),
+            "/opt/sysroot/usr/include"
+        );
+        assert_eq!(
+            sysroot_path("/opt/sysroot2/include", sysroot),
+            "/opt/sysroot/opt/sysroot2/include"
+        );
+        assert_eq!(sysroot_path("/usr/include", None), "/usr/include");
+        assert_eq!(sysroot_path("/usr/include", Some("")), "/usr/include");
+    }
+}
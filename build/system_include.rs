@@ -0,0 +1,94 @@
+//! The dirs where the package manager of the system puts the FFmpeg headers,
+//! searched when no include dir is set.
+
+/// The Debian multiarch tuple of a Rust target, e.g. `x86_64-linux-gnu`, which
+/// libavcodec-dev and friends put their headers under. `None` for targets
+/// other than linux-gnu.
+pub fn multiarch(rust_target: &str) -> Option<String> {
+    let parts: Vec<&str> = rust_target.split('-').collect();
+    let (arch, env) = match parts[..] {
+        [arch, _, "linux", env] if env.starts_with("gnu") => (arch, env),
+        _ => return None,
+    };
+    let arch = match arch {
+        "i586" | "i686" => "i386",
+        arch if arch.starts_with("arm") => "arm",
+        arch if arch.starts_with("riscv64") => "riscv64",
+        arch => arch,
+    };
+    Some(format!("{}-linux-{}", arch, env))
+}
+
+/// The system include dirs to look for FFmpeg headers in, most specific
+/// first. The ones shared by all archs only count when building for the host.
+pub fn system_include_dirs(target: &str, host: &str) -> Vec<String> {
+    if target.contains("-windows-") || host.contains("-windows-") {
+        return Vec::new();
+    }
+    let mut dirs = Vec::new();
+    if let Some(multiarch) = multiarch(target) {
+        dirs.push(format!("/usr/include/{}", multiarch));
+    }
+    if target == host {
+        dirs.push("/usr/local/include".to_string());
+        if target.contains("-apple-") {
+            // Homebrew on Apple silicon.
+            dirs.push("/opt/homebrew/include".to_string());
+        }
+        dirs.push("/usr/include".to_string());
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_multiarch() {
+        assert_eq!(
+            multiarch("x86_64-unknown-linux-gnu").as_deref(),
+            Some("x86_64-linux-gnu")
+        );
+        assert_eq!(
+            multiarch("i686-unknown-linux-gnu").as_deref(),
+            Some("i386-linux-gnu")
+        );
+        assert_eq!(
+            multiarch("armv7-unknown-linux-gnueabihf").as_deref(),
+            Some("arm-linux-gnueabihf")
+        );
+        assert_eq!(
+            multiarch("riscv64gc-unknown-linux-gnu").as_deref(),
+            Some("riscv64-linux-gnu")
+        );
+        assert_eq!(multiarch("x86_64-unknown-linux-musl"), None);
+        assert_eq!(multiarch("aarch64-apple-darwin"), None);
+    }
+
+    #[test]
+    fn test_system_include_dirs() {
+        let host = "x86_64-unknown-linux-gnu";
+        assert_eq!(
+            system_include_dirs(host, host),
+            [
+                "/usr/include/x86_64-linux-gnu",
+                "/usr/local/include",
+                "/usr/include"
+            ]
+        );
+        assert_eq!(
+            system_include_dirs("aarch64-unknown-linux-gnu", host),
+            ["/usr/include/aarch64-linux-gnu"]
+        );
+        assert_eq!(
+            system_include_dirs("aarch64-apple-darwin", "aarch64-apple-darwin"),
+            [
+                "/usr/local/include",
+                "/opt/homebrew/include",
+                "/usr/include"
+            ]
+        );
+        assert!(system_include_dirs("x86_64-pc-windows-msvc", "x86_64-pc-windows-msvc").is_empty());
+    }
+}
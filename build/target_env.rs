@@ -0,0 +1,84 @@
+//! The lookup of the env vars that can be scoped to a target, like
+//! `FFMPEG_LIBS_DIR_aarch64_unknown_linux_gnu`.
+
+/// The names `var` is looked up by when building for `target`, in order:
+/// `<var>_<target with _ for ->`, `<var>_<target>` and `<var>`.
+pub fn scoped_names(var: &str, target: &str) -> Vec<String> {
+    if target.is_empty() {
+        return vec![var.to_string()];
+    }
+    vec![
+        format!("{}_{}", var, target.replace('-', "_")),
+        format!("{}_{}", var, target),
+        var.to_string(),
+    ]
+}
+
+/// The first of the `scoped_names()` of `var` that `env` has, with its name.
+pub fn lookup(
+    var: &str,
+    target: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<(String, String)> {
+    scoped_names(var, target)
+        .into_iter()
+        .find_map(|name| env(&name).map(|value| (name, value)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_scoped_names() {
+        assert_eq!(
+            scoped_names("FFMPEG_LIBS_DIR", "aarch64-unknown-linux-gnu"),
+            [
+                "FFMPEG_LIBS_DIR_aarch64_unknown_linux_gnu",
+                "FFMPEG_LIBS_DIR_aarch64-unknown-linux-gnu",
+                "FFMPEG_LIBS_DIR"
+            ]
+        );
+        assert_eq!(scoped_names("FFMPEG_LIBS_DIR", ""), ["FFMPEG_LIBS_DIR"]);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let env: HashMap<&str, &str> = [
+            ("FFMPEG_LIBS_DIR", "/usr/lib"),
+            ("FFMPEG_LIBS_DIR_aarch64-unknown-linux-gnu", "/dashes/lib"),
+            ("FFMPEG_LIBS_DIR_aarch64_unknown_linux_gnu", "/arm/lib"),
+            (
+                "FFMPEG_INCLUDE_DIR_x86_64-pc-windows-msvc",
+                "C:\\ffmpeg\\include",
+            ),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        let get = |name: &str| env.get(name).map(|value| value.to_string());
+        assert_eq!(
+            lookup("FFMPEG_LIBS_DIR", "aarch64-unknown-linux-gnu", get),
+            Some((
+                "FFMPEG_LIBS_DIR_aarch64_unknown_linux_gnu".to_string(),
+                "/arm/lib".to_string()
+            ))
+        );
+        assert_eq!(
+            lookup("FFMPEG_LIBS_DIR", "x86_64-unknown-linux-gnu", get),
+            Some(("FFMPEG_LIBS_DIR".to_string(), "/usr/lib".to_string()))
+        );
+        assert_eq!(
+            lookup("FFMPEG_INCLUDE_DIR", "x86_64-pc-windows-msvc", get),
+            Some((
+                "FFMPEG_INCLUDE_DIR_x86_64-pc-windows-msvc".to_string(),
+                "C:\\ffmpeg\\include".to_string()
+            ))
+        );
+        assert_eq!(
+            lookup("FFMPEG_INCLUDE_DIR", "aarch64-unknown-linux-gnu", get),
+            None
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! What vcpkg installed of a port, read from its `installed/vcpkg/status` file.
+
+/// The installed version and features of a port for a triplet.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PortStatus {
+    pub version: String,
+    pub features: Vec<String>,
+}
+
+/// The status of `port` for `triplet` in the content of a status file, `None`
+/// if it isn't installed. The file has a paragraph per package and feature,
+/// a later paragraph overrides an earlier one (e.g. on removal).
+pub fn port_status(status: &str, port: &str, triplet: &str) -> Option<PortStatus> {
+    let mut version = None;
+    let mut features: Vec<String> = Vec::new();
+    for paragraph in status.replace("\r\n", "\n").split("\n\n") {
+        let field = |name: &str| {
+            paragraph.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                (key.trim() == name).then(|| value.trim())
+            })
+        };
+        if field("Package") != Some(port) || field("Architecture") != Some(triplet) {
+            continue;
+        }
+        let installed = field("Status").is_some_and(|status| status.ends_with(" installed"));
+        match field("Feature") {
+            Some(feature) => {
+                features.retain(|x| x != feature);
+                if installed {
+                    features.push(feature.to_string());
+                }
+            }
+            None => {
+                version = if installed {
+                    field("Version").map(str::to_string)
+                } else {
+                    None
+                };
+            }
+        }
+    }
+    let version = version?;
+    features.sort();
+    Some(PortStatus { version, features })
+}
+
+/// The command installing `port` with `features` for `triplet`.
+pub fn install_command(port: &str, features: &[&str], triplet: &str) -> String {
+    if features.is_empty() {
+        format!("vcpkg install {}:{}", port, triplet)
+    } else {
+        format!("vcpkg install {}[{}]:{}", port, features.join(","), triplet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STATUS: &str = "Package: ffmpeg
+Version: 7.1
+Port-Version: 1
+Architecture: x64-windows
+Multi-Arch: same
+Status: install ok installed
+
+Package: ffmpeg
+Feature: avcodec
+Architecture: x64-windows
+Multi-Arch: same
+Status: install ok installed
+
+Package: ffmpeg
+Feature: x264
+Architecture: x64-windows
+Multi-Arch: same
+Status: install ok installed
+
+Package: ffmpeg
+Feature: avdevice
+Architecture: x64-windows
+Multi-Arch: same
+Status: install ok installed
+
+Package: ffmpeg
+Feature: avdevice
+Architecture: x64-windows
+Multi-Arch: same
+Status: purge ok not-installed
+
+Package: ffmpeg
+Version: 6.1
+Architecture: x64-windows-static
+Multi-Arch: same
+Status: install ok installed
+";
+
+    #[test]
+    fn test_port_status() {
+        assert_eq!(
+            port_status(STATUS, "ffmpeg", "x64-windows"),
+            Some(PortStatus {
+                version: "7.1".to_string(),
+                features: vec!["avcodec".to_string(), "x264".to_string()],
+            })
+        );
+        assert_eq!(
+            port_status(STATUS, "ffmpeg", "x64-windows-static"),
+            Some(PortStatus {
+                version: "6.1".to_string(),
+                features: Vec::new(),
+            })
+        );
+        assert_eq!(port_status(STATUS, "ffmpeg", "arm64-windows"), None);
+        assert_eq!(port_status(STATUS, "x264", "x64-windows"), None);
+    }
+
+    #[test]
+    fn test_install_command() {
+        assert_eq!(
+            install_command("ffmpeg", &["avdevice", "x264"], "x64-windows-static-md"),
+            "vcpkg install ffmpeg[avdevice,x264]:x64-windows-static-md"
+        );
+        assert_eq!(
+            install_command("ffmpeg", &[], "x64-windows"),
+            "vcpkg install ffmpeg:x64-windows"
+        );
+    }
+}
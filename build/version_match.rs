@@ -0,0 +1,137 @@
+//! The versions of the FFmpeg headers and libraries found, to catch headers and
+//! libraries of different FFmpeg releases.
+
+/// FFmpeg release series, with the libavcodec and libavutil majors they ship.
+const RELEASE_MAJORS: [(u32, u32, u32); 6] = [
+    (3, 57, 55),
+    (4, 58, 56),
+    (5, 59, 57),
+    (6, 60, 58),
+    (7, 61, 59),
+    (8, 62, 60),
+];
+
+/// The version int of `libname` (`AV_VERSION_INT`) from the contents of its
+/// `version.h`, and `version_major.h` for newer FFmpeg.
+pub fn parse_version(contents: &[&str], libname: &str) -> Option<u32> {
+    let prefix = format!("#define LIB{}_VERSION_", libname.to_uppercase());
+    let mut version = [None; 3];
+    for line in contents.iter().flat_map(|content| content.lines()) {
+        let mut parts = match line.strip_prefix(&prefix) {
+            Some(rest) => rest.split_whitespace(),
+            None => continue,
+        };
+        let index = match parts.next() {
+            Some("MAJOR") => 0,
+            Some("MINOR") => 1,
+            Some("MICRO") => 2,
+            _ => continue,
+        };
+        version[index] = parts.next().and_then(|x| x.parse::<u32>().ok());
+    }
+    match version {
+        [Some(major), Some(minor), Some(micro)] => Some(major << 16 | minor << 8 | micro),
+        _ => None,
+    }
+}
+
+/// The major of a library version like pkg-config reports it, `60.31.102`.
+pub fn version_major(version: &str) -> Option<u32> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// The `Version:` of a `.pc` file.
+pub fn pc_version(content: &str) -> Option<&str> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("Version:"))
+        .map(str::trim)
+}
+
+/// The major of `libname` (avcodec or avutil) in an FFmpeg release, like
+/// vcpkg reports it (`6.1.1`, `7.0#2`).
+pub fn release_lib_major(release: &str, libname: &str) -> Option<u32> {
+    let series = version_major(release)?;
+    RELEASE_MAJORS
+        .iter()
+        .find(|(release, _, _)| *release == series)
+        .and_then(|(_, avcodec, avutil)| match libname {
+            "avcodec" => Some(*avcodec),
+            "avutil" => Some(*avutil),
+            _ => None,
+        })
+}
+
+/// `libavcodec 60 (FFmpeg 6.x)`, without the release if it isn't known.
+pub fn describe(libname: &str, major: u32) -> String {
+    let series = RELEASE_MAJORS
+        .iter()
+        .find(|(_, avcodec, avutil)| match libname {
+            "avcodec" => *avcodec == major,
+            "avutil" => *avutil == major,
+            _ => false,
+        });
+    match series {
+        Some((release, _, _)) => format!("lib{} {} (FFmpeg {}.x)", libname, major, release),
+        None => format!("lib{} {}", libname, major),
+    }
+}
+
+/// Whether the libraries, with the majors `lib_majors` found for them, can't
+/// be the ones of headers of `header_major`. Nothing found can't be told.
+pub fn is_mismatch(header_major: u32, lib_majors: &[u32]) -> bool {
+    !lib_majors.is_empty() && !lib_majors.contains(&header_major)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const VERSION_H: &str = "#ifndef AVCODEC_VERSION_H\n\
+                             #include \"version_major.h\"\n\
+                             #define LIBAVCODEC_VERSION_MINOR  31\n\
+                             #define LIBAVCODEC_VERSION_MICRO 102\n";
+    const VERSION_MAJOR_H: &str = "#define LIBAVCODEC_VERSION_MAJOR  60\n";
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version(&[VERSION_H, VERSION_MAJOR_H], "avcodec"),
+            Some(60 << 16 | 31 << 8 | 102)
+        );
+        assert_eq!(parse_version(&[VERSION_H], "avcodec"), None);
+        assert_eq!(parse_version(&[VERSION_H, VERSION_MAJOR_H], "avutil"), None);
+        let ffmpeg4 = "#define LIBAVUTIL_VERSION_MAJOR  56\n\
+                       #define LIBAVUTIL_VERSION_MINOR  70\n\
+                       #define LIBAVUTIL_VERSION_MICRO 100\n";
+        assert_eq!(
+            parse_version(&[ffmpeg4], "avutil"),
+            Some(56 << 16 | 70 << 8 | 100)
+        );
+    }
+
+    #[test]
+    fn test_lib_versions() {
+        assert_eq!(version_major("60.31.102"), Some(60));
+        assert_eq!(version_major("unknown"), None);
+        assert_eq!(
+            pc_version("prefix=/opt/ffmpeg\nName: libavcodec\nVersion: 58.134.100\n"),
+            Some("58.134.100")
+        );
+        assert_eq!(pc_version("Name: libavcodec\n"), None);
+        assert_eq!(release_lib_major("6.1.1", "avcodec"), Some(60));
+        assert_eq!(release_lib_major("7.0#2", "avutil"), Some(59));
+        assert_eq!(release_lib_major("2.8", "avcodec"), None);
+    }
+
+    #[test]
+    fn test_mismatch() {
+        assert!(!is_mismatch(60, &[60]));
+        assert!(!is_mismatch(60, &[58, 60]));
+        assert!(is_mismatch(60, &[58]));
+        assert!(!is_mismatch(60, &[]));
+        assert_eq!(describe("avcodec", 60), "libavcodec 60 (FFmpeg 6.x)");
+        assert_eq!(describe("avutil", 56), "libavutil 56 (FFmpeg 4.x)");
+        assert_eq!(describe("avcodec", 70), "libavcodec 70");
+    }
+}
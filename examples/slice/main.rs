@@ -109,7 +109,7 @@ fn main() {
             .unwrap();
 
         println!(
-            "\tCodec {} ID {} bit_rate {}",
+            "\tCodec {} ID {:?} bit_rate {}",
             codec_name, local_codec.id, local_codec_params.bit_rate
         );
     }
@@ -0,0 +1,133 @@
+use crate::ffi::{
+    self, AVCodecDescriptor, AVCodecID, AVMediaType, AV_CODEC_PROP_INTRA_ONLY,
+    AV_CODEC_PROP_LOSSLESS, AV_CODEC_PROP_LOSSY,
+};
+#[cfg(feature = "avformat")]
+use crate::ffi::{AVCodecTag, AVOutputFormat};
+use core::ffi::CStr;
+
+/// Media type of a codec, `AVMEDIA_TYPE_UNKNOWN` for unknown ids.
+pub fn codec_id_media_type(id: AVCodecID) -> AVMediaType {
+    unsafe { ffi::avcodec_get_type(id) }
+}
+
+/// Name of a codec, e.g. "h264". FFmpeg never returns null, unknown ids are
+/// named "unknown_codec".
+pub fn codec_id_name(id: AVCodecID) -> &'static str {
+    unsafe { CStr::from_ptr(ffi::avcodec_get_name(id)) }
+        .to_str()
+        .unwrap_or("unknown_codec")
+}
+
+/// Descriptor of a codec from the descriptor table of libavcodec.
+pub fn codec_descriptor(id: AVCodecID) -> Option<&'static AVCodecDescriptor> {
+    unsafe { ffi::avcodec_descriptor_get(id).as_ref() }
+}
+
+fn has_prop(id: AVCodecID, prop: u32) -> bool {
+    codec_descriptor(id)
+        .map(|descriptor| descriptor.props as u32 & prop != 0)
+        .unwrap_or(false)
+}
+
+/// Whether every frame of the codec is a keyframe.
+pub fn codec_is_intra_only(id: AVCodecID) -> bool {
+    has_prop(id, AV_CODEC_PROP_INTRA_ONLY)
+}
+
+/// Whether the codec supports lossy compression.
+pub fn codec_is_lossy(id: AVCodecID) -> bool {
+    has_prop(id, AV_CODEC_PROP_LOSSY)
+}
+
+/// Whether the codec supports lossless compression.
+pub fn codec_is_lossless(id: AVCodecID) -> bool {
+    has_prop(id, AV_CODEC_PROP_LOSSLESS)
+}
+
+/// The codec tag (fourcc) a muxer writes for a codec, `None` if the muxer has
+/// no tag table or no tag for the codec.
+///
+/// # Safety
+/// `fmt` must be null or point to a valid `AVOutputFormat`.
+#[cfg(feature = "avformat")]
+pub unsafe fn codec_tag_for(fmt: *const AVOutputFormat, id: AVCodecID) -> Option<u32> {
+    let fmt = fmt.as_ref()?;
+    if fmt.codec_tag.is_null() {
+        return None;
+    }
+    let mut tag = 0;
+    if ffi::av_codec_get_tag2(fmt.codec_tag, id, &mut tag) != 0 {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+/// The codec of a tag (fourcc) in null terminated tag tables, e.g. the
+/// `codec_tag` of an `AVInputFormat`/`AVOutputFormat` or
+/// `[avformat_get_mov_video_tags(), null()]`. `AV_CODEC_ID_NONE` if not found.
+///
+/// # Safety
+/// `tags` must be null or point to a null terminated array of tag tables.
+#[cfg(feature = "avformat")]
+pub unsafe fn codec_id_from_tag(tags: *const *const AVCodecTag, tag: u32) -> AVCodecID {
+    if tags.is_null() {
+        return ffi::AVCodecID_AV_CODEC_ID_NONE;
+    }
+    ffi::av_codec_get_id(tags, tag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "avformat")]
+    use crate::ffi::MKTAG;
+    #[cfg(feature = "avformat")]
+    use core::ptr;
+
+    #[test]
+    fn test_codec_id_media_type() {
+        assert_eq!(
+            codec_id_media_type(ffi::AVCodecID_AV_CODEC_ID_H264),
+            ffi::AVMediaType_AVMEDIA_TYPE_VIDEO
+        );
+        assert_eq!(
+            codec_id_media_type(ffi::AVCodecID_AV_CODEC_ID_AAC),
+            ffi::AVMediaType_AVMEDIA_TYPE_AUDIO
+        );
+    }
+
+    #[test]
+    fn test_codec_descriptor() {
+        assert_eq!(codec_id_name(ffi::AVCodecID_AV_CODEC_ID_H264), "h264");
+        assert!(codec_is_lossy(ffi::AVCodecID_AV_CODEC_ID_H264));
+        assert!(!codec_is_intra_only(ffi::AVCodecID_AV_CODEC_ID_H264));
+        assert!(codec_is_intra_only(ffi::AVCodecID_AV_CODEC_ID_MJPEG));
+        assert!(codec_is_lossless(ffi::AVCodecID_AV_CODEC_ID_FFV1));
+        assert!(codec_descriptor(ffi::AVCodecID_AV_CODEC_ID_NONE).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "avformat")]
+    fn test_mov_tags() {
+        let avc1 = MKTAG(b'a', b'v', b'c', b'1');
+        let tags = [unsafe { ffi::avformat_get_mov_video_tags() }, ptr::null()];
+        assert_eq!(
+            unsafe { codec_id_from_tag(tags.as_ptr(), avc1) },
+            ffi::AVCodecID_AV_CODEC_ID_H264
+        );
+
+        let mov = unsafe { ffi::av_guess_format(b"mov\0".as_ptr() as _, ptr::null(), ptr::null()) };
+        assert!(!mov.is_null());
+        let tag = unsafe { codec_tag_for(mov, ffi::AVCodecID_AV_CODEC_ID_H264) }.unwrap();
+        assert_eq!(
+            unsafe { codec_id_from_tag((*mov).codec_tag, tag) },
+            ffi::AVCodecID_AV_CODEC_ID_H264
+        );
+        assert_eq!(
+            unsafe { codec_tag_for(ptr::null(), ffi::AVCodecID_AV_CODEC_ID_H264) },
+            None
+        );
+    }
+}
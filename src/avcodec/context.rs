@@ -0,0 +1,133 @@
+use crate::ffi::{
+    self, AVCodec, AVCodecContext, AVCodecParameters, Dictionary, FfmpegError, AVERROR_ENOMEM,
+};
+use std::{
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+/// Owns an `AVCodecContext` from `avcodec_alloc_context3()` and frees it with
+/// `avcodec_free_context()` on drop. Only the lifecycle is wrapped, use
+/// [`as_mut_ptr()`](Self::as_mut_ptr) for everything else.
+#[derive(Debug)]
+pub struct CodecContextGuard {
+    ctx: *mut AVCodecContext,
+}
+
+impl CodecContextGuard {
+    /// Allocate a context with the defaults of `codec`.
+    ///
+    /// # Safety
+    /// `codec` must be null or point to a valid `AVCodec`.
+    pub unsafe fn new(codec: *const AVCodec) -> Result<Self, FfmpegError> {
+        let ctx = ffi::avcodec_alloc_context3(codec);
+        if ctx.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(Self { ctx })
+        }
+    }
+
+    /// Take ownership of a context allocated by `avcodec_alloc_context3()`.
+    ///
+    /// # Safety
+    /// `ctx` must be a valid context not owned by anything else, it's
+    /// dereferenced by `Deref` and `DerefMut`.
+    pub unsafe fn from_raw(ctx: *mut AVCodecContext) -> Self {
+        debug_assert!(!ctx.is_null());
+        Self { ctx }
+    }
+
+    /// Give up ownership of the context, the caller has to free it.
+    pub fn into_raw(self) -> *mut AVCodecContext {
+        let ctx = self.ctx;
+        std::mem::forget(self);
+        ctx
+    }
+
+    /// Fill the context from stream parameters with
+    /// `avcodec_parameters_to_context()`.
+    ///
+    /// # Safety
+    /// `par` must point to valid codec parameters.
+    pub unsafe fn set_parameters(
+        &mut self,
+        par: *const AVCodecParameters,
+    ) -> Result<(), FfmpegError> {
+        match ffi::avcodec_parameters_to_context(self.ctx, par) {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Open the context with the codec it was allocated with. Options the
+    /// codec didn't use are left in `options`.
+    pub fn open(&mut self, options: Option<&mut Dictionary>) -> Result<(), FfmpegError> {
+        let options = options.map_or(ptr::null_mut(), |options| options.as_mut_ptr());
+        match unsafe { ffi::avcodec_open2(self.ctx, ptr::null(), options) } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const AVCodecContext {
+        self.ctx
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVCodecContext {
+        self.ctx
+    }
+}
+
+impl Deref for CodecContextGuard {
+    type Target = AVCodecContext;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ctx }
+    }
+}
+
+impl DerefMut for CodecContextGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ctx }
+    }
+}
+
+impl Drop for CodecContextGuard {
+    fn drop(&mut self) {
+        // Writes null to `ctx`, and does nothing if it's already null.
+        unsafe { ffi::avcodec_free_context(&mut self.ctx) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_rawvideo_decoder() {
+        let codec = unsafe { ffi::avcodec_find_decoder(ffi::AVCodecID_AV_CODEC_ID_RAWVIDEO) };
+        let mut ctx = unsafe { CodecContextGuard::new(codec) }.unwrap();
+        assert_eq!(ctx.codec_id, ffi::AVCodecID_AV_CODEC_ID_RAWVIDEO);
+        ctx.width = 2;
+        ctx.height = 2;
+        ctx.pix_fmt = ffi::AVPixelFormat_AV_PIX_FMT_YUV420P;
+
+        let mut options = Dictionary::new();
+        options.set("not_an_option", "1").unwrap();
+        ctx.open(Some(&mut options)).unwrap();
+        assert_eq!(options.get("not_an_option"), Some("1"));
+        assert_eq!(unsafe { ffi::avcodec_is_open(ctx.as_mut_ptr()) }, 1);
+    }
+
+    #[test]
+    fn test_drop() {
+        let ctx = unsafe { CodecContextGuard::new(ptr::null()) }.unwrap();
+        let raw = ctx.into_raw();
+        let mut ctx = unsafe { CodecContextGuard::from_raw(raw) };
+        // Freeing through the pointer leaves a null context, dropping it is fine.
+        unsafe { ffi::avcodec_free_context(&mut ctx.ctx) };
+        assert!(ctx.as_ptr().is_null());
+        drop(ctx);
+    }
+}
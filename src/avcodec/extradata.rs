@@ -0,0 +1,181 @@
+use crate::ffi::{
+    self, AVCodecContext, AVCodecParameters, FfmpegError, AVERROR_EINVAL, AVERROR_ENOMEM,
+    AV_INPUT_BUFFER_PADDING_SIZE,
+};
+use core::{convert::TryFrom, ptr, slice};
+
+fn check(ret: libc::c_int) -> Result<(), FfmpegError> {
+    if ret < 0 {
+        Err(FfmpegError::from_averror(ret))
+    } else {
+        Ok(())
+    }
+}
+
+unsafe fn get<'a>(extradata: *const u8, size: libc::c_int) -> Option<&'a [u8]> {
+    if extradata.is_null() || size <= 0 {
+        None
+    } else {
+        Some(slice::from_raw_parts(extradata, size as usize))
+    }
+}
+
+unsafe fn set(
+    extradata: &mut *mut u8,
+    extradata_size: &mut libc::c_int,
+    data: &[u8],
+) -> Result<(), FfmpegError> {
+    let size = libc::c_int::try_from(data.len()).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+    let buf = if data.is_empty() {
+        ptr::null_mut()
+    } else {
+        // Zeroed, so the padding is too.
+        let buf =
+            ffi::av_mallocz((data.len() + AV_INPUT_BUFFER_PADDING_SIZE as usize) as _) as *mut u8;
+        if buf.is_null() {
+            return Err(FfmpegError::Av(AVERROR_ENOMEM));
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
+        buf
+    };
+    ffi::av_freep(extradata as *mut *mut u8 as _);
+    *extradata = buf;
+    *extradata_size = size;
+    Ok(())
+}
+
+/// The extradata of codec parameters, `None` if there is none.
+pub fn extradata(par: &AVCodecParameters) -> Option<&[u8]> {
+    unsafe { get(par.extradata, par.extradata_size) }
+}
+
+/// Replace the extradata of codec parameters with a copy of `data`, followed
+/// by `AV_INPUT_BUFFER_PADDING_SIZE` zero bytes. Empty `data` removes it.
+///
+/// # Safety
+/// The current extradata must be null or allocated with `av_malloc()`.
+pub unsafe fn set_extradata(par: &mut AVCodecParameters, data: &[u8]) -> Result<(), FfmpegError> {
+    set(&mut par.extradata, &mut par.extradata_size, data)
+}
+
+/// The extradata of a codec context, `None` if there is none.
+pub fn context_extradata(ctx: &AVCodecContext) -> Option<&[u8]> {
+    unsafe { get(ctx.extradata, ctx.extradata_size) }
+}
+
+/// [`set_extradata()`] for a codec context.
+///
+/// # Safety
+/// The current extradata must be null or allocated with `av_malloc()`.
+pub unsafe fn set_context_extradata(
+    ctx: &mut AVCodecContext,
+    data: &[u8],
+) -> Result<(), FfmpegError> {
+    set(&mut ctx.extradata, &mut ctx.extradata_size, data)
+}
+
+/// Copy `src` into `dst` with `avcodec_parameters_copy()`, extradata
+/// included.
+///
+/// # Safety
+/// The allocated fields of both must come from FFmpeg's allocators.
+pub unsafe fn parameters_copy(
+    dst: &mut AVCodecParameters,
+    src: &AVCodecParameters,
+) -> Result<(), FfmpegError> {
+    check(ffi::avcodec_parameters_copy(dst, src))
+}
+
+/// Fill `par` from a codec context with `avcodec_parameters_from_context()`.
+///
+/// # Safety
+/// The allocated fields of both must come from FFmpeg's allocators.
+pub unsafe fn parameters_from_context(
+    par: &mut AVCodecParameters,
+    ctx: &AVCodecContext,
+) -> Result<(), FfmpegError> {
+    check(ffi::avcodec_parameters_from_context(par, ctx))
+}
+
+/// Fill a codec context from `par` with `avcodec_parameters_to_context()`.
+///
+/// # Safety
+/// The allocated fields of both must come from FFmpeg's allocators.
+pub unsafe fn parameters_to_context(
+    ctx: &mut AVCodecContext,
+    par: &AVCodecParameters,
+) -> Result<(), FfmpegError> {
+    check(ffi::avcodec_parameters_to_context(ctx, par))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    unsafe fn padding(extradata: *const u8, size: libc::c_int) -> &'static [u8] {
+        slice::from_raw_parts(
+            extradata.add(size as usize),
+            AV_INPUT_BUFFER_PADDING_SIZE as usize,
+        )
+    }
+
+    #[test]
+    fn test_parameters_extradata() {
+        unsafe {
+            let mut par = ffi::avcodec_parameters_alloc();
+            assert_eq!(extradata(&*par), None);
+
+            set_extradata(&mut *par, &[1, 2, 3]).unwrap();
+            assert_eq!(extradata(&*par), Some(&[1, 2, 3][..]));
+            assert!(padding((*par).extradata, 3).iter().all(|&b| b == 0));
+
+            // The old buffer is freed, which valgrind or ASan would catch.
+            for len in 1..100 {
+                let data = vec![0xff; len];
+                set_extradata(&mut *par, &data).unwrap();
+                assert_eq!(extradata(&*par), Some(&data[..]));
+                assert!(padding((*par).extradata, len as _).iter().all(|&b| b == 0));
+            }
+
+            let mut copy = ffi::avcodec_parameters_alloc();
+            parameters_copy(&mut *copy, &*par).unwrap();
+            assert_eq!(extradata(&*copy), extradata(&*par));
+            assert_ne!((*copy).extradata, (*par).extradata);
+
+            set_extradata(&mut *par, &[]).unwrap();
+            assert_eq!(extradata(&*par), None);
+            assert!((*par).extradata.is_null());
+            assert_eq!((*par).extradata_size, 0);
+
+            ffi::avcodec_parameters_free(&mut par);
+            ffi::avcodec_parameters_free(&mut copy);
+        }
+    }
+
+    #[test]
+    fn test_context_round_trip() {
+        unsafe {
+            let mut ctx = ffi::avcodec_alloc_context3(ptr::null());
+            set_context_extradata(&mut *ctx, b"extra").unwrap();
+            assert_eq!(context_extradata(&*ctx), Some(&b"extra"[..]));
+            assert!(padding((*ctx).extradata, 5).iter().all(|&b| b == 0));
+            (*ctx).codec_type = ffi::AVMediaType_AVMEDIA_TYPE_VIDEO;
+            (*ctx).width = 16;
+
+            let mut par = ffi::avcodec_parameters_alloc();
+            parameters_from_context(&mut *par, &*ctx).unwrap();
+            assert_eq!(extradata(&*par), Some(&b"extra"[..]));
+            assert_eq!((*par).width, 16);
+
+            set_extradata(&mut *par, b"other").unwrap();
+            (*par).width = 32;
+            parameters_to_context(&mut *ctx, &*par).unwrap();
+            assert_eq!(context_extradata(&*ctx), Some(&b"other"[..]));
+            assert!(padding((*ctx).extradata, 5).iter().all(|&b| b == 0));
+            assert_eq!((*ctx).width, 32);
+
+            ffi::avcodec_parameters_free(&mut par);
+            ffi::avcodec_free_context(&mut ctx);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+pub(crate) mod codec_id;
+#[cfg(feature = "std")]
+pub(crate) mod context;
+pub(crate) mod extradata;
+pub(crate) mod packet;
+#[cfg(feature = "bytes")]
+pub(crate) mod packet_bytes;
+pub(crate) mod parser;
+pub(crate) mod subtitle;
+
+#[cfg(feature = "std")]
+pub use self::context::*;
+#[cfg(feature = "bytes")]
+pub use self::packet_bytes::*;
+pub use self::{codec_id::*, extradata::*, packet::*, parser::*, subtitle::*};
+include!(concat!(env!("OUT_DIR"), "/namespace/avcodec.rs"));
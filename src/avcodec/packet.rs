@@ -0,0 +1,88 @@
+use crate::ffi::{self, AVPacket, FfmpegError, AVERROR_ENOMEM};
+use core::{
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+/// An `AVPacket` from `av_packet_alloc()`, freed with `av_packet_free()` on
+/// drop.
+#[derive(Debug)]
+pub struct Packet {
+    packet: *mut AVPacket,
+}
+
+impl Packet {
+    /// An empty packet.
+    pub fn new() -> Result<Self, FfmpegError> {
+        let packet = unsafe { ffi::av_packet_alloc() };
+        if packet.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(Self { packet })
+        }
+    }
+
+    /// Give up ownership of the packet, the caller has to free it.
+    pub fn into_raw(self) -> *mut AVPacket {
+        let packet = self.packet;
+        core::mem::forget(self);
+        packet
+    }
+
+    /// The payload of the packet.
+    pub fn data(&self) -> &[u8] {
+        if self.data.is_null() || self.size <= 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.data, self.size as usize) }
+        }
+    }
+
+    /// Release the payload and reset the fields, so the packet can be reused.
+    pub fn unref(&mut self) {
+        unsafe { ffi::av_packet_unref(self.packet) }
+    }
+
+    pub fn as_ptr(&self) -> *const AVPacket {
+        self.packet
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVPacket {
+        self.packet
+    }
+}
+
+impl Deref for Packet {
+    type Target = AVPacket;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.packet }
+    }
+}
+
+impl DerefMut for Packet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.packet }
+    }
+}
+
+impl Drop for Packet {
+    fn drop(&mut self) {
+        unsafe { ffi::av_packet_free(&mut self.packet) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_packet() {
+        let mut packet = Packet::new().unwrap();
+        assert!(packet.data().is_empty());
+        assert_eq!(unsafe { ffi::av_new_packet(packet.as_mut_ptr(), 4) }, 0);
+        assert_eq!(packet.data(), [0; 4]);
+        packet.unref();
+        assert!(packet.data().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Packets sharing their payload with `bytes::Bytes`.
+
+use crate::ffi::{
+    self, AVBufferRef, FfmpegError, Packet, AVERROR_EINVAL, AVERROR_ENOMEM,
+    AV_INPUT_BUFFER_PADDING_SIZE,
+};
+use bytes::{Bytes, BytesMut};
+use std::{convert::TryFrom, ptr, slice};
+
+const PADDING: usize = AV_INPUT_BUFFER_PADDING_SIZE as usize;
+
+unsafe extern "C" fn free_bytes(opaque: *mut libc::c_void, _data: *mut u8) {
+    drop(Box::from_raw(opaque as *mut BytesMut));
+}
+
+/// A packet with the payload of `b`.
+///
+/// Decoders may read up to `AV_INPUT_BUFFER_PADDING_SIZE` bytes past the end
+/// of a payload, so they have to be allocated and zeroed. `Bytes` can't tell
+/// whether they are, so the payload is only used without a copy if `b` is the
+/// only reference to its buffer and has that much spare capacity (e.g. it's
+/// frozen from a `BytesMut::with_capacity(len + AV_INPUT_BUFFER_PADDING_SIZE)`).
+/// The padding is zeroed then, and the buffer is freed when FFmpeg drops the
+/// last reference to it. Otherwise the payload is copied into a padded packet
+/// buffer.
+pub fn packet_from_bytes(b: Bytes) -> Result<Packet, FfmpegError> {
+    let size = libc::c_int::try_from(b.len()).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+    let mut packet = Packet::new()?;
+    let b = match b.try_into_mut() {
+        Ok(mut b) if b.capacity() - b.len() >= PADDING => {
+            for byte in &mut b.spare_capacity_mut()[..PADDING] {
+                *byte = core::mem::MaybeUninit::new(0);
+            }
+            let data = b.as_mut_ptr();
+            let opaque = Box::into_raw(Box::new(b));
+            let buf =
+                unsafe { ffi::av_buffer_create(data, size, Some(free_bytes), opaque as _, 0) };
+            if buf.is_null() {
+                drop(unsafe { Box::from_raw(opaque) });
+                return Err(FfmpegError::Av(AVERROR_ENOMEM));
+            }
+            packet.buf = buf;
+            packet.data = data;
+            packet.size = size;
+            return Ok(packet);
+        }
+        Ok(b) => b.freeze(),
+        Err(b) => b,
+    };
+    match unsafe { ffi::av_new_packet(packet.as_mut_ptr(), size) } {
+        ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+        _ => {
+            unsafe { ptr::copy_nonoverlapping(b.as_ptr(), packet.data, b.len()) };
+            Ok(packet)
+        }
+    }
+}
+
+/// A reference to the buffer of a packet, kept by the `Bytes` of
+/// [`packet_to_bytes()`].
+struct PacketBuffer {
+    buf: *mut AVBufferRef,
+    data: *const u8,
+    len: usize,
+}
+
+// The reference count of `AVBufferRef`s is atomic, and FFmpeg doesn't write
+// into buffers with more than one reference.
+unsafe impl Send for PacketBuffer {}
+
+impl AsRef<[u8]> for PacketBuffer {
+    fn as_ref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Drop for PacketBuffer {
+    fn drop(&mut self) {
+        unsafe { ffi::av_buffer_unref(&mut self.buf) }
+    }
+}
+
+/// The payload of a packet as `Bytes`. A reference counted payload is shared
+/// by taking a new reference to its buffer, the payload of a packet without a
+/// buffer is copied.
+pub fn packet_to_bytes(packet: &Packet) -> Bytes {
+    let data = packet.data();
+    if data.is_empty() {
+        return Bytes::new();
+    }
+    let buf = if packet.buf.is_null() {
+        ptr::null_mut()
+    } else {
+        unsafe { ffi::av_buffer_ref(packet.buf) }
+    };
+    if buf.is_null() {
+        Bytes::copy_from_slice(data)
+    } else {
+        Bytes::from_owner(PacketBuffer {
+            buf,
+            data: data.as_ptr(),
+            len: data.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_zero_copy() {
+        let mut b = BytesMut::with_capacity(5 + PADDING);
+        b.extend_from_slice(b"hello");
+        let b = b.freeze();
+        let data = b.as_ptr();
+
+        let packet = packet_from_bytes(b).unwrap();
+        assert_eq!(packet.data as *const u8, data);
+        assert_eq!(packet.data(), b"hello");
+        let padding = unsafe { slice::from_raw_parts(packet.data.add(5), PADDING) };
+        assert!(padding.iter().all(|&byte| byte == 0));
+
+        let shared = packet_to_bytes(&packet);
+        assert_eq!(shared.as_ptr(), data);
+        assert_eq!(unsafe { ffi::av_buffer_get_ref_count(packet.buf) }, 2);
+        drop(shared);
+        assert_eq!(unsafe { ffi::av_buffer_get_ref_count(packet.buf) }, 1);
+
+        // The payload outlives the packet.
+        let shared = packet_to_bytes(&packet);
+        drop(packet);
+        assert_eq!(&shared[..], b"hello");
+    }
+
+    struct Owner(Arc<AtomicUsize>);
+
+    impl AsRef<[u8]> for Owner {
+        fn as_ref(&self) -> &[u8] {
+            b"hello"
+        }
+    }
+
+    impl Drop for Owner {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_copy() {
+        // Without spare capacity the payload is copied, and the `Bytes` is
+        // released right away.
+        let drops = Arc::new(AtomicUsize::new(0));
+        let b = Bytes::from_owner(Owner(drops.clone()));
+        let data = b.as_ptr();
+        let packet = packet_from_bytes(b).unwrap();
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert_ne!(packet.data as *const u8, data);
+        assert_eq!(packet.data(), b"hello");
+        assert!(!packet.buf.is_null());
+
+        let b = Bytes::from_static(b"static");
+        let packet = packet_from_bytes(b.clone()).unwrap();
+        assert_ne!(packet.data as *const u8, b.as_ptr());
+        assert_eq!(packet.data(), b"static");
+
+        assert!(packet_from_bytes(Bytes::new()).unwrap().data().is_empty());
+    }
+
+    #[test]
+    fn test_packet_without_buffer_to_bytes() {
+        let payload = b"abc";
+        let mut packet = Packet::new().unwrap();
+        packet.data = payload.as_ptr() as *mut u8;
+        packet.size = 3;
+        let b = packet_to_bytes(&packet);
+        assert_ne!(b.as_ptr(), payload.as_ptr());
+        assert_eq!(&b[..], payload);
+        assert!(packet_to_bytes(&Packet::new().unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::avutil::ffi_enum::FfiEnum;
+use crate::ffi::{
+    self, AVCodecContext, AVCodecID, AVCodecParserContext, FfmpegError, Packet, AVERROR_ENOMEM,
+    AV_NOPTS_VALUE, AV_PKT_FLAG_KEY,
+};
+use core::ptr;
+
+/// Splits an elementary stream into packets with `av_parser_parse2()`, closed
+/// with `av_parser_close()` on drop.
+#[derive(Debug)]
+pub struct Parser {
+    parser: *mut AVCodecParserContext,
+    /// Byte offset of the next input in the stream.
+    pos: i64,
+}
+
+impl Parser {
+    /// Fails with `FfmpegError::Unsupported` if FFmpeg has no parser for the
+    /// codec.
+    pub fn new(codec_id: AVCodecID) -> Result<Self, FfmpegError> {
+        let parser = unsafe { ffi::av_parser_init(codec_id.to_int()) };
+        if parser.is_null() {
+            Err(FfmpegError::Unsupported)
+        } else {
+            Ok(Self { parser, pos: 0 })
+        }
+    }
+
+    /// Feed the next bytes of the stream, with the `pts` and `dts` of the
+    /// packet they start (`AV_NOPTS_VALUE` if unknown), yielding the packets
+    /// completed by them. `av_parser_parse2()` consumes a part of the input
+    /// per call, the iterator calls it until all is consumed (the parser
+    /// buffers incomplete packets): what's left when it's dropped early isn't
+    /// parsed. It ends after the first error. The packets carry the `pts`,
+    /// `dts` and `pos` found by the parser, and `AV_PKT_FLAG_KEY` for
+    /// keyframes.
+    ///
+    /// # Safety
+    /// `ctx` must be a valid codec context of the parsed codec.
+    pub unsafe fn parse<'a>(
+        &'a mut self,
+        ctx: *mut AVCodecContext,
+        data: &'a [u8],
+        pts: i64,
+        dts: i64,
+    ) -> impl Iterator<Item = Result<Packet, FfmpegError>> + 'a {
+        let mut data = data;
+        let mut done = false;
+        core::iter::from_fn(move || {
+            while !done && !data.is_empty() {
+                let (consumed, packet) = self.parse_once(ctx, data, pts, dts);
+                data = &data[consumed.min(data.len())..];
+                // Nothing consumed and nothing produced, don't spin.
+                done = matches!(packet, Some(Err(_))) || consumed == 0 && packet.is_none();
+                if packet.is_some() {
+                    return packet;
+                }
+            }
+            None
+        })
+    }
+
+    /// Signal the end of the stream, yielding the packets still buffered. It
+    /// ends after the first error.
+    ///
+    /// # Safety
+    /// `ctx` must be a valid codec context of the parsed codec.
+    pub unsafe fn flush(
+        &mut self,
+        ctx: *mut AVCodecContext,
+    ) -> impl Iterator<Item = Result<Packet, FfmpegError>> + '_ {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let packet = self.parse_once(ctx, &[], AV_NOPTS_VALUE, AV_NOPTS_VALUE).1;
+            done = !matches!(packet, Some(Ok(_)));
+            packet
+        })
+    }
+
+    /// One `av_parser_parse2()` call, returns the number of bytes consumed and
+    /// the packet completed, if any.
+    unsafe fn parse_once(
+        &mut self,
+        ctx: *mut AVCodecContext,
+        data: &[u8],
+        pts: i64,
+        dts: i64,
+    ) -> (usize, Option<Result<Packet, FfmpegError>>) {
+        let mut out_data = ptr::null_mut();
+        let mut out_size = 0;
+        let consumed = ffi::av_parser_parse2(
+            self.parser,
+            ctx,
+            &mut out_data,
+            &mut out_size,
+            if data.is_empty() {
+                ptr::null()
+            } else {
+                data.as_ptr()
+            },
+            data.len() as _,
+            pts,
+            dts,
+            self.pos,
+        );
+        if consumed < 0 {
+            return (0, Some(Err(FfmpegError::from_averror(consumed))));
+        }
+        self.pos += consumed as i64;
+        if out_size <= 0 {
+            return (consumed as usize, None);
+        }
+        (consumed as usize, Some(self.packet(out_data, out_size)))
+    }
+
+    unsafe fn packet(&self, data: *const u8, size: libc::c_int) -> Result<Packet, FfmpegError> {
+        let mut packet = Packet::new()?;
+        if ffi::av_new_packet(packet.as_mut_ptr(), size) < 0 {
+            return Err(FfmpegError::Av(AVERROR_ENOMEM));
+        }
+        ptr::copy_nonoverlapping(data, packet.data, size as usize);
+        let parser = &*self.parser;
+        packet.pts = parser.pts;
+        packet.dts = parser.dts;
+        packet.pos = parser.pos;
+        if parser.key_frame == 1 {
+            packet.flags |= AV_PKT_FLAG_KEY as libc::c_int;
+        }
+        Ok(packet)
+    }
+}
+
+impl Drop for Parser {
+    fn drop(&mut self) {
+        unsafe { ffi::av_parser_close(self.parser) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_annex_b_h264() {
+        // Three access units, each an access unit delimiter and a slice with
+        // first_mb_in_slice = 0.
+        let stream = [
+            0, 0, 0, 1, 0x09, 0x10, 0, 0, 0, 1, 0x65, 0x88, 0x84, 0x21, 0xa0, //
+            0, 0, 0, 1, 0x09, 0x30, 0, 0, 0, 1, 0x41, 0x9a, 0x21, 0x6c, 0x42, //
+            0, 0, 0, 1, 0x09, 0x30, 0, 0, 0, 1, 0x41, 0x9a, 0x42, 0x3c, 0x21, //
+        ];
+        let mut parser = Parser::new(ffi::AVCodecID_AV_CODEC_ID_H264).unwrap();
+        unsafe {
+            let codec = ffi::avcodec_find_decoder(ffi::AVCodecID_AV_CODEC_ID_H264);
+            let mut ctx = ffi::avcodec_alloc_context3(codec);
+
+            // Fed in small pieces, the packets are only complete once the next
+            // access unit starts.
+            let mut packets = 0;
+            let mut positions = [0; 3];
+            for chunk in stream.chunks(4) {
+                for packet in parser.parse(ctx, chunk, AV_NOPTS_VALUE, AV_NOPTS_VALUE) {
+                    let packet = packet.unwrap();
+                    assert_eq!(packet.data().len(), 15);
+                    assert_eq!(packet.data()[..5], [0, 0, 0, 1, 0x09]);
+                    positions[packets] = packet.pos;
+                    packets += 1;
+                }
+            }
+            assert_eq!(packets, 2);
+            for packet in parser.flush(ctx) {
+                assert_eq!(packet.unwrap().data().len(), 15);
+                packets += 1;
+            }
+            assert_eq!(packets, 3);
+            assert_eq!(positions[..2], [0, 15]);
+
+            // Fed by access unit, the packets take the timestamps of the input
+            // they start in.
+            let mut parser = Parser::new(ffi::AVCodecID_AV_CODEC_ID_H264).unwrap();
+            let mut packets = 0;
+            let mut timestamps = [(0, 0); 3];
+            for (i, access_unit) in stream.chunks(15).enumerate() {
+                let ts = i as i64 * 10;
+                for packet in parser.parse(ctx, access_unit, ts, ts) {
+                    let packet = packet.unwrap();
+                    timestamps[packets] = (packet.pts, packet.dts);
+                    packets += 1;
+                }
+            }
+            assert_eq!(packets, 2);
+            assert_eq!(timestamps[..2], [(0, 0), (10, 10)]);
+
+            ffi::avcodec_free_context(&mut ctx);
+        }
+        assert_eq!(
+            Parser::new(ffi::AVCodecID_AV_CODEC_ID_NONE).unwrap_err(),
+            FfmpegError::Unsupported
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::ffi::{self, AVCodecContext, AVPacket, AVSubtitle, AVSubtitleRect, FfmpegError};
+use core::{ffi::CStr, mem, slice};
+
+/// A decoded `AVSubtitle`, freed with `avsubtitle_free()` on drop.
+pub struct Subtitle(AVSubtitle);
+
+/// Content of one rect of a [`Subtitle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleRect<'a> {
+    /// A paletted bitmap: `data` holds `h` rows of `linesize` bytes, each byte
+    /// is an index into `palette` (RGBA colors in native endian).
+    Bitmap {
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        data: &'a [u8],
+        linesize: i32,
+        palette: &'a [u32],
+    },
+    /// An ASS/SSA event line.
+    Ass(&'a str),
+    /// Plain text.
+    Text(&'a str),
+}
+
+unsafe fn c_str<'a>(s: *const libc::c_char) -> &'a str {
+    if s.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(s).to_str().unwrap_or("")
+    }
+}
+
+unsafe fn subtitle_rect(rect: &AVSubtitleRect) -> Option<SubtitleRect<'_>> {
+    match rect.type_ {
+        ffi::AVSubtitleType_SUBTITLE_BITMAP => {
+            let len = (rect.linesize[0].max(0) as usize).checked_mul(rect.h.max(0) as usize);
+            let data = match len {
+                Some(len) if len > 0 && !rect.data[0].is_null() => {
+                    slice::from_raw_parts(rect.data[0], len)
+                }
+                _ => &[][..],
+            };
+            let palette = if rect.data[1].is_null() || rect.nb_colors <= 0 {
+                &[][..]
+            } else {
+                slice::from_raw_parts(rect.data[1] as *const u32, rect.nb_colors as usize)
+            };
+            Some(SubtitleRect::Bitmap {
+                x: rect.x,
+                y: rect.y,
+                w: rect.w,
+                h: rect.h,
+                data,
+                linesize: rect.linesize[0],
+                palette,
+            })
+        }
+        ffi::AVSubtitleType_SUBTITLE_ASS => Some(SubtitleRect::Ass(c_str(rect.ass))),
+        ffi::AVSubtitleType_SUBTITLE_TEXT => Some(SubtitleRect::Text(c_str(rect.text))),
+        _ => None,
+    }
+}
+
+impl Subtitle {
+    /// Decode a subtitle packet, `Ok(None)` if the packet didn't produce a
+    /// subtitle.
+    ///
+    /// # Safety
+    /// `ctx` must be an opened subtitle decoder context.
+    pub unsafe fn decode(
+        ctx: *mut AVCodecContext,
+        packet: &mut AVPacket,
+    ) -> Result<Option<Subtitle>, FfmpegError> {
+        let mut subtitle: AVSubtitle = mem::zeroed();
+        let mut got_subtitle = 0;
+        let ret = ffi::avcodec_decode_subtitle2(ctx, &mut subtitle, &mut got_subtitle, packet);
+        if ret < 0 {
+            Err(FfmpegError::from_averror(ret))
+        } else if got_subtitle == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Subtitle(subtitle)))
+        }
+    }
+
+    /// The wrapped `AVSubtitle`.
+    pub fn as_av(&self) -> &AVSubtitle {
+        &self.0
+    }
+
+    /// Display time relative to the packet pts, in milliseconds.
+    pub fn display_time(&self) -> (u32, u32) {
+        (self.0.start_display_time, self.0.end_display_time)
+    }
+
+    /// The rects of the subtitle, rects of an unknown type are skipped.
+    pub fn rects(&self) -> impl Iterator<Item = SubtitleRect<'_>> {
+        let rects = if self.0.rects.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(self.0.rects, self.0.num_rects as usize) }
+        };
+        rects
+            .iter()
+            .filter_map(|rect| unsafe { rect.as_ref().and_then(|rect| subtitle_rect(rect)) })
+    }
+}
+
+impl Drop for Subtitle {
+    fn drop(&mut self) {
+        unsafe { ffi::avsubtitle_free(&mut self.0) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::ptr;
+
+    #[test]
+    fn test_decode_subrip() {
+        unsafe {
+            let codec = ffi::avcodec_find_decoder(ffi::AVCodecID_AV_CODEC_ID_SUBRIP);
+            assert!(!codec.is_null());
+            let mut ctx = ffi::avcodec_alloc_context3(codec);
+            assert_eq!(ffi::avcodec_open2(ctx, codec, ptr::null_mut()), 0);
+
+            let text = b"Hello <b>world</b>\n";
+            let mut packet = ffi::av_packet_alloc();
+            assert_eq!(ffi::av_new_packet(packet, text.len() as _), 0);
+            ptr::copy_nonoverlapping(text.as_ptr(), (*packet).data, text.len());
+            (*packet).pts = 0;
+            (*packet).duration = 1000;
+
+            let subtitle = Subtitle::decode(ctx, &mut *packet).unwrap().unwrap();
+            let mut rects = subtitle.rects();
+            match rects.next() {
+                Some(SubtitleRect::Ass(ass)) => assert!(ass.ends_with("Hello {\\b1}world{\\b0}")),
+                rect => panic!("Unexpected rect {:?}", rect),
+            }
+            assert!(rects.next().is_none());
+            drop(rects);
+            drop(subtitle);
+
+            ffi::av_packet_free(&mut packet);
+            ffi::avcodec_free_context(&mut ctx);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Reading an `AVIOContext` from a tokio `AsyncRead + AsyncSeek`.
+
+use crate::ffi::{
+    self, AVIOContext, FfmpegError, AVERROR, AVERROR_EINVAL, AVERROR_ENOMEM, AVERROR_EOF,
+    AVERROR_EXIT, AVSEEK_FORCE, AVSEEK_SIZE,
+};
+use std::{convert::TryFrom, io, io::SeekFrom, pin::Pin, ptr};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt},
+    runtime::Handle,
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+type Reply<T> = oneshot::Sender<io::Result<T>>;
+
+enum Request {
+    Read(usize, Reply<Vec<u8>>),
+    Seek(SeekFrom, Reply<u64>),
+    Size(Reply<u64>),
+}
+
+// `dyn AsyncRead + AsyncSeek` isn't allowed, so combine them.
+trait AsyncReadSeek: AsyncRead + AsyncSeek {}
+impl<T: AsyncRead + AsyncSeek + ?Sized> AsyncReadSeek for T {}
+
+type Reader = Pin<Box<dyn AsyncReadSeek + Send>>;
+
+async fn size(reader: &mut Reader) -> io::Result<u64> {
+    let position = reader.stream_position().await?;
+    let size = reader.seek(SeekFrom::End(0)).await?;
+    reader.seek(SeekFrom::Start(position)).await?;
+    Ok(size)
+}
+
+async fn serve(mut reader: Reader, mut requests: mpsc::Receiver<Request>) {
+    while let Some(request) = requests.recv().await {
+        // The requester may be gone already, ignore send errors.
+        match request {
+            Request::Read(len, reply) => {
+                let mut buf = vec![0; len];
+                let result = reader.read(&mut buf).await.map(|n| {
+                    buf.truncate(n);
+                    buf
+                });
+                let _ = reply.send(result);
+            }
+            Request::Seek(pos, reply) => {
+                let _ = reply.send(reader.seek(pos).await);
+            }
+            Request::Size(reply) => {
+                let _ = reply.send(size(&mut reader).await);
+            }
+        }
+    }
+}
+
+fn io_averror(e: &io::Error) -> libc::c_int {
+    AVERROR(e.raw_os_error().map_or(ffi::EIO, |errno| errno as u32))
+}
+
+/// Send a request to the reader task and wait for the reply. Fails with
+/// `AVERROR_EXIT` if the task is gone, e.g. because it was cancelled.
+unsafe fn request<T>(
+    opaque: *mut libc::c_void,
+    request: impl FnOnce(Reply<T>) -> Request,
+) -> Result<T, libc::c_int> {
+    let requests = &*(opaque as *const mpsc::Sender<Request>);
+    let (reply, response) = oneshot::channel();
+    requests
+        .blocking_send(request(reply))
+        .map_err(|_| AVERROR_EXIT)?;
+    match response.blocking_recv() {
+        Ok(result) => result.map_err(|e| io_averror(&e)),
+        Err(_) => Err(AVERROR_EXIT),
+    }
+}
+
+unsafe extern "C" fn read_packet(
+    opaque: *mut libc::c_void,
+    buf: *mut u8,
+    buf_size: libc::c_int,
+) -> libc::c_int {
+    match request(opaque, |reply| {
+        Request::Read(buf_size.max(0) as usize, reply)
+    }) {
+        Ok(data) if data.is_empty() => AVERROR_EOF,
+        Ok(data) => {
+            ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
+            data.len() as libc::c_int
+        }
+        Err(errnum) => errnum,
+    }
+}
+
+unsafe extern "C" fn seek(opaque: *mut libc::c_void, offset: i64, whence: libc::c_int) -> i64 {
+    let whence = whence & !(AVSEEK_FORCE as libc::c_int);
+    let result = if whence & AVSEEK_SIZE as libc::c_int != 0 {
+        request(opaque, Request::Size)
+    } else {
+        let pos = match whence {
+            libc::SEEK_SET => match u64::try_from(offset) {
+                Ok(offset) => SeekFrom::Start(offset),
+                Err(_) => return AVERROR_EINVAL as i64,
+            },
+            libc::SEEK_CUR => SeekFrom::Current(offset),
+            libc::SEEK_END => SeekFrom::End(offset),
+            _ => return AVERROR_EINVAL as i64,
+        };
+        request(opaque, |reply| Request::Seek(pos, reply))
+    };
+    match result {
+        Ok(pos) => pos as i64,
+        Err(errnum) => errnum as i64,
+    }
+}
+
+/// A read-only `AVIOContext` whose data comes from a tokio reader, e.g. a
+/// `TcpStream` wrapper or an object storage client, without spooling it to a
+/// file first.
+///
+/// The reader is moved into a task of the runtime, and the `AVIOContext`
+/// callbacks pass read and seek requests to it over a channel, one at a time.
+/// The callbacks block the calling thread until the task answers, so FFmpeg
+/// functions using the context (`avformat_open_input()`, `av_read_frame()`...)
+/// must not be called on a runtime thread: use `spawn_blocking()` or a thread
+/// of your own.
+///
+/// Dropping the bridge or calling [`cancel()`](Self::cancel) aborts the task.
+/// A read FFmpeg is waiting for then fails with `AVERROR_EXIT` instead of
+/// blocking forever, as do all following ones. The same happens when the
+/// runtime shuts down.
+#[derive(Debug)]
+pub struct AsyncIoBridge {
+    avio: *mut AVIOContext,
+    requests: *mut mpsc::Sender<Request>,
+    task: JoinHandle<()>,
+}
+
+// The context is only used through `&mut self`, and the callbacks only touch
+// the channel sender, which is `Send`.
+unsafe impl Send for AsyncIoBridge {}
+
+impl AsyncIoBridge {
+    /// Create the context with an internal buffer of `buf_size` bytes, with
+    /// `avio_alloc_context()`. The reader task is spawned on the current
+    /// runtime.
+    ///
+    /// # Panics
+    /// If called outside of a tokio runtime.
+    pub fn new(
+        reader: impl AsyncRead + AsyncSeek + Send + 'static,
+        buf_size: usize,
+    ) -> Result<Self, FfmpegError> {
+        let buf_size =
+            libc::c_int::try_from(buf_size).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+        // Only one request is in flight at a time, so a single slot is enough
+        // and FFmpeg never runs ahead of the reader.
+        let (sender, receiver) = mpsc::channel(1);
+        let task = Handle::current().spawn(serve(Box::pin(reader), receiver));
+        let requests = Box::into_raw(Box::new(sender));
+        unsafe {
+            let buffer = ffi::av_malloc(buf_size as _) as *mut u8;
+            let avio = if buffer.is_null() {
+                ptr::null_mut()
+            } else {
+                ffi::avio_alloc_context(
+                    buffer,
+                    buf_size,
+                    0,
+                    requests as _,
+                    Some(read_packet),
+                    None,
+                    Some(seek),
+                )
+            };
+            if avio.is_null() {
+                ffi::av_free(buffer as _);
+                drop(Box::from_raw(requests));
+                task.abort();
+                return Err(FfmpegError::Av(AVERROR_ENOMEM));
+            }
+            Ok(Self {
+                avio,
+                requests,
+                task,
+            })
+        }
+    }
+
+    /// The context to read from, e.g. to assign to `AVFormatContext.pb`
+    /// together with `AVFMT_FLAG_CUSTOM_IO`. It's invalid once `self` is
+    /// dropped, so close the format context first.
+    pub fn as_avio(&mut self) -> *mut AVIOContext {
+        self.avio
+    }
+
+    /// Abort the reader task. Reads from the context fail with
+    /// `AVERROR_EXIT` from now on, including one FFmpeg is waiting for.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for AsyncIoBridge {
+    fn drop(&mut self) {
+        self.task.abort();
+        unsafe {
+            // FFmpeg may have replaced the buffer, so free the current one.
+            ffi::av_freep(&mut (*self.avio).buffer as *mut _ as _);
+            ffi::avio_context_free(&mut self.avio);
+            drop(Box::from_raw(self.requests));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::{Packet, AVFMT_FLAG_CUSTOM_IO};
+    use std::io::Cursor;
+    use tokio::runtime::{Builder, Runtime};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    /// Run `f` on a blocking thread of `runtime`, while the runtime drives the
+    /// reader task.
+    fn run_blocking<T: Send + 'static>(
+        runtime: &Runtime,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> T {
+        runtime
+            .block_on(async { tokio::task::spawn_blocking(f).await })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_demux_from_async_reader() {
+        // A 2x2 yuv4mpeg file with two frames.
+        let mut y4m = b"YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C420jpeg\n".to_vec();
+        for _ in 0..2 {
+            y4m.extend_from_slice(b"FRAME\n");
+            y4m.extend_from_slice(&[16, 16, 16, 16, 128, 128]);
+        }
+        let len = y4m.len() as i64;
+
+        let runtime = runtime();
+        let mut bridge = runtime
+            .block_on(async { AsyncIoBridge::new(Cursor::new(y4m), 16) })
+            .unwrap();
+        let packets = run_blocking(&runtime, move || unsafe {
+            assert_eq!(ffi::avio_size(bridge.as_avio()), len);
+
+            let mut ctx = ffi::avformat_alloc_context();
+            (*ctx).pb = bridge.as_avio();
+            (*ctx).flags |= AVFMT_FLAG_CUSTOM_IO as libc::c_int;
+            assert_eq!(
+                ffi::avformat_open_input(&mut ctx, ptr::null(), ptr::null_mut(), ptr::null_mut()),
+                0
+            );
+            let mut packet = Packet::new().unwrap();
+            let mut packets = 0;
+            while ffi::av_read_frame(ctx, packet.as_mut_ptr()) >= 0 {
+                assert_eq!(packet.size, 6);
+                packet.unref();
+                packets += 1;
+            }
+            ffi::avformat_close_input(&mut ctx);
+            drop(bridge);
+            packets
+        });
+        assert_eq!(packets, 2);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let runtime = runtime();
+        let mut bridge = runtime
+            .block_on(async { AsyncIoBridge::new(Cursor::new(vec![0u8; 64]), 16) })
+            .unwrap();
+        bridge.cancel();
+        let ret = run_blocking(&runtime, move || {
+            let mut buf = [0u8; 8];
+            unsafe { ffi::avio_read(bridge.as_avio(), buf.as_mut_ptr(), 8) }
+        });
+        assert_eq!(ret, AVERROR_EXIT);
+
+        // Shutting the runtime down doesn't leave reads waiting either.
+        let mut bridge = runtime
+            .block_on(async { AsyncIoBridge::new(Cursor::new(vec![0u8; 64]), 16) })
+            .unwrap();
+        drop(runtime);
+        let mut buf = [0u8; 8];
+        let ret = unsafe { ffi::avio_read(bridge.as_avio(), buf.as_mut_ptr(), 8) };
+        assert_eq!(ret, AVERROR_EXIT);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::ffi::{
+    self, AVFormatContext, AVInputFormat, AVStream, Dictionary, FfmpegError, Packet, Recv,
+    AVERROR_EINVAL, AVFMT_NOFILE, AVIO_FLAG_WRITE,
+};
+use std::{ffi::CString, ptr, slice};
+
+fn c_string(s: &str) -> Result<CString, FfmpegError> {
+    CString::new(s).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))
+}
+
+fn check(ret: libc::c_int) -> Result<(), FfmpegError> {
+    if ret < 0 {
+        Err(FfmpegError::from_averror(ret))
+    } else {
+        Ok(())
+    }
+}
+
+unsafe fn streams<'a>(ctx: *const AVFormatContext) -> &'a [*mut AVStream] {
+    if (*ctx).streams.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts((*ctx).streams, (*ctx).nb_streams as usize)
+    }
+}
+
+/// An opened demuxer, closed with `avformat_close_input()` on drop.
+#[derive(Debug)]
+pub struct InputFormatContext {
+    ctx: *mut AVFormatContext,
+}
+
+impl InputFormatContext {
+    /// Open an input with `avformat_open_input()`, probing the format unless
+    /// `fmt` is given. Options the demuxer didn't use are left in `options`.
+    ///
+    /// # Safety
+    /// `fmt` must point to a valid `AVInputFormat` if given.
+    pub unsafe fn open(
+        url: &str,
+        fmt: Option<*const AVInputFormat>,
+        options: Option<&mut Dictionary>,
+    ) -> Result<Self, FfmpegError> {
+        let url = c_string(url)?;
+        // On failure the context is freed and null is written back, so there
+        // is nothing to clean up.
+        let mut ctx = ptr::null_mut();
+        check(ffi::avformat_open_input(
+            &mut ctx,
+            url.as_ptr(),
+            fmt.unwrap_or(ptr::null()) as _,
+            options.map_or(ptr::null_mut(), |options| options.as_mut_ptr()),
+        ))?;
+        Ok(Self { ctx })
+    }
+
+    /// Read some packets to fill in the stream information, with
+    /// `avformat_find_stream_info()`.
+    pub fn find_stream_info(&mut self) -> Result<(), FfmpegError> {
+        check(unsafe { ffi::avformat_find_stream_info(self.ctx, ptr::null_mut()) })
+    }
+
+    pub fn streams(&self) -> &[*mut AVStream] {
+        unsafe { streams(self.ctx) }
+    }
+
+    /// Read the next packet into `packet` (which is unreferenced first).
+    pub fn read_frame(&mut self, packet: &mut Packet) -> Recv {
+        packet.unref();
+        Recv::from_ret(unsafe { ffi::av_read_frame(self.ctx, packet.as_mut_ptr()) })
+    }
+
+    pub fn as_ptr(&self) -> *const AVFormatContext {
+        self.ctx
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVFormatContext {
+        self.ctx
+    }
+}
+
+impl Drop for InputFormatContext {
+    fn drop(&mut self) {
+        unsafe { ffi::avformat_close_input(&mut self.ctx) }
+    }
+}
+
+/// A muxer context, freed with `avformat_free_context()` on drop. The output
+/// file opened by [`OutputFormatContext::create()`] is closed too.
+#[derive(Debug)]
+pub struct OutputFormatContext {
+    ctx: *mut AVFormatContext,
+}
+
+impl OutputFormatContext {
+    /// Allocate a muxer context with `avformat_alloc_output_context2()` for
+    /// `format_name` or else the format guessed from `filename`, and open
+    /// `filename` for writing if the muxer needs a file.
+    pub fn create(format_name: Option<&str>, filename: &str) -> Result<Self, FfmpegError> {
+        let format_name = format_name.map(c_string).transpose()?;
+        let filename = c_string(filename)?;
+        let mut ctx = ptr::null_mut();
+        check(unsafe {
+            ffi::avformat_alloc_output_context2(
+                &mut ctx,
+                ptr::null_mut(),
+                format_name
+                    .as_ref()
+                    .map_or(ptr::null(), |name| name.as_ptr()),
+                filename.as_ptr(),
+            )
+        })?;
+        let output = Self { ctx };
+        if unsafe { (*(*ctx).oformat).flags } & AVFMT_NOFILE as libc::c_int == 0 {
+            check(unsafe {
+                ffi::avio_open(&mut (*ctx).pb, filename.as_ptr(), AVIO_FLAG_WRITE as _)
+            })?;
+        }
+        Ok(output)
+    }
+
+    /// Add a stream with `avformat_new_stream()`.
+    pub fn new_stream(&mut self) -> Result<*mut AVStream, FfmpegError> {
+        let stream = unsafe { ffi::avformat_new_stream(self.ctx, ptr::null()) };
+        if stream.is_null() {
+            Err(FfmpegError::Av(ffi::AVERROR_ENOMEM))
+        } else {
+            Ok(stream)
+        }
+    }
+
+    pub fn streams(&self) -> &[*mut AVStream] {
+        unsafe { streams(self.ctx) }
+    }
+
+    /// Options the muxer didn't use are left in `options`.
+    pub fn write_header(&mut self, options: Option<&mut Dictionary>) -> Result<(), FfmpegError> {
+        check(unsafe {
+            ffi::avformat_write_header(
+                self.ctx,
+                options.map_or(ptr::null_mut(), |options| options.as_mut_ptr()),
+            )
+        })
+    }
+
+    /// Write a packet with `av_interleaved_write_frame()`, which takes the
+    /// payload of the packet.
+    pub fn write_frame(&mut self, packet: &mut Packet) -> Result<(), FfmpegError> {
+        check(unsafe { ffi::av_interleaved_write_frame(self.ctx, packet.as_mut_ptr()) })
+    }
+
+    pub fn write_trailer(&mut self) -> Result<(), FfmpegError> {
+        check(unsafe { ffi::av_write_trailer(self.ctx) })
+    }
+
+    pub fn as_ptr(&self) -> *const AVFormatContext {
+        self.ctx
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVFormatContext {
+        self.ctx
+    }
+}
+
+impl Drop for OutputFormatContext {
+    fn drop(&mut self) {
+        unsafe {
+            if (*(*self.ctx).oformat).flags & AVFMT_NOFILE as libc::c_int == 0 {
+                ffi::avio_closep(&mut (*self.ctx).pb);
+            }
+            ffi::avformat_free_context(self.ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_remux() {
+        // A 2x2 yuv4mpeg file with two frames.
+        let mut y4m = b"YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C420jpeg\n".to_vec();
+        for _ in 0..2 {
+            y4m.extend_from_slice(b"FRAME\n");
+            y4m.extend_from_slice(&[16, 16, 16, 16, 128, 128]);
+        }
+        let dir = std::env::temp_dir();
+        let input_path = dir.join("rusty_ffmpeg_remux.y4m");
+        let output_path = dir.join("rusty_ffmpeg_remux.mkv");
+        fs::write(&input_path, y4m).unwrap();
+
+        let mut input =
+            unsafe { InputFormatContext::open(input_path.to_str().unwrap(), None, None) }.unwrap();
+        input.find_stream_info().unwrap();
+        assert_eq!(input.streams().len(), 1);
+
+        let mut output = OutputFormatContext::create(None, output_path.to_str().unwrap()).unwrap();
+        let stream = output.new_stream().unwrap();
+        unsafe {
+            let input_stream = input.streams()[0];
+            check(ffi::avcodec_parameters_copy(
+                (*stream).codecpar,
+                (*input_stream).codecpar,
+            ))
+            .unwrap();
+            (*stream).time_base = (*input_stream).time_base;
+        }
+        output.write_header(None).unwrap();
+        let mut packet = Packet::new().unwrap();
+        let mut packets = 0;
+        loop {
+            match input.read_frame(&mut packet) {
+                Recv::Ready => {
+                    packets += 1;
+                    output.write_frame(&mut packet).unwrap();
+                }
+                Recv::Eof => break,
+                recv => panic!("Unexpected {:?}", recv),
+            }
+        }
+        output.write_trailer().unwrap();
+        drop(output);
+        assert_eq!(packets, 2);
+
+        let mut remuxed =
+            unsafe { InputFormatContext::open(output_path.to_str().unwrap(), None, None) }.unwrap();
+        assert_eq!(
+            unsafe { std::ffi::CStr::from_ptr((*(*remuxed.as_ptr()).iformat).name) }
+                .to_str()
+                .unwrap(),
+            "matroska,webm"
+        );
+        let mut remuxed_packets = 0;
+        while remuxed.read_frame(&mut packet) == Recv::Ready {
+            remuxed_packets += 1;
+        }
+        assert_eq!(remuxed_packets, 2);
+        assert!(unsafe { InputFormatContext::open("/no/such/file.mkv", None, None) }.is_err());
+
+        fs::remove_file(&input_path).unwrap();
+        fs::remove_file(&output_path).unwrap();
+    }
+}
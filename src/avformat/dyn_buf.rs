@@ -0,0 +1,112 @@
+use crate::ffi::{self, AVIOContext, FfmpegError};
+use std::{ptr, slice};
+
+/// An `AVIOContext` writing into memory, from `avio_open_dyn_buf()`. Get the
+/// written bytes with [`into_vec()`](Self::into_vec), dropping it discards
+/// them.
+#[derive(Debug)]
+pub struct DynBuf {
+    avio: *mut AVIOContext,
+}
+
+impl DynBuf {
+    pub fn new() -> Result<Self, FfmpegError> {
+        let mut avio = ptr::null_mut();
+        match unsafe { ffi::avio_open_dyn_buf(&mut avio) } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(Self { avio }),
+        }
+    }
+
+    /// The context to write to, e.g. to assign to `AVFormatContext.pb`. It's
+    /// invalid once `self` is consumed or dropped, so unset `pb` before that
+    /// if the format context outlives it.
+    pub fn as_avio(&mut self) -> *mut AVIOContext {
+        self.avio
+    }
+
+    /// Close the buffer and return the bytes written to it.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        let (buffer, size) = unsafe { self.close() };
+        let vec = if buffer.is_null() || size <= 0 {
+            Vec::new()
+        } else {
+            unsafe { slice::from_raw_parts(buffer, size as usize) }.to_vec()
+        };
+        unsafe { ffi::av_free(buffer as _) };
+        vec
+    }
+
+    /// Close the context and take the buffer, which has to be freed with
+    /// `av_free()`.
+    unsafe fn close(&mut self) -> (*mut u8, libc::c_int) {
+        if self.avio.is_null() {
+            return (ptr::null_mut(), 0);
+        }
+        let mut buffer = ptr::null_mut();
+        let size = ffi::avio_close_dyn_buf(self.avio, &mut buffer);
+        self.avio = ptr::null_mut();
+        (buffer, size)
+    }
+}
+
+impl Drop for DynBuf {
+    fn drop(&mut self) {
+        unsafe {
+            let (buffer, _) = self.close();
+            ffi::av_free(buffer as _);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::Packet;
+
+    #[test]
+    fn test_mux_to_memory() {
+        let mut dyn_buf = DynBuf::new().unwrap();
+        unsafe {
+            let mut ctx = ptr::null_mut();
+            assert!(
+                ffi::avformat_alloc_output_context2(
+                    &mut ctx,
+                    ptr::null_mut(),
+                    b"mpegts\0".as_ptr() as _,
+                    ptr::null()
+                ) >= 0
+            );
+            (*ctx).pb = dyn_buf.as_avio();
+            let stream = ffi::avformat_new_stream(ctx, ptr::null());
+            (*(*stream).codecpar).codec_type = ffi::AVMediaType_AVMEDIA_TYPE_VIDEO;
+            (*(*stream).codecpar).codec_id = ffi::AVCodecID_AV_CODEC_ID_MPEG2VIDEO;
+            (*(*stream).codecpar).width = 16;
+            (*(*stream).codecpar).height = 16;
+            (*stream).time_base = ffi::AVRational { num: 1, den: 90000 };
+            assert!(ffi::avformat_write_header(ctx, ptr::null_mut()) >= 0);
+
+            let mut packet = Packet::new().unwrap();
+            for i in 0..2 {
+                assert_eq!(ffi::av_new_packet(packet.as_mut_ptr(), 100), 0);
+                packet.pts = i * 3600;
+                packet.dts = i * 3600;
+                packet.stream_index = 0;
+                assert!(ffi::av_interleaved_write_frame(ctx, packet.as_mut_ptr()) >= 0);
+            }
+            assert!(ffi::av_write_trailer(ctx) >= 0);
+            (*ctx).pb = ptr::null_mut();
+            ffi::avformat_free_context(ctx);
+        }
+
+        let ts = dyn_buf.into_vec();
+        assert!(!ts.is_empty());
+        assert_eq!(ts.len() % 188, 0);
+        assert!(ts.chunks(188).all(|packet| packet[0] == 0x47));
+
+        // Dropped without taking the bytes.
+        let mut dyn_buf = DynBuf::new().unwrap();
+        unsafe { ffi::avio_write(dyn_buf.as_avio(), b"discarded".as_ptr(), 9) };
+        drop(dyn_buf);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::ffi::{
+    self, AVCodecID, AVFormatContext, AVFrame, AVOutputFormat, AVRational, AVStream, MediaType,
+};
+use std::{ffi::CString, ptr};
+
+/// `Ok(null)` for `None`, `Err` if the string contains a NUL byte.
+fn optional_c_string(s: Option<&str>) -> Result<Option<CString>, ()> {
+    s.map(|s| CString::new(s).map_err(|_| ())).transpose()
+}
+
+fn as_ptr(s: &Option<CString>) -> *const libc::c_char {
+    s.as_ref().map_or(ptr::null(), |s| s.as_ptr())
+}
+
+/// The muxer matching a short name (e.g. "matroska"), a filename extension or
+/// a mime type, `None` if there is none or an argument contains a NUL byte.
+pub fn guess_format(
+    short_name: Option<&str>,
+    filename: Option<&str>,
+    mime_type: Option<&str>,
+) -> Option<*const AVOutputFormat> {
+    let short_name = optional_c_string(short_name).ok()?;
+    let filename = optional_c_string(filename).ok()?;
+    let mime_type = optional_c_string(mime_type).ok()?;
+    let fmt =
+        unsafe { ffi::av_guess_format(as_ptr(&short_name), as_ptr(&filename), as_ptr(&mime_type)) };
+    if fmt.is_null() {
+        None
+    } else {
+        Some(fmt as *const AVOutputFormat)
+    }
+}
+
+/// The default codec of a muxer for a media type, `AV_CODEC_ID_NONE` if there
+/// is none or an argument contains a NUL byte.
+///
+/// # Safety
+/// `fmt` must point to a valid `AVOutputFormat`, e.g. one returned by
+/// [`guess_format()`].
+pub unsafe fn guess_codec(
+    fmt: *const AVOutputFormat,
+    short_name: Option<&str>,
+    filename: Option<&str>,
+    mime_type: Option<&str>,
+    media_type: MediaType,
+) -> AVCodecID {
+    let (short_name, filename, mime_type) = match (
+        optional_c_string(short_name),
+        optional_c_string(filename),
+        optional_c_string(mime_type),
+    ) {
+        (Ok(short_name), Ok(filename), Ok(mime_type)) => (short_name, filename, mime_type),
+        _ => return ffi::AVCodecID_AV_CODEC_ID_NONE,
+    };
+    ffi::av_guess_codec(
+        fmt as *mut AVOutputFormat,
+        as_ptr(&short_name),
+        as_ptr(&filename),
+        as_ptr(&mime_type),
+        media_type.into(),
+    )
+}
+
+/// The frame rate of a stream, guessed from the container and codec
+/// information, `None` if it's unknown.
+///
+/// # Safety
+/// `fmt_ctx` and `stream` must be valid, `stream` being one of the streams of
+/// `fmt_ctx`.
+pub unsafe fn guess_frame_rate(
+    fmt_ctx: *mut AVFormatContext,
+    stream: *mut AVStream,
+    frame: Option<&AVFrame>,
+) -> Option<AVRational> {
+    let frame = frame.map_or(ptr::null_mut(), |frame| {
+        frame as *const AVFrame as *mut AVFrame
+    });
+    let frame_rate = ffi::av_guess_frame_rate(fmt_ctx, stream, frame);
+    if frame_rate.num == 0 || frame_rate.den == 0 {
+        None
+    } else {
+        Some(frame_rate)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_guess_format() {
+        let fmt = guess_format(None, Some("out.mkv"), None).unwrap();
+        assert_eq!(
+            unsafe { CStr::from_ptr((*fmt).name) }.to_str().unwrap(),
+            "matroska"
+        );
+        assert_eq!(
+            unsafe { guess_codec(fmt, None, Some("out.mkv"), None, MediaType::Audio) },
+            unsafe { (*fmt).audio_codec }
+        );
+        assert!(guess_format(None, Some("out.not_a_container"), None).is_none());
+        assert!(guess_format(Some("matr\0oska"), None, None).is_none());
+    }
+}
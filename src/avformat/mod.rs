@@ -0,0 +1,20 @@
+#[cfg(feature = "async-io")]
+pub(crate) mod async_io;
+#[cfg(feature = "std")]
+pub(crate) mod context;
+#[cfg(feature = "std")]
+pub(crate) mod dyn_buf;
+#[cfg(feature = "std")]
+pub(crate) mod guess;
+#[cfg(feature = "std")]
+pub(crate) mod network;
+#[cfg(feature = "std")]
+pub(crate) mod probe;
+pub(crate) mod stream;
+
+#[cfg(feature = "async-io")]
+pub use self::async_io::*;
+pub use self::stream::*;
+#[cfg(feature = "std")]
+pub use self::{context::*, dyn_buf::*, guess::*, network::*, probe::*};
+include!(concat!(env!("OUT_DIR"), "/namespace/avformat.rs"));
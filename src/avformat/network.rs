@@ -0,0 +1,178 @@
+use crate::ffi::{self, FfmpegError};
+use std::sync::Mutex;
+
+type InitFn = unsafe extern "C" fn() -> libc::c_int;
+
+/// A count of the holders of an init/deinit pair, calling init for the first
+/// one and deinit when the last one is gone.
+struct Refcount {
+    count: Mutex<usize>,
+    init: InitFn,
+    deinit: InitFn,
+}
+
+impl std::fmt::Debug for Refcount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Refcount")
+            .field("count", &self.count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Refcount {
+    const fn new(init: InitFn, deinit: InitFn) -> Self {
+        Self {
+            count: Mutex::new(0),
+            init,
+            deinit,
+        }
+    }
+
+    fn acquire(&'static self) -> Result<NetworkGuard, FfmpegError> {
+        // The count stays consistent when a holder panics, so ignore
+        // poisoning.
+        let mut count = self.count.lock().unwrap_or_else(|e| e.into_inner());
+        if *count == 0 {
+            match unsafe { (self.init)() } {
+                ret if ret < 0 => return Err(FfmpegError::from_averror(ret)),
+                _ => {}
+            }
+        }
+        *count += 1;
+        Ok(NetworkGuard { refcount: self })
+    }
+
+    fn release(&self) {
+        let mut count = self.count.lock().unwrap_or_else(|e| e.into_inner());
+        *count -= 1;
+        if *count == 0 {
+            unsafe { (self.deinit)() };
+        }
+    }
+}
+
+static NETWORK: Refcount = Refcount::new(ffi::avformat_network_init, ffi::avformat_network_deinit);
+
+/// Keeps the network protocols of libavformat initialized: the first guard
+/// of the process calls `avformat_network_init()`, dropping the last one
+/// calls `avformat_network_deinit()`. Hold one while using network protocols
+/// (TLS in particular needs it with some builds). Newer FFmpeg versions do
+/// little in these functions, which is harmless.
+#[derive(Debug)]
+pub struct NetworkGuard {
+    refcount: &'static Refcount,
+}
+
+impl NetworkGuard {
+    /// Fails with the error of `avformat_network_init()`, which is retried by
+    /// the next `acquire()`.
+    pub fn acquire() -> Result<Self, FfmpegError> {
+        NETWORK.acquire()
+    }
+
+    /// Keep the network initialized for the rest of the process.
+    pub fn leak(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for NetworkGuard {
+    fn drop(&mut self) {
+        self.refcount.release()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Barrier,
+        },
+        thread,
+    };
+
+    static INITS: AtomicUsize = AtomicUsize::new(0);
+    static DEINITS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn count_init() -> libc::c_int {
+        INITS.fetch_add(1, Ordering::SeqCst);
+        0
+    }
+
+    unsafe extern "C" fn count_deinit() -> libc::c_int {
+        DEINITS.fetch_add(1, Ordering::SeqCst);
+        0
+    }
+
+    static COUNTED: Refcount = Refcount::new(count_init, count_deinit);
+
+    unsafe extern "C" fn fail_init() -> libc::c_int {
+        ffi::AVERROR_ENOMEM
+    }
+
+    static FAILING: Refcount = Refcount::new(fail_init, count_deinit);
+
+    #[test]
+    fn test_balanced_init() {
+        let held = COUNTED.acquire().unwrap();
+        let barrier = Arc::new(Barrier::new(8));
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        let guard = COUNTED.acquire().unwrap();
+                        drop(guard);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        // Held all along, so initialized only once.
+        assert_eq!(INITS.load(Ordering::SeqCst), 1);
+        assert_eq!(DEINITS.load(Ordering::SeqCst), 0);
+        drop(held);
+        assert_eq!(DEINITS.load(Ordering::SeqCst), 1);
+
+        // Without a long lived guard, every init is matched by a deinit.
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    for _ in 0..100 {
+                        let _guard = COUNTED.acquire().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(INITS.load(Ordering::SeqCst), DEINITS.load(Ordering::SeqCst));
+
+        COUNTED.acquire().unwrap().leak();
+        assert_eq!(
+            INITS.load(Ordering::SeqCst),
+            DEINITS.load(Ordering::SeqCst) + 1
+        );
+
+        // A failed init isn't counted, so it's retried by the next acquire.
+        assert_eq!(
+            FAILING.acquire().unwrap_err(),
+            FfmpegError::Av(ffi::AVERROR_ENOMEM)
+        );
+        assert_eq!(*FAILING.count.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_network_guard() {
+        let guard = NetworkGuard::acquire().unwrap();
+        let nested = NetworkGuard::acquire().unwrap();
+        drop(guard);
+        drop(nested);
+    }
+}
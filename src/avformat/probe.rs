@@ -0,0 +1,158 @@
+use crate::ffi::{
+    self, AVIOContext, AVInputFormat, AVProbeData, FfmpegError, AVERROR_EINVAL,
+    AVPROBE_PADDING_SIZE,
+};
+use std::{
+    convert::TryFrom,
+    ffi::{CStr, CString},
+    ptr,
+};
+
+/// A demuxer found by probing, with its probe score (up to
+/// `AVPROBE_SCORE_MAX`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub format: *const AVInputFormat,
+    /// e.g. "mov,mp4,m4a,3gp,3g2,mj2"
+    pub name: String,
+    pub long_name: String,
+    pub score: i32,
+}
+
+impl ProbeResult {
+    unsafe fn new(format: *const AVInputFormat, score: i32) -> Self {
+        let to_string = |s: *const libc::c_char| {
+            if s.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(s).to_string_lossy().into_owned()
+            }
+        };
+        Self {
+            format,
+            name: to_string((*format).name),
+            long_name: to_string((*format).long_name),
+            score,
+        }
+    }
+}
+
+/// Guess the container of the start of a file with
+/// `av_probe_input_format3()`, taking the extension of `filename` into account
+/// if given. `None` if no demuxer recognizes the data, or `filename` contains a
+/// NUL byte.
+pub fn probe_format(data: &[u8], filename: Option<&str>) -> Option<ProbeResult> {
+    let filename = filename.map(CString::new).transpose().ok()?;
+    // The probe functions may read past the end of the data, FFmpeg wants
+    // `AVPROBE_PADDING_SIZE` zeroed bytes after it.
+    let padded_size = data.len().checked_add(AVPROBE_PADDING_SIZE as usize)?;
+    let buf_size = libc::c_int::try_from(data.len()).ok()?;
+    unsafe {
+        let buf = ffi::av_mallocz(padded_size as _) as *mut u8;
+        if buf.is_null() {
+            return None;
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
+        let mut probe_data = AVProbeData {
+            filename: filename
+                .as_ref()
+                .map_or(b"\0".as_ptr() as _, |s| s.as_ptr()),
+            buf,
+            buf_size,
+            mime_type: ptr::null(),
+        };
+        let mut score = 0;
+        let format = ffi::av_probe_input_format3(&mut probe_data, 1, &mut score);
+        ffi::av_free(buf as _);
+        if format.is_null() {
+            None
+        } else {
+            Some(ProbeResult::new(format, score))
+        }
+    }
+}
+
+/// Guess the container of an opened input with `av_probe_input_buffer2()`,
+/// reading up to `max_probe_size` bytes (0 for FFmpeg's default). The data
+/// read stays buffered in `pb`, so it can be handed to a demuxer afterwards.
+///
+/// # Safety
+/// `pb` must be a valid `AVIOContext` opened for reading.
+pub unsafe fn probe_input(
+    pb: *mut AVIOContext,
+    url: Option<&str>,
+    max_probe_size: u32,
+) -> Result<ProbeResult, FfmpegError> {
+    let url = url
+        .map(CString::new)
+        .transpose()
+        .map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+    let mut format = ptr::null_mut();
+    let score = ffi::av_probe_input_buffer2(
+        pb,
+        &mut format as *mut _ as _,
+        url.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+        ptr::null_mut(),
+        0,
+        max_probe_size,
+    );
+    if score < 0 {
+        Err(FfmpegError::from_averror(score))
+    } else {
+        Ok(ProbeResult::new(format, score))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::{AVIO_FLAG_READ, AVPROBE_SCORE_MAX};
+    use std::fs;
+
+    /// An EBML header with the "matroska" doc type.
+    const MATROSKA_HEADER: &[u8] = &[
+        0x1a, 0x45, 0xdf, 0xa3, 0xa3, // EBML
+        0x42, 0x86, 0x81, 0x01, // EBMLVersion
+        0x42, 0xf7, 0x81, 0x01, // EBMLReadVersion
+        0x42, 0xf2, 0x81, 0x04, // EBMLMaxIDLength
+        0x42, 0xf3, 0x81, 0x08, // EBMLMaxSizeLength
+        0x42, 0x82, 0x88, b'm', b'a', b't', b'r', b'o', b's', b'k', b'a', // DocType
+        0x42, 0x87, 0x81, 0x04, // DocTypeVersion
+        0x42, 0x85, 0x81, 0x02, // DocTypeReadVersion
+    ];
+
+    #[test]
+    fn test_probe_format() {
+        // An ftyp box followed by the header of an empty moov box.
+        let mp4 = [
+            0x00, 0x00, 0x00, 0x18, b'f', b't', b'y', b'p', b'i', b's', b'o', b'm', 0x00, 0x00,
+            0x02, 0x00, b'i', b's', b'o', b'm', b'i', b's', b'o', b'2', 0x00, 0x00, 0x00, 0x08,
+            b'm', b'o', b'o', b'v',
+        ];
+        let result = probe_format(&mp4, None).unwrap();
+        assert_eq!(result.name, "mov,mp4,m4a,3gp,3g2,mj2");
+        assert_eq!(result.score, AVPROBE_SCORE_MAX as i32);
+
+        let result = probe_format(MATROSKA_HEADER, Some("header.mkv")).unwrap();
+        assert_eq!(result.name, "matroska,webm");
+        assert_eq!(result.long_name, "Matroska / WebM");
+
+        assert_eq!(probe_format(&[], None), None);
+        assert_eq!(probe_format(MATROSKA_HEADER, Some("a\0b")), None);
+    }
+
+    #[test]
+    fn test_probe_input() {
+        let path = std::env::temp_dir().join("rusty_ffmpeg_probe_input.mkv");
+        fs::write(&path, MATROSKA_HEADER).unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+        unsafe {
+            let mut pb = ptr::null_mut();
+            assert!(ffi::avio_open(&mut pb, c_path.as_ptr(), AVIO_FLAG_READ as _) >= 0);
+            let result = probe_input(pb, path.to_str(), 0).unwrap();
+            assert_eq!(result.name, "matroska,webm");
+            ffi::avio_closep(&mut pb);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::ffi::{
+    self, AVCodec, AVFormatContext, AVPacketSideDataType, AVStream, FfmpegError, MediaType,
+};
+use core::{ptr, slice};
+
+/// Result of [`find_best_stream()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BestStream {
+    /// Index of the stream in `AVFormatContext.streams`.
+    pub index: usize,
+    /// Decoder of the stream.
+    pub decoder: Option<*const AVCodec>,
+}
+
+/// Find the best stream of a media type with `av_find_best_stream()`, and a
+/// decoder for it. `wanted` asks for a specific stream, `related` prefers a
+/// stream related to (e.g. in the same program as) the given one. Fails with
+/// `FfmpegError::StreamNotFound` if there is no such stream and
+/// `FfmpegError::DecoderNotFound` if there is one but no decoder for it.
+///
+/// # Safety
+/// `fmt` must be an opened `AVFormatContext` (after
+/// `avformat_find_stream_info()` for meaningful results).
+pub unsafe fn find_best_stream(
+    fmt: *mut AVFormatContext,
+    kind: MediaType,
+    wanted: Option<usize>,
+    related: Option<usize>,
+) -> Result<BestStream, FfmpegError> {
+    // `AVCodec **` before FFmpeg 5, `const AVCodec **` after.
+    let mut decoder: *const AVCodec = ptr::null();
+    let index = ffi::av_find_best_stream(
+        fmt,
+        kind.into(),
+        wanted.map_or(-1, |wanted| wanted as _),
+        related.map_or(-1, |related| related as _),
+        &mut decoder as *mut *const AVCodec as _,
+        0,
+    );
+    if index < 0 {
+        Err(FfmpegError::from_averror(index))
+    } else {
+        Ok(BestStream {
+            index: index as usize,
+            decoder: if decoder.is_null() {
+                None
+            } else {
+                Some(decoder)
+            },
+        })
+    }
+}
+
+/// The payload of the side data of `kind` of a stream, from
+/// `AVCodecParameters.coded_side_data` since FFmpeg 6.1 and
+/// `AVStream.side_data` before.
+pub(crate) fn stream_side_data(stream: &AVStream, kind: AVPacketSideDataType) -> Option<&[u8]> {
+    unsafe {
+        #[cfg(ffmpeg_6_1)]
+        let (data, size) = {
+            let codecpar = stream.codecpar.as_ref()?;
+            let side_data = ffi::av_packet_side_data_get(
+                codecpar.coded_side_data,
+                codecpar.nb_coded_side_data,
+                kind,
+            )
+            .as_ref()?;
+            (side_data.data, side_data.size as usize)
+        };
+        #[cfg(not(ffmpeg_6_1))]
+        let (data, size) = {
+            // `int` before FFmpeg 5, `size_t` after.
+            let mut size = 0;
+            let data = ffi::av_stream_get_side_data(stream, kind, &mut size);
+            (data, size as usize)
+        };
+        if data.is_null() {
+            None
+        } else {
+            Some(slice::from_raw_parts(data, size))
+        }
+    }
+}
+
+/// Add zeroed side data of `kind` to a stream.
+#[cfg(test)]
+pub(crate) unsafe fn new_stream_side_data(
+    stream: *mut AVStream,
+    kind: AVPacketSideDataType,
+    size: usize,
+) -> *mut u8 {
+    #[cfg(ffmpeg_6_1)]
+    let data = {
+        let codecpar = (*stream).codecpar;
+        let side_data = ffi::av_packet_side_data_new(
+            &mut (*codecpar).coded_side_data,
+            &mut (*codecpar).nb_coded_side_data,
+            kind,
+            size as _,
+            0,
+        );
+        (*side_data).data
+    };
+    #[cfg(not(ffmpeg_6_1))]
+    let data = ffi::av_stream_new_side_data(stream, kind, size as _);
+    ptr::write_bytes(data, 0, size);
+    data
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use std::{ffi::CString, fs};
+
+    #[test]
+    fn test_find_best_stream() {
+        // A 2x2 yuv4mpeg file with one frame.
+        let mut y4m = b"YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C420jpeg\nFRAME\n".to_vec();
+        y4m.extend_from_slice(&[16, 16, 16, 16, 128, 128]);
+        let path = std::env::temp_dir().join("rusty_ffmpeg_find_best_stream.y4m");
+        fs::write(&path, y4m).unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let mut fmt = ptr::null_mut();
+            assert_eq!(
+                ffi::avformat_open_input(
+                    &mut fmt,
+                    c_path.as_ptr(),
+                    ptr::null_mut(),
+                    ptr::null_mut()
+                ),
+                0
+            );
+            assert!(ffi::avformat_find_stream_info(fmt, ptr::null_mut()) >= 0);
+
+            let best = find_best_stream(fmt, MediaType::Video, None, None).unwrap();
+            assert_eq!(best.index, 0);
+            assert_eq!(
+                (*best.decoder.unwrap()).id,
+                ffi::AVCodecID_AV_CODEC_ID_RAWVIDEO
+            );
+            assert_eq!(
+                find_best_stream(fmt, MediaType::Audio, None, None),
+                Err(FfmpegError::StreamNotFound)
+            );
+
+            ffi::avformat_close_input(&mut fmt);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,7 +1,78 @@
-use crate::ffi::{AVRational, AV_TIME_BASE};
+use crate::ffi::{self, AVMediaType, AVRational, AV_TIME_BASE};
 
 pub const AV_NOPTS_VALUE: i64 = 0x8000000000000000u64 as i64;
 pub const AV_TIME_BASE_Q: AVRational = AVRational {
     num: 1,
     den: AV_TIME_BASE as libc::c_int,
 };
+
+/// Typed `AVMediaType` without `AVMEDIA_TYPE_UNKNOWN`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    Video,
+    Audio,
+    Data,
+    Subtitle,
+    Attachment,
+}
+
+impl From<MediaType> for AVMediaType {
+    fn from(media_type: MediaType) -> Self {
+        match media_type {
+            MediaType::Video => ffi::AVMediaType_AVMEDIA_TYPE_VIDEO,
+            MediaType::Audio => ffi::AVMediaType_AVMEDIA_TYPE_AUDIO,
+            MediaType::Data => ffi::AVMediaType_AVMEDIA_TYPE_DATA,
+            MediaType::Subtitle => ffi::AVMediaType_AVMEDIA_TYPE_SUBTITLE,
+            MediaType::Attachment => ffi::AVMediaType_AVMEDIA_TYPE_ATTACHMENT,
+        }
+    }
+}
+
+impl MediaType {
+    /// `None` for `AVMEDIA_TYPE_UNKNOWN` and out of range values.
+    pub fn from_av(media_type: AVMediaType) -> Option<Self> {
+        match media_type {
+            ffi::AVMediaType_AVMEDIA_TYPE_VIDEO => Some(MediaType::Video),
+            ffi::AVMediaType_AVMEDIA_TYPE_AUDIO => Some(MediaType::Audio),
+            ffi::AVMediaType_AVMEDIA_TYPE_DATA => Some(MediaType::Data),
+            ffi::AVMediaType_AVMEDIA_TYPE_SUBTITLE => Some(MediaType::Subtitle),
+            ffi::AVMediaType_AVMEDIA_TYPE_ATTACHMENT => Some(MediaType::Attachment),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_media_type() {
+        for media_type in [
+            MediaType::Video,
+            MediaType::Audio,
+            MediaType::Data,
+            MediaType::Subtitle,
+            MediaType::Attachment,
+        ] {
+            assert_eq!(MediaType::from_av(media_type.into()), Some(media_type));
+        }
+        assert_eq!(
+            MediaType::from_av(ffi::AVMediaType_AVMEDIA_TYPE_UNKNOWN),
+            None
+        );
+    }
+
+    #[cfg(feature = "cstr-constants")]
+    #[test]
+    fn test_cstr_constants() {
+        let mut buf = [0 as libc::c_char; 8];
+        let len =
+            unsafe { ffi::av_strlcpy(buf.as_mut_ptr(), ffi::PRId64.as_ptr(), buf.len() as _) };
+        assert_eq!(len as usize, ffi::PRId64.to_bytes().len());
+        assert_eq!(
+            unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) },
+            ffi::PRId64
+        );
+    }
+}
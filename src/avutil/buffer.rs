@@ -0,0 +1,97 @@
+use crate::ffi::{self, AVBufferRef, FfmpegError, AVERROR_ENOMEM};
+use core::slice;
+
+/// An owned `AVBufferRef`, unreferenced on drop. Cloning creates a new
+/// reference to the same buffer.
+#[derive(Debug)]
+pub struct AvBufferRefGuard {
+    buffer: *mut AVBufferRef,
+}
+
+impl AvBufferRefGuard {
+    /// A new zero filled buffer.
+    pub fn alloc(size: usize) -> Result<Self, FfmpegError> {
+        let buffer = unsafe { ffi::av_buffer_allocz(size as _) };
+        if buffer.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(Self { buffer })
+        }
+    }
+
+    /// Take ownership of a reference.
+    ///
+    /// # Safety
+    /// `buffer` must be a valid reference not owned by anything else.
+    pub unsafe fn from_raw(buffer: *mut AVBufferRef) -> Self {
+        Self { buffer }
+    }
+
+    /// Give up ownership of the reference, the caller has to unreference it.
+    pub fn into_raw(self) -> *mut AVBufferRef {
+        let buffer = self.buffer;
+        core::mem::forget(self);
+        buffer
+    }
+
+    /// A new reference for fields that take ownership of one, like
+    /// `AVCodecContext.hw_frames_ctx`.
+    pub fn new_ref(&self) -> Result<*mut AVBufferRef, FfmpegError> {
+        let buffer = unsafe { ffi::av_buffer_ref(self.buffer) };
+        if buffer.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(buffer)
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let buffer = &*self.buffer;
+            if buffer.data.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(buffer.data, buffer.size as usize)
+            }
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const AVBufferRef {
+        self.buffer
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVBufferRef {
+        self.buffer
+    }
+}
+
+impl Clone for AvBufferRefGuard {
+    /// Panics if the new reference can't be allocated.
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.new_ref().expect("Cannot reference the buffer."),
+        }
+    }
+}
+
+impl Drop for AvBufferRefGuard {
+    fn drop(&mut self) {
+        unsafe { ffi::av_buffer_unref(&mut self.buffer) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_buffer_ref() {
+        let buffer = AvBufferRefGuard::alloc(16).unwrap();
+        assert_eq!(buffer.data(), [0; 16]);
+        assert_eq!(unsafe { ffi::av_buffer_get_ref_count(buffer.as_ptr()) }, 1);
+        let clone = buffer.clone();
+        assert_eq!(unsafe { ffi::av_buffer_get_ref_count(buffer.as_ptr()) }, 2);
+        drop(clone);
+        assert_eq!(unsafe { ffi::av_buffer_get_ref_count(buffer.as_ptr()) }, 1);
+    }
+}
@@ -0,0 +1,534 @@
+//! Channel layouts of the `uint64_t` channel mask API of FFmpeg 4, which
+//! FFmpeg 7 removed, and of the `AVChannelLayout` API of FFmpeg 5.1. A mask
+//! layout is a mask of `AV_CH_*` bits with the channels in bit order, or only
+//! a channel count when the channels are unknown (e.g. "3c").
+
+use crate::ffi;
+#[cfg(ffmpeg_5_1)]
+use crate::ffi::{FfmpegError, AVERROR_EINVAL, AVERROR_ENOMEM};
+use std::{
+    convert::TryFrom,
+    ffi::{CStr, CString},
+    fmt, ptr,
+};
+
+/// A channel layout, `mask` is 0 if only the number of channels is known.
+/// Layouts with the same mask and count are equal. The mask API is deprecated
+/// since FFmpeg 5.1, use [`ChLayout`] there.
+#[cfg(not(ffmpeg_7_0))]
+#[cfg_attr(
+    ffmpeg_5_1,
+    deprecated(note = "The channel mask API is deprecated since FFmpeg 5.1, use `ChLayout`.")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelLayout {
+    mask: u64,
+    nb_channels: i32,
+}
+
+#[cfg(not(ffmpeg_7_0))]
+fn static_str(s: *const libc::c_char) -> &'static str {
+    if s.is_null() {
+        ""
+    } else {
+        unsafe { CStr::from_ptr(s) }.to_str().unwrap_or("")
+    }
+}
+
+/// The description of a layout by `av_channel_layout_describe()`.
+#[cfg(ffmpeg_5_1)]
+fn describe(layout: &ffi::AVChannelLayout) -> String {
+    let mut buf = [0 as libc::c_char; 256];
+    unsafe {
+        ffi::av_channel_layout_describe(layout, buf.as_mut_ptr(), buf.len() as _);
+        CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(not(ffmpeg_7_0))]
+#[cfg_attr(ffmpeg_5_1, allow(deprecated))]
+impl ChannelLayout {
+    /// Parse a layout like "stereo", "5.1(side)", "FL+FR+LFE", a mask like
+    /// "0x3" or a channel count like "3c", with
+    /// `av_get_extended_channel_layout()`.
+    pub fn from_string(s: &str) -> Option<Self> {
+        let s = CString::new(s).ok()?;
+        let mut mask = 0;
+        let mut nb_channels = 0;
+        let ret =
+            unsafe { ffi::av_get_extended_channel_layout(s.as_ptr(), &mut mask, &mut nb_channels) };
+        if ret < 0 || nb_channels <= 0 {
+            None
+        } else {
+            Some(Self { mask, nb_channels })
+        }
+    }
+
+    /// `None` for an empty mask.
+    pub fn from_mask(mask: u64) -> Option<Self> {
+        if mask == 0 {
+            None
+        } else {
+            Some(Self {
+                mask,
+                nb_channels: unsafe { ffi::av_get_channel_layout_nb_channels(mask) },
+            })
+        }
+    }
+
+    /// The default layout for a number of channels, e.g. stereo for 2, with
+    /// `av_get_default_channel_layout()`. Without a default layout the
+    /// channels are unknown.
+    pub fn default_for(nb_channels: i32) -> Option<Self> {
+        if nb_channels <= 0 {
+            return None;
+        }
+        match unsafe { ffi::av_get_default_channel_layout(nb_channels) } {
+            0 => Some(Self {
+                mask: 0,
+                nb_channels,
+            }),
+            mask => Self::from_mask(mask as u64),
+        }
+    }
+
+    /// The `channel_layout` value of codec contexts and frames.
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// The `channels` value of codec contexts and frames.
+    pub fn nb_channels(&self) -> i32 {
+        self.nb_channels
+    }
+
+    /// The index, `AV_CH_*` bit and name (e.g. "FL") of each channel, nothing
+    /// if the channels are unknown.
+    pub fn iter_channels(&self) -> impl Iterator<Item = (usize, u64, &'static str)> {
+        let mask = self.mask;
+        (0..64)
+            .map(|bit| 1u64 << bit)
+            .filter(move |channel| mask & channel != 0)
+            .enumerate()
+            .map(|(index, channel)| {
+                let name = static_str(unsafe { ffi::av_get_channel_name(channel) });
+                (index, channel, name)
+            })
+    }
+}
+
+/// A layout of the table of standard layouts of FFmpeg, see
+/// [`standard_channel_layouts()`].
+#[derive(Clone, Copy)]
+pub struct ChannelLayoutRef {
+    #[cfg(ffmpeg_5_1)]
+    layout: &'static ffi::AVChannelLayout,
+    #[cfg(not(ffmpeg_5_1))]
+    name: &'static str,
+    #[cfg(not(ffmpeg_5_1))]
+    layout: ChannelLayout,
+}
+
+impl ChannelLayoutRef {
+    /// The layout as FFmpeg has it in its table.
+    #[cfg(ffmpeg_5_1)]
+    pub fn as_av(&self) -> &'static ffi::AVChannelLayout {
+        self.layout
+    }
+
+    pub fn nb_channels(&self) -> i32 {
+        #[cfg(ffmpeg_5_1)]
+        {
+            self.layout.nb_channels
+        }
+        #[cfg(not(ffmpeg_5_1))]
+        {
+            self.layout.nb_channels()
+        }
+    }
+
+    /// The `AV_CH_*` bits of the channels, `None` if the layout isn't in the
+    /// native order.
+    pub fn mask(&self) -> Option<u64> {
+        #[cfg(ffmpeg_5_1)]
+        {
+            if self.layout.order == ffi::AVChannelOrder_AV_CHANNEL_ORDER_NATIVE {
+                Some(unsafe { self.layout.u.mask })
+            } else {
+                None
+            }
+        }
+        #[cfg(not(ffmpeg_5_1))]
+        {
+            Some(self.layout.mask())
+        }
+    }
+
+    /// The name FFmpeg describes the layout with, e.g. "5.1(side)".
+    pub fn name(&self) -> String {
+        #[cfg(ffmpeg_5_1)]
+        {
+            describe(self.layout)
+        }
+        #[cfg(not(ffmpeg_5_1))]
+        {
+            self.name.to_string()
+        }
+    }
+}
+
+impl fmt::Debug for ChannelLayoutRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelLayoutRef")
+            .field("name", &self.name())
+            .field("nb_channels", &self.nb_channels())
+            .field("mask", &self.mask())
+            .finish()
+    }
+}
+
+/// The standard layouts of FFmpeg, with `av_channel_layout_standard()` since
+/// FFmpeg 5.1 and `av_get_standard_channel_layout()` before.
+pub fn standard_channel_layouts() -> impl Iterator<Item = ChannelLayoutRef> {
+    #[cfg(ffmpeg_5_1)]
+    {
+        let mut opaque = ptr::null_mut();
+        std::iter::from_fn(move || {
+            unsafe { ffi::av_channel_layout_standard(&mut opaque).as_ref() }
+                .map(|layout| ChannelLayoutRef { layout })
+        })
+    }
+    #[cfg(not(ffmpeg_5_1))]
+    {
+        (0..).map_while(|index| {
+            let mut mask = 0;
+            let mut name = ptr::null();
+            if unsafe { ffi::av_get_standard_channel_layout(index, &mut mask, &mut name) } < 0 {
+                None
+            } else {
+                Some(ChannelLayoutRef {
+                    name: static_str(name),
+                    layout: ChannelLayout::from_mask(mask)?,
+                })
+            }
+        })
+    }
+}
+
+/// The name of a standard layout, the channel names joined by "+" otherwise,
+/// or "N channels" if they are unknown.
+#[cfg(not(ffmpeg_7_0))]
+#[cfg_attr(ffmpeg_5_1, allow(deprecated))]
+impl fmt::Display for ChannelLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0 as libc::c_char; 256];
+        unsafe {
+            ffi::av_get_channel_layout_string(
+                buf.as_mut_ptr(),
+                i32::try_from(buf.len()).unwrap(),
+                self.nb_channels,
+                self.mask,
+            );
+            f.write_str(&CStr::from_ptr(buf.as_ptr()).to_string_lossy())
+        }
+    }
+}
+
+/// An owned `AVChannelLayout`, which unlike a mask can have the channels in
+/// a custom order or be ambisonic, uninitialized with
+/// `av_channel_layout_uninit()` on drop. Clones and
+/// [`copy_from()`](Self::copy_from) are deep copies with
+/// `av_channel_layout_copy()`, a custom order map isn't shared.
+#[cfg(ffmpeg_5_1)]
+pub struct ChLayout {
+    layout: ffi::AVChannelLayout,
+}
+
+#[cfg(ffmpeg_5_1)]
+impl ChLayout {
+    fn empty() -> Self {
+        // A zeroed layout is an empty one of unspecified order.
+        Self {
+            layout: unsafe { std::mem::zeroed() },
+        }
+    }
+
+    /// Parse a layout like "stereo", "FR+FL", "ambisonic 1" or "3c", with
+    /// `av_channel_layout_from_string()`.
+    pub fn from_string(s: &str) -> Option<Self> {
+        let s = CString::new(s).ok()?;
+        let mut layout = Self::empty();
+        match unsafe { ffi::av_channel_layout_from_string(&mut layout.layout, s.as_ptr()) } {
+            ret if ret < 0 => None,
+            _ => Some(layout),
+        }
+    }
+
+    /// The native order layout of the `AV_CH_*` bits of `mask`, `None` for an
+    /// empty mask.
+    pub fn from_mask(mask: u64) -> Option<Self> {
+        let mut layout = Self::empty();
+        match unsafe { ffi::av_channel_layout_from_mask(&mut layout.layout, mask) } {
+            ret if ret < 0 => None,
+            _ => Some(layout),
+        }
+    }
+
+    /// The default layout for a number of channels, e.g. stereo for 2, with
+    /// `av_channel_layout_default()`. Without a default layout the order is
+    /// unspecified.
+    pub fn default_for(nb_channels: i32) -> Option<Self> {
+        if nb_channels <= 0 {
+            return None;
+        }
+        let mut layout = Self::empty();
+        unsafe { ffi::av_channel_layout_default(&mut layout.layout, nb_channels) };
+        Some(layout)
+    }
+
+    /// A layout of `AV_CHANNEL_ORDER_CUSTOM` with the channels in the order
+    /// given. `AVERROR(EINVAL)` for no channels.
+    pub fn custom(channels: &[ffi::AVChannel]) -> Result<Self, FfmpegError> {
+        let nb_channels = i32::try_from(channels.len())
+            .ok()
+            .filter(|&nb_channels| nb_channels > 0)
+            .ok_or(FfmpegError::Av(AVERROR_EINVAL))?;
+        let map = unsafe {
+            ffi::av_calloc(
+                channels.len() as _,
+                std::mem::size_of::<ffi::AVChannelCustom>() as _,
+            )
+        } as *mut ffi::AVChannelCustom;
+        if map.is_null() {
+            return Err(FfmpegError::Av(AVERROR_ENOMEM));
+        }
+        for (i, &id) in channels.iter().enumerate() {
+            unsafe { (*map.add(i)).id = id };
+        }
+        // The map is freed by `av_channel_layout_uninit()` from now on.
+        let mut layout = Self::empty();
+        layout.layout.order = ffi::AVChannelOrder_AV_CHANNEL_ORDER_CUSTOM;
+        layout.layout.nb_channels = nb_channels;
+        layout.layout.u.map = map;
+        Ok(layout)
+    }
+
+    /// Replace the layout by a copy of `src`, with `av_channel_layout_copy()`.
+    /// The old layout is uninitialized first, and kept if copying fails.
+    pub fn copy_from(&mut self, src: &ffi::AVChannelLayout) -> Result<(), FfmpegError> {
+        match unsafe { ffi::av_channel_layout_copy(&mut self.layout, src) } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `other` is the same layout, with `av_channel_layout_compare()`.
+    /// Layouts of different orders can be the same. An error if either is
+    /// invalid.
+    pub fn compare(&self, other: &ffi::AVChannelLayout) -> Result<bool, FfmpegError> {
+        match unsafe { ffi::av_channel_layout_compare(&self.layout, other) } {
+            0 => Ok(true),
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(false),
+        }
+    }
+
+    /// Each channel in order, e.g. `AV_CHAN_FRONT_LEFT`, or
+    /// `AV_CHAN_AMBISONIC_BASE + n` for the ambisonic components.
+    pub fn channels(&self) -> impl Iterator<Item = ffi::AVChannel> + '_ {
+        (0..self.layout.nb_channels.max(0) as libc::c_uint).map(move |index| unsafe {
+            ffi::av_channel_layout_channel_from_index(&self.layout, index)
+        })
+    }
+
+    /// The index, channel and name (e.g. "FL", or "AMBI0" for an ambisonic
+    /// component) of each channel in order, with `av_channel_name()`.
+    pub fn iter_channels(&self) -> impl Iterator<Item = (usize, ffi::AVChannel, String)> + '_ {
+        self.channels().enumerate().map(|(index, channel)| {
+            let mut buf = [0 as libc::c_char; 64];
+            let name = unsafe {
+                ffi::av_channel_name(buf.as_mut_ptr(), buf.len() as _, channel);
+                CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+            };
+            (index, channel, name)
+        })
+    }
+
+    pub fn as_ptr(&self) -> *const ffi::AVChannelLayout {
+        &self.layout
+    }
+
+    /// The layout must stay valid, a custom order one owns its map.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::AVChannelLayout {
+        &mut self.layout
+    }
+}
+
+#[cfg(ffmpeg_5_1)]
+impl std::ops::Deref for ChLayout {
+    type Target = ffi::AVChannelLayout;
+
+    fn deref(&self) -> &Self::Target {
+        &self.layout
+    }
+}
+
+/// Panics if the map of a custom order layout can't be allocated.
+#[cfg(ffmpeg_5_1)]
+impl Clone for ChLayout {
+    fn clone(&self) -> Self {
+        let mut layout = Self::empty();
+        layout
+            .copy_from(&self.layout)
+            .expect("Cannot copy the channel layout.");
+        layout
+    }
+}
+
+#[cfg(ffmpeg_5_1)]
+impl Drop for ChLayout {
+    fn drop(&mut self) {
+        unsafe { ffi::av_channel_layout_uninit(&mut self.layout) }
+    }
+}
+
+/// The description of `av_channel_layout_describe()`, e.g. "5.1(side)",
+/// "2 channels (FR+FL)" or "ambisonic 1".
+#[cfg(ffmpeg_5_1)]
+impl fmt::Display for ChLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&describe(&self.layout))
+    }
+}
+
+#[cfg(ffmpeg_5_1)]
+impl fmt::Debug for ChLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChLayout")
+            .field("order", &self.layout.order)
+            .field("nb_channels", &self.layout.nb_channels)
+            .field("description", &describe(&self.layout))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(not(ffmpeg_7_0))]
+    #[cfg_attr(ffmpeg_5_1, allow(deprecated))]
+    fn test_from_string() {
+        let layout = ChannelLayout::from_string("FL+FR+LFE").unwrap();
+        assert_eq!(layout.mask(), 0b1011);
+        assert_eq!(layout.nb_channels(), 3);
+        assert_eq!(
+            layout.iter_channels().collect::<Vec<_>>(),
+            [(0, 0b1, "FL"), (1, 0b10, "FR"), (2, 0b1000, "LFE")]
+        );
+        assert_eq!(layout.to_string(), "2.1");
+        // Masks can't express a channel order, the channels are in bit order.
+        assert_eq!(ChannelLayout::from_string("LFE+FR+FL"), Some(layout));
+
+        assert_eq!(
+            ChannelLayout::from_string("stereo"),
+            ChannelLayout::from_mask(0b11)
+        );
+        assert_eq!(ChannelLayout::from_string("no such layout"), None);
+    }
+
+    #[test]
+    #[cfg(not(ffmpeg_7_0))]
+    #[cfg_attr(ffmpeg_5_1, allow(deprecated))]
+    fn test_unknown_channels() {
+        let layout = ChannelLayout::from_string("3c").unwrap();
+        assert_eq!(layout.mask(), 0);
+        assert_eq!(layout.nb_channels(), 3);
+        assert_eq!(layout.iter_channels().count(), 0);
+        assert_eq!(layout.to_string(), "3 channels");
+        assert_eq!(ChannelLayout::default_for(3).unwrap().to_string(), "3.0");
+        assert_eq!(ChannelLayout::default_for(0), None);
+        assert_eq!(ChannelLayout::from_mask(0), None);
+    }
+
+    #[test]
+    fn test_standard_channel_layouts() {
+        let standard = standard_channel_layouts().collect::<Vec<_>>();
+        let find = |name: &str| {
+            standard
+                .iter()
+                .find(|layout| layout.name() == name)
+                .unwrap_or_else(|| panic!("{} is missing", name))
+        };
+        let stereo = find("stereo");
+        assert_eq!(stereo.nb_channels(), 2);
+        assert_eq!(stereo.mask(), Some(0b11));
+        assert_eq!(find("5.1").nb_channels(), 6);
+        for layout in standard {
+            if let Some(mask) = layout.mask() {
+                assert_eq!(mask.count_ones() as i32, layout.nb_channels());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(ffmpeg_5_1)]
+    fn test_custom_order() {
+        let channels = [
+            ffi::AVChannel_AV_CHAN_FRONT_RIGHT,
+            ffi::AVChannel_AV_CHAN_FRONT_LEFT,
+        ];
+        let layout = ChLayout::custom(&channels).unwrap();
+        assert_eq!(layout.order, ffi::AVChannelOrder_AV_CHANNEL_ORDER_CUSTOM);
+        assert_eq!(layout.nb_channels, 2);
+        assert_eq!(layout.channels().collect::<Vec<_>>(), channels);
+        assert!(layout.to_string().contains("FR+FL"));
+        // Same channels as stereo, but not in its order.
+        let stereo = ChLayout::from_mask(0b11).unwrap();
+        assert_eq!(layout.compare(&stereo), Ok(false));
+        assert_eq!(stereo.compare(&ChLayout::default_for(2).unwrap()), Ok(true));
+
+        let mut copy = ChLayout::default_for(1).unwrap();
+        copy.copy_from(&layout).unwrap();
+        assert_eq!(copy.compare(&layout), Ok(true));
+        drop(layout);
+        assert_eq!(copy.channels().collect::<Vec<_>>(), channels);
+        assert_eq!(
+            copy.clone().iter_channels().collect::<Vec<_>>(),
+            [
+                (0, channels[0], "FR".to_string()),
+                (1, channels[1], "FL".to_string())
+            ]
+        );
+        assert!(ChLayout::custom(&[]).is_err());
+    }
+
+    #[test]
+    #[cfg(ffmpeg_5_1)]
+    fn test_ambisonic() {
+        let layout = ChLayout::from_string("ambisonic 1").unwrap();
+        assert_eq!(layout.order, ffi::AVChannelOrder_AV_CHANNEL_ORDER_AMBISONIC);
+        assert_eq!(layout.nb_channels, 4);
+        assert_eq!(
+            layout.channels().collect::<Vec<_>>(),
+            (0..4)
+                .map(|n| ffi::AVChannel_AV_CHAN_AMBISONIC_BASE + n)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(layout.to_string(), "ambisonic 1");
+
+        let mut with_stereo = ChLayout::from_string("ambisonic 1+stereo").unwrap();
+        assert_eq!(with_stereo.nb_channels, 6);
+        assert_eq!(
+            with_stereo.channels().skip(4).collect::<Vec<_>>(),
+            [
+                ffi::AVChannel_AV_CHAN_FRONT_LEFT,
+                ffi::AVChannel_AV_CHAN_FRONT_RIGHT
+            ]
+        );
+        assert_eq!(with_stereo.compare(&layout), Ok(false));
+        with_stereo.copy_from(&layout).unwrap();
+        assert_eq!(with_stereo.compare(&layout), Ok(true));
+    }
+}
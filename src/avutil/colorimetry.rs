@@ -0,0 +1,207 @@
+//! Canonical names of the colorimetry enums, as used by the `-color_*`
+//! options of the ffmpeg CLI and shown by ffprobe.
+
+use super::ffi_enum::FfiEnum;
+use crate::ffi::{
+    self, AVChromaLocation, AVColorPrimaries, AVColorPrimaries_AVCOL_PRI_UNSPECIFIED, AVColorRange,
+    AVColorSpace, AVColorSpace_AVCOL_SPC_UNSPECIFIED, AVColorTransferCharacteristic,
+    AVColorTransferCharacteristic_AVCOL_TRC_UNSPECIFIED,
+};
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+};
+
+fn static_str(s: *const libc::c_char) -> Option<&'static str> {
+    if s.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(s) }.to_str().ok()
+    }
+}
+
+macro_rules! name_functions {
+    ($ty:ty, $name:ident, $av_name:ident, $from_name:ident, $av_from_name:ident) => {
+        /// The canonical name, `None` for values FFmpeg doesn't name.
+        pub fn $name(value: $ty) -> Option<&'static str> {
+            static_str(unsafe { ffi::$av_name(value) })
+        }
+
+        /// The value of a canonical name, `None` if there is none.
+        pub fn $from_name(name: &str) -> Option<$ty> {
+            let name = CString::new(name).ok()?;
+            match unsafe { ffi::$av_from_name(name.as_ptr()) } {
+                ret if ret < 0 => None,
+                value => Some(<$ty>::from_int(value)),
+            }
+        }
+    };
+}
+
+name_functions!(
+    AVColorPrimaries,
+    color_primaries_name,
+    av_color_primaries_name,
+    color_primaries_from_name,
+    av_color_primaries_from_name
+);
+name_functions!(
+    AVColorTransferCharacteristic,
+    color_transfer_name,
+    av_color_transfer_name,
+    color_transfer_from_name,
+    av_color_transfer_from_name
+);
+name_functions!(
+    AVColorSpace,
+    color_space_name,
+    av_color_space_name,
+    color_space_from_name,
+    av_color_space_from_name
+);
+name_functions!(
+    AVColorRange,
+    color_range_name,
+    av_color_range_name,
+    color_range_from_name,
+    av_color_range_from_name
+);
+name_functions!(
+    AVChromaLocation,
+    chroma_location_name,
+    av_chroma_location_name,
+    chroma_location_from_name,
+    av_chroma_location_from_name
+);
+
+/// Color space, primaries and transfer characteristic of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorDescription {
+    pub space: AVColorSpace,
+    pub primaries: AVColorPrimaries,
+    pub transfer: AVColorTransferCharacteristic,
+}
+
+impl Default for ColorDescription {
+    fn default() -> Self {
+        Self {
+            space: AVColorSpace_AVCOL_SPC_UNSPECIFIED,
+            primaries: AVColorPrimaries_AVCOL_PRI_UNSPECIFIED,
+            transfer: AVColorTransferCharacteristic_AVCOL_TRC_UNSPECIFIED,
+        }
+    }
+}
+
+/// Formatted like ffprobe does, "space/primaries/transfer" such as
+/// "bt2020nc/bt2020/smpte2084", or a single name if all three are the same
+/// (e.g. "bt709"). Unnamed values are shown as "unknown".
+impl fmt::Display for ColorDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let space = color_space_name(self.space).unwrap_or("unknown");
+        let primaries = color_primaries_name(self.primaries).unwrap_or("unknown");
+        let transfer = color_transfer_name(self.transfer).unwrap_or("unknown");
+        if space == primaries && space == transfer {
+            f.write_str(space)
+        } else {
+            write!(f, "{}/{}/{}", space, primaries, transfer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::{
+        AVChromaLocation_AVCHROMA_LOC_NB, AVColorPrimaries_AVCOL_PRI_BT2020,
+        AVColorPrimaries_AVCOL_PRI_BT709, AVColorPrimaries_AVCOL_PRI_NB,
+        AVColorRange_AVCOL_RANGE_MPEG, AVColorRange_AVCOL_RANGE_NB,
+        AVColorSpace_AVCOL_SPC_BT2020_NCL, AVColorSpace_AVCOL_SPC_BT709, AVColorSpace_AVCOL_SPC_NB,
+        AVColorTransferCharacteristic_AVCOL_TRC_BT709, AVColorTransferCharacteristic_AVCOL_TRC_NB,
+        AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084,
+    };
+
+    /// Every named value maps back to a value of the same name (several
+    /// reserved values share a name).
+    fn round_trip<T: FfiEnum>(
+        nb: T,
+        name: impl Fn(T) -> Option<&'static str>,
+        from_name: impl Fn(&str) -> Option<T>,
+    ) -> usize {
+        let mut named = 0;
+        for value in 0..nb.to_int() {
+            if let Some(value_name) = name(T::from_int(value)) {
+                named += 1;
+                let parsed = from_name(value_name).unwrap();
+                assert_eq!(name(parsed), Some(value_name));
+            }
+        }
+        named
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert!(
+            round_trip(
+                AVColorPrimaries_AVCOL_PRI_NB,
+                color_primaries_name,
+                color_primaries_from_name
+            ) > 10
+        );
+        assert!(
+            round_trip(
+                AVColorTransferCharacteristic_AVCOL_TRC_NB,
+                color_transfer_name,
+                color_transfer_from_name
+            ) > 10
+        );
+        assert!(
+            round_trip(
+                AVColorSpace_AVCOL_SPC_NB,
+                color_space_name,
+                color_space_from_name
+            ) > 10
+        );
+        assert_eq!(
+            round_trip(
+                AVColorRange_AVCOL_RANGE_NB,
+                color_range_name,
+                color_range_from_name
+            ),
+            3
+        );
+        assert_eq!(
+            round_trip(
+                AVChromaLocation_AVCHROMA_LOC_NB,
+                chroma_location_name,
+                chroma_location_from_name
+            ),
+            7
+        );
+        assert_eq!(color_range_name(AVColorRange_AVCOL_RANGE_MPEG), Some("tv"));
+        assert_eq!(
+            color_transfer_from_name("smpte2084"),
+            Some(AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084)
+        );
+        assert_eq!(color_space_from_name("no such space"), None);
+        assert_eq!(color_space_from_name("bt709\0"), None);
+        assert_eq!(color_primaries_name(AVColorPrimaries_AVCOL_PRI_NB), None);
+    }
+
+    #[test]
+    fn test_color_description_display() {
+        let hdr = ColorDescription {
+            space: AVColorSpace_AVCOL_SPC_BT2020_NCL,
+            primaries: AVColorPrimaries_AVCOL_PRI_BT2020,
+            transfer: AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084,
+        };
+        assert_eq!(hdr.to_string(), "bt2020nc/bt2020/smpte2084");
+        let bt709 = ColorDescription {
+            space: AVColorSpace_AVCOL_SPC_BT709,
+            primaries: AVColorPrimaries_AVCOL_PRI_BT709,
+            transfer: AVColorTransferCharacteristic_AVCOL_TRC_BT709,
+        };
+        assert_eq!(bt709.to_string(), "bt709");
+        // FFmpeg names the unspecified values "unknown".
+        assert_eq!(ColorDescription::default().to_string(), "unknown");
+    }
+}
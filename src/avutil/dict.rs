@@ -0,0 +1,208 @@
+use crate::ffi::{
+    self, AVDictionary, AVDictionaryEntry, FfmpegError, AVERROR_EINVAL, AV_DICT_IGNORE_SUFFIX,
+};
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+};
+
+/// An owned `AVDictionary`, freed with `av_dict_free()` on drop. Options that
+/// FFmpeg didn't consume are left in it by functions like `avcodec_open2()`.
+#[derive(Debug)]
+pub struct Dictionary {
+    dict: *mut AVDictionary,
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn c_string(s: &str) -> Result<CString, FfmpegError> {
+    CString::new(s).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))
+}
+
+unsafe fn entry_str<'a>(s: *const libc::c_char) -> &'a str {
+    CStr::from_ptr(s).to_str().unwrap_or("")
+}
+
+impl Dictionary {
+    /// An empty dictionary, nothing is allocated until the first `set()`.
+    pub fn new() -> Self {
+        Self {
+            dict: ptr::null_mut(),
+        }
+    }
+
+    /// Take ownership of a dictionary allocated by FFmpeg.
+    ///
+    /// # Safety
+    /// `dict` must be null or a valid dictionary not owned by anything else.
+    pub unsafe fn from_raw(dict: *mut AVDictionary) -> Self {
+        Self { dict }
+    }
+
+    /// Give up ownership of the dictionary, the caller has to free it.
+    pub fn into_raw(self) -> *mut AVDictionary {
+        let dict = self.dict;
+        std::mem::forget(self);
+        dict
+    }
+
+    /// Set a value, replacing the existing one. Keys and values containing a
+    /// NUL byte are rejected with `AVERROR(EINVAL)`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), FfmpegError> {
+        let key = c_string(key)?;
+        let value = c_string(value)?;
+        match unsafe { ffi::av_dict_set(&mut self.dict, key.as_ptr(), value.as_ptr(), 0) } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The value of a key, matched case-insensitively like FFmpeg does.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = CString::new(key).ok()?;
+        let entry = unsafe { ffi::av_dict_get(self.dict, key.as_ptr(), ptr::null(), 0) };
+        unsafe { entry.as_ref() }.map(|entry| unsafe { entry_str(entry.value) })
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { ffi::av_dict_count(self.dict) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Key value pairs, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut entry: *const AVDictionaryEntry = ptr::null();
+        std::iter::from_fn(move || unsafe {
+            entry = ffi::av_dict_get(
+                self.dict,
+                b"\0".as_ptr() as _,
+                entry,
+                AV_DICT_IGNORE_SUFFIX as _,
+            );
+            entry
+                .as_ref()
+                .map(|entry| (entry_str(entry.key), entry_str(entry.value)))
+        })
+    }
+
+    /// Parse an option string like "preset=fast:crf=23" (with `kv_sep` "="
+    /// and `pairs_sep` ":") with `av_dict_parse_string()`, where either
+    /// separator may be several characters to accept any of them. Quotes and
+    /// backslash escapes protect separators. Later values of a key replace
+    /// earlier ones.
+    pub fn parse(s: &str, kv_sep: &str, pairs_sep: &str) -> Result<Self, FfmpegError> {
+        let mut dict = Self::new();
+        dict.parse_append(s, kv_sep, pairs_sep)?;
+        Ok(dict)
+    }
+
+    /// Like [`Dictionary::parse()`], adding the pairs to this dictionary. The
+    /// pairs before a failing one are kept.
+    pub fn parse_append(
+        &mut self,
+        s: &str,
+        kv_sep: &str,
+        pairs_sep: &str,
+    ) -> Result<(), FfmpegError> {
+        let s = c_string(s)?;
+        let kv_sep = c_string(kv_sep)?;
+        let pairs_sep = c_string(pairs_sep)?;
+        match unsafe {
+            ffi::av_dict_parse_string(
+                &mut self.dict,
+                s.as_ptr(),
+                kv_sep.as_ptr(),
+                pairs_sep.as_ptr(),
+                0,
+            )
+        } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const AVDictionary {
+        self.dict
+    }
+
+    /// For the `AVDictionary **` parameters of FFmpeg functions.
+    pub fn as_mut_ptr(&mut self) -> *mut *mut AVDictionary {
+        &mut self.dict
+    }
+}
+
+impl Drop for Dictionary {
+    fn drop(&mut self) {
+        unsafe { ffi::av_dict_free(&mut self.dict) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dictionary() {
+        let mut dict = Dictionary::new();
+        assert!(dict.is_empty());
+        dict.set("preset", "fast").unwrap();
+        dict.set("crf", "23").unwrap();
+        dict.set("preset", "slow").unwrap();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.get("PRESET"), Some("slow"));
+        assert_eq!(dict.get("tune"), None);
+        assert_eq!(
+            dict.iter().collect::<Vec<_>>(),
+            [("crf", "23"), ("preset", "slow")]
+        );
+        assert_eq!(
+            dict.set("bad\0key", "value"),
+            Err(FfmpegError::Av(AVERROR_EINVAL))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let dict = Dictionary::parse("preset=fast:crf=23:preset=slow:", "=", ":").unwrap();
+        assert_eq!(
+            dict.iter().collect::<Vec<_>>(),
+            [("crf", "23"), ("preset", "slow")]
+        );
+
+        // Quotes and escapes protect separators, several separator characters
+        // are alternatives.
+        let dict = Dictionary::parse(r"title='a, b',artist=c\,d;x:y", "=:", ",;").unwrap();
+        assert_eq!(dict.get("title"), Some("a, b"));
+        assert_eq!(dict.get("artist"), Some("c,d"));
+        assert_eq!(dict.get("x"), Some("y"));
+
+        for invalid in ["a=1:b=2:c:d=4", "a=1::b=2", "=1", "a=", "a\0=1"] {
+            assert_eq!(
+                Dictionary::parse(invalid, "=", ":").unwrap_err(),
+                FfmpegError::Av(AVERROR_EINVAL)
+            );
+        }
+        assert!(Dictionary::parse("", "=", ":").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_append() {
+        let mut dict = Dictionary::new();
+        dict.set("crf", "23").unwrap();
+        dict.parse_append("preset=fast", "=", ":").unwrap();
+        assert_eq!(
+            dict.parse_append("tune=film:crf", "=", ":"),
+            Err(FfmpegError::Av(AVERROR_EINVAL))
+        );
+        assert_eq!(dict.len(), 3);
+        assert_eq!(dict.get("tune"), Some("film"));
+        assert_eq!(dict.get("crf"), Some("23"));
+    }
+}
@@ -0,0 +1,51 @@
+//! Downmix side data of audio frames (`AV_FRAME_DATA_DOWNMIX_INFO`).
+
+use crate::ffi::{self, AVDownmixInfo, AVFrame, FfmpegError, Frame, AVERROR_ENOMEM};
+
+/// The downmix side data of a frame.
+pub fn frame_downmix_info(frame: &AVFrame) -> Option<&AVDownmixInfo> {
+    unsafe {
+        let side_data =
+            ffi::av_frame_get_side_data(frame, ffi::AVFrameSideDataType_AV_FRAME_DATA_DOWNMIX_INFO);
+        if side_data.is_null() {
+            None
+        } else {
+            ((*side_data).data as *const AVDownmixInfo).as_ref()
+        }
+    }
+}
+
+/// The downmix side data of a frame for editing, added with zeroed fields if
+/// the frame has none, with `av_downmix_info_update_side_data()`.
+pub fn frame_downmix_info_mut(frame: &mut Frame) -> Result<&mut AVDownmixInfo, FfmpegError> {
+    unsafe { ffi::av_downmix_info_update_side_data(frame.as_mut_ptr()).as_mut() }
+        .ok_or(FfmpegError::Av(AVERROR_ENOMEM))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frame_downmix_info() {
+        let mut frame = Frame::new().unwrap();
+        assert!(frame_downmix_info(&frame).is_none());
+
+        let downmix_info = frame_downmix_info_mut(&mut frame).unwrap();
+        assert_eq!(downmix_info.center_mix_level, 0.0);
+        downmix_info.preferred_downmix_type = ffi::AVDownmixType_AV_DOWNMIX_TYPE_LTRT;
+        // -3 dB
+        downmix_info.center_mix_level = 0.707946;
+
+        // The existing side data is updated.
+        frame_downmix_info_mut(&mut frame).unwrap().lfe_mix_level = 0.5;
+        assert_eq!(frame.nb_side_data, 1);
+        let downmix_info = frame_downmix_info(&frame).unwrap();
+        assert_eq!(
+            downmix_info.preferred_downmix_type,
+            ffi::AVDownmixType_AV_DOWNMIX_TYPE_LTRT
+        );
+        assert_eq!(downmix_info.center_mix_level, 0.707946);
+        assert_eq!(downmix_info.lfe_mix_level, 0.5);
+    }
+}
@@ -1,4 +1,5 @@
 use libc::c_int;
+#[cfg(feature = "std")]
 use std::ffi::CStr;
 use super::common::MKTAG;
 use crate::ffi;
@@ -40,6 +41,12 @@ pub const AVERROR_BUG2: c_int               = FFERRTAG!(b'B', b'U', b'G', b' ');
 pub const AVERROR_UNKNOWN: c_int            = FFERRTAG!(b'U', b'N', b'K', b'N');
 
 
+pub const AVERROR_EAGAIN: c_int             = AVERROR(ffi::EAGAIN);
+pub const AVERROR_ENOMEM: c_int             = AVERROR(ffi::ENOMEM);
+pub const AVERROR_EINVAL: c_int             = AVERROR(ffi::EINVAL);
+pub const AVERROR_ENOSYS: c_int             = AVERROR(ffi::ENOSYS);
+pub const AVERROR_ERANGE: c_int             = AVERROR(ffi::ERANGE);
+
 pub const AVERROR_HTTP_BAD_REQUEST: c_int   = FFERRTAG!(0xF8, b'4', b'0', b'0');
 pub const AVERROR_HTTP_UNAUTHORIZED: c_int  = FFERRTAG!(0xF8, b'4', b'0', b'1');
 pub const AVERROR_HTTP_FORBIDDEN: c_int     = FFERRTAG!(0xF8, b'4', b'0', b'3');
@@ -74,6 +81,7 @@ pub unsafe fn av_make_error_string(
     errbuf
 }
 
+#[cfg(feature = "std")]
 pub fn av_err2str(
     errnum: libc::c_int
 ) -> String {
@@ -83,10 +91,98 @@ pub fn av_err2str(
     unsafe { CStr::from_ptr(errbuf_ptr) }.to_string_lossy().into()
 }
 
+/// Same as `av_err2str()`, but the description is written into the given
+/// buffer instead of an allocated `String`, so it's also available without
+/// `std`. Invalid UTF-8 at the end of the description is cut off.
+pub fn av_err2str_buf(
+    errnum: libc::c_int,
+    errbuf: &mut [u8; AV_ERROR_MAX_STRING_SIZE]
+) -> &str {
+    unsafe { av_make_error_string(errbuf.as_mut_ptr() as _, AV_ERROR_MAX_STRING_SIZE, errnum); }
+    let len = errbuf.iter().position(|&c| c == 0).unwrap_or(AV_ERROR_MAX_STRING_SIZE);
+    match core::str::from_utf8(&errbuf[..len]) {
+        Ok(description) => description,
+        Err(e) => unsafe { core::str::from_utf8_unchecked(&errbuf[..e.valid_up_to()]) },
+    }
+}
+
+/// Error of the safe helpers of this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfmpegError {
+    /// An `AVERROR` code returned by FFmpeg.
+    Av(c_int),
+    /// A linked FFmpeg component doesn't work, see `self_test()`.
+    Component(&'static str),
+    /// `AVERROR_STREAM_NOT_FOUND`
+    StreamNotFound,
+    /// `AVERROR_DECODER_NOT_FOUND`
+    DecoderNotFound,
+    /// `AVERROR(ENOSYS)`, e.g. a hardware frame transfer the device can't do.
+    Unsupported,
+}
+
+impl FfmpegError {
+    /// Error of a negative return value of FFmpeg, with the codes that have
+    /// their own variant mapped to it.
+    pub fn from_averror(errnum: c_int) -> Self {
+        match errnum {
+            AVERROR_STREAM_NOT_FOUND => FfmpegError::StreamNotFound,
+            AVERROR_DECODER_NOT_FOUND => FfmpegError::DecoderNotFound,
+            AVERROR_ENOSYS => FfmpegError::Unsupported,
+            errnum => FfmpegError::Av(errnum),
+        }
+    }
+}
+
+impl core::fmt::Display for FfmpegError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            FfmpegError::Av(errnum) => {
+                let mut errbuf = [0u8; AV_ERROR_MAX_STRING_SIZE];
+                f.write_str(av_err2str_buf(errnum, &mut errbuf))
+            }
+            FfmpegError::Component(component) => {
+                write!(f, "FFmpeg component {} failed the self test", component)
+            }
+            FfmpegError::StreamNotFound => f.write_str("Stream not found"),
+            FfmpegError::DecoderNotFound => f.write_str("Decoder not found"),
+            FfmpegError::Unsupported => f.write_str("Not supported"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FfmpegError {}
+
+/// Outcome of FFmpeg functions that fill an output (e.g. `av_read_frame()`),
+/// where `AVERROR(EAGAIN)` and `AVERROR_EOF` aren't really errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recv {
+    /// The output was filled.
+    Ready,
+    /// Nothing is available yet, try again (after feeding more input).
+    Again,
+    /// The end of the stream was reached.
+    Eof,
+    Err(FfmpegError),
+}
+
+impl Recv {
+    pub fn from_ret(ret: c_int) -> Self {
+        match ret {
+            AVERROR_EAGAIN => Recv::Again,
+            AVERROR_EOF => Recv::Eof,
+            ret if ret < 0 => Recv::Err(FfmpegError::from_averror(ret)),
+            _ => Recv::Ready,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_err2str() {
         assert_eq!(&av_err2str(AVERROR(ffi::EINVAL)), "Invalid argument");
@@ -94,4 +190,38 @@ mod test {
         assert_eq!(&av_err2str(AVERROR(ffi::ENOMEM)), "Cannot allocate memory");
         assert_eq!(&av_err2str(AVERROR_EOF), "End of file");
     }
+
+    #[test]
+    fn test_err2str_buf() {
+        let mut errbuf = [0u8; AV_ERROR_MAX_STRING_SIZE];
+        assert_eq!(av_err2str_buf(AVERROR(ffi::EINVAL), &mut errbuf), "Invalid argument");
+        assert_eq!(av_err2str_buf(AVERROR_EOF, &mut errbuf), "End of file");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ffmpeg_error_display() {
+        assert_eq!(FfmpegError::Av(AVERROR_EOF).to_string(), "End of file");
+        assert_eq!(
+            FfmpegError::Component("avcodec").to_string(),
+            "FFmpeg component avcodec failed the self test"
+        );
+        assert_eq!(FfmpegError::StreamNotFound.to_string(), "Stream not found");
+    }
+
+    #[test]
+    fn test_from_averror() {
+        assert_eq!(FfmpegError::from_averror(AVERROR_STREAM_NOT_FOUND), FfmpegError::StreamNotFound);
+        assert_eq!(FfmpegError::from_averror(AVERROR_DECODER_NOT_FOUND), FfmpegError::DecoderNotFound);
+        assert_eq!(FfmpegError::from_averror(AVERROR_ENOSYS), FfmpegError::Unsupported);
+        assert_eq!(FfmpegError::from_averror(AVERROR_EOF), FfmpegError::Av(AVERROR_EOF));
+    }
+
+    #[test]
+    fn test_recv_from_ret() {
+        assert_eq!(Recv::from_ret(0), Recv::Ready);
+        assert_eq!(Recv::from_ret(AVERROR_EAGAIN), Recv::Again);
+        assert_eq!(Recv::from_ret(AVERROR_EOF), Recv::Eof);
+        assert_eq!(Recv::from_ret(AVERROR_ENOMEM), Recv::Err(FfmpegError::Av(AVERROR_ENOMEM)));
+    }
 }
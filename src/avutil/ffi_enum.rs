@@ -0,0 +1,53 @@
+//! Conversions between the enums of the binding and the `c_int`s FFmpeg keeps
+//! them in (`AVFrame::format`...), whether the `newtype-enums` feature makes
+//! them newtypes or leaves them integers.
+
+use libc::c_int;
+
+pub(crate) trait FfiEnum: Copy {
+    fn from_int(value: c_int) -> Self;
+    fn to_int(self) -> c_int;
+}
+
+#[cfg(not(feature = "newtype-enums"))]
+impl FfiEnum for c_int {
+    fn from_int(value: c_int) -> Self {
+        value
+    }
+
+    fn to_int(self) -> c_int {
+        self
+    }
+}
+
+#[cfg(not(feature = "newtype-enums"))]
+impl FfiEnum for libc::c_uint {
+    fn from_int(value: c_int) -> Self {
+        value as Self
+    }
+
+    fn to_int(self) -> c_int {
+        self as c_int
+    }
+}
+
+#[cfg(feature = "newtype-enums")]
+macro_rules! impl_ffi_enum {
+    ($($ty:ident),*) => {
+        $(
+            impl FfiEnum for crate::ffi::$ty {
+                fn from_int(value: c_int) -> Self {
+                    Self(value as _)
+                }
+
+                fn to_int(self) -> c_int {
+                    self.0 as c_int
+                }
+            }
+        )*
+    };
+}
+
+// The enums the build script made newtypes.
+#[cfg(feature = "newtype-enums")]
+include!(concat!(env!("OUT_DIR"), "/ffi_enums.rs"));
@@ -0,0 +1,82 @@
+use crate::ffi::{self, AVFrame, FfmpegError, AVERROR_ENOMEM};
+use core::ops::{Deref, DerefMut};
+
+/// An `AVFrame` from `av_frame_alloc()`, freed with `av_frame_free()` on drop.
+#[derive(Debug)]
+pub struct Frame {
+    frame: *mut AVFrame,
+}
+
+impl Frame {
+    /// An empty frame, without data buffers.
+    pub fn new() -> Result<Self, FfmpegError> {
+        let frame = unsafe { ffi::av_frame_alloc() };
+        if frame.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(Self { frame })
+        }
+    }
+
+    /// Give up ownership of the frame, the caller has to free it.
+    pub fn into_raw(self) -> *mut AVFrame {
+        let frame = self.frame;
+        core::mem::forget(self);
+        frame
+    }
+
+    /// Release the data buffers and reset the fields, so the frame can be
+    /// reused.
+    pub fn unref(&mut self) {
+        unsafe { ffi::av_frame_unref(self.frame) }
+    }
+
+    pub fn as_ptr(&self) -> *const AVFrame {
+        self.frame
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVFrame {
+        self.frame
+    }
+}
+
+impl Deref for Frame {
+    type Target = AVFrame;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.frame }
+    }
+}
+
+impl DerefMut for Frame {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.frame }
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        unsafe { ffi::av_frame_free(&mut self.frame) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::avutil::ffi_enum::FfiEnum;
+
+    #[test]
+    fn test_frame() {
+        let mut frame = Frame::new().unwrap();
+        frame.format = ffi::AVPixelFormat_AV_PIX_FMT_GRAY8.to_int();
+        frame.width = 4;
+        frame.height = 2;
+        assert_eq!(
+            unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) },
+            0
+        );
+        assert!(!frame.data[0].is_null());
+        frame.unref();
+        assert!(frame.data[0].is_null());
+    }
+}
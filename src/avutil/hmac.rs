@@ -0,0 +1,127 @@
+use crate::ffi::{self, AVHMACType, FfmpegError, AVERROR_EINVAL, AVHMAC};
+use std::convert::TryFrom;
+
+/// Longest digest of the `AVHMACType`s, from SHA-512.
+const MAX_DIGEST_SIZE: usize = 64;
+
+/// An `AVHMAC` context, freed with `av_hmac_free()` on drop.
+///
+/// The key is kept, so the context is ready for the next message after
+/// [`finalize()`](Self::finalize).
+#[derive(Debug)]
+pub struct Hmac {
+    ctx: *mut AVHMAC,
+    key: Vec<u8>,
+}
+
+impl Hmac {
+    /// Allocate a context with `av_hmac_alloc()` and initialize it with `key`.
+    /// `AVERROR(EINVAL)` if `kind` is unknown, or the key is longer than
+    /// 4 GiB.
+    pub fn new(kind: AVHMACType, key: &[u8]) -> Result<Self, FfmpegError> {
+        let ctx = unsafe { ffi::av_hmac_alloc(kind) };
+        if ctx.is_null() {
+            return Err(FfmpegError::Av(AVERROR_EINVAL));
+        }
+        let mut hmac = Self {
+            ctx,
+            key: Vec::new(),
+        };
+        hmac.reset(key)?;
+        Ok(hmac)
+    }
+
+    /// Discard the data hashed so far and start over with another key.
+    pub fn reset(&mut self, key: &[u8]) -> Result<(), FfmpegError> {
+        let keylen =
+            libc::c_uint::try_from(key.len()).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+        self.key.clear();
+        self.key.extend_from_slice(key);
+        unsafe { ffi::av_hmac_init(self.ctx, self.key.as_ptr(), keylen) };
+        Ok(())
+    }
+
+    /// Hash more of the message.
+    pub fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(libc::c_uint::MAX as usize) {
+            unsafe { ffi::av_hmac_update(self.ctx, chunk.as_ptr(), chunk.len() as _) };
+        }
+    }
+
+    /// The digest of the message hashed since the last `finalize()` or
+    /// `reset()`, as long as the digest of the hash function (32 bytes for
+    /// `AV_HMAC_SHA256`).
+    pub fn finalize(&mut self) -> Vec<u8> {
+        let mut out = vec![0; MAX_DIGEST_SIZE];
+        let len = unsafe { ffi::av_hmac_final(self.ctx, out.as_mut_ptr(), out.len() as _) };
+        out.truncate(len.max(0) as usize);
+        // `av_hmac_final()` leaves the context unusable until the next
+        // `av_hmac_init()`. The key length was checked before.
+        unsafe { ffi::av_hmac_init(self.ctx, self.key.as_ptr(), self.key.len() as _) };
+        out
+    }
+}
+
+impl Drop for Hmac {
+    fn drop(&mut self) {
+        unsafe { ffi::av_hmac_free(self.ctx) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_rfc4231_sha256() {
+        let cases: [(&[u8], &[u8], &str); 4] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                &[0xaa; 20],
+                &[0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for &(key, data, digest) in &cases {
+            let mut hmac = Hmac::new(ffi::AVHMACType_AV_HMAC_SHA256, key).unwrap();
+            hmac.update(data);
+            assert_eq!(hex(&hmac.finalize()), digest);
+            // Ready for the next message with the same key, which may come in
+            // pieces.
+            let (head, tail) = data.split_at(data.len() / 2);
+            hmac.update(head);
+            hmac.update(tail);
+            assert_eq!(hex(&hmac.finalize()), digest);
+        }
+
+        let mut hmac = Hmac::new(ffi::AVHMACType_AV_HMAC_SHA256, b"other key").unwrap();
+        hmac.update(b"discarded");
+        hmac.reset(b"Jefe").unwrap();
+        hmac.update(b"what do ya want for nothing?");
+        assert_eq!(hex(&hmac.finalize()), cases[1].2);
+
+        let mut hmac = Hmac::new(ffi::AVHMACType_AV_HMAC_MD5, b"Jefe").unwrap();
+        hmac.update(b"what do ya want for nothing?");
+        assert_eq!(hex(&hmac.finalize()), "750c783e6ab0b503eaa86e310a5db738");
+
+        assert!(Hmac::new(100, b"key").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use super::ffi_enum::FfiEnum;
+use crate::ffi::{
+    self, AVBufferRef, AVHWDeviceContext, AVHWDeviceType, AVPixelFormat, AvBufferRefGuard,
+    Dictionary, FfmpegError, Frame, AVERROR_EINVAL, AVERROR_ENOMEM,
+};
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+};
+
+/// The hardware device types FFmpeg was built with.
+pub fn hwdevice_types() -> impl Iterator<Item = AVHWDeviceType> {
+    let mut kind = ffi::AVHWDeviceType_AV_HWDEVICE_TYPE_NONE;
+    std::iter::from_fn(move || {
+        kind = unsafe { ffi::av_hwdevice_iterate_types(kind) };
+        if kind == ffi::AVHWDeviceType_AV_HWDEVICE_TYPE_NONE {
+            None
+        } else {
+            Some(kind)
+        }
+    })
+}
+
+/// Name of a hardware device type, e.g. "vaapi".
+pub fn hwdevice_type_name(kind: AVHWDeviceType) -> Option<&'static str> {
+    let name = unsafe { ffi::av_hwdevice_get_type_name(kind) };
+    if name.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(name) }.to_str().ok()
+    }
+}
+
+/// A reference to a hardware device context (an `AVBufferRef` wrapping an
+/// `AVHWDeviceContext`), unreferenced on drop.
+#[derive(Debug)]
+pub struct HwDevice {
+    buffer: *mut AVBufferRef,
+}
+
+impl HwDevice {
+    /// Open a device with `av_hwdevice_ctx_create()`. `device` is the device
+    /// to open in a type specific format (e.g. "/dev/dri/renderD128" for
+    /// vaapi), the default device is used without it.
+    pub fn create(
+        kind: AVHWDeviceType,
+        device: Option<&str>,
+        opts: Option<&Dictionary>,
+    ) -> Result<Self, FfmpegError> {
+        let device = device
+            .map(CString::new)
+            .transpose()
+            .map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+        let mut buffer = ptr::null_mut();
+        match unsafe {
+            ffi::av_hwdevice_ctx_create(
+                &mut buffer,
+                kind,
+                device
+                    .as_ref()
+                    .map_or(ptr::null(), |device| device.as_ptr()),
+                opts.map_or(ptr::null_mut(), |opts| opts.as_ptr() as _),
+                0,
+            )
+        } {
+            ret if ret < 0 => Err(FfmpegError::from_averror(ret)),
+            _ => Ok(Self { buffer }),
+        }
+    }
+
+    pub fn kind(&self) -> AVHWDeviceType {
+        unsafe { (*((*self.buffer).data as *const AVHWDeviceContext)).type_ }
+    }
+
+    pub fn as_ptr(&self) -> *const AVBufferRef {
+        self.buffer
+    }
+
+    /// A new reference for fields that take ownership of one, like
+    /// `AVCodecContext.hw_device_ctx`.
+    pub fn new_ref(&self) -> Result<*mut AVBufferRef, FfmpegError> {
+        let buffer = unsafe { ffi::av_buffer_ref(self.buffer) };
+        if buffer.is_null() {
+            Err(FfmpegError::Av(AVERROR_ENOMEM))
+        } else {
+            Ok(buffer)
+        }
+    }
+}
+
+impl Clone for HwDevice {
+    /// Panics if the new reference can't be allocated.
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self
+                .new_ref()
+                .expect("Cannot reference the hardware device."),
+        }
+    }
+}
+
+impl Drop for HwDevice {
+    fn drop(&mut self) {
+        unsafe { ffi::av_buffer_unref(&mut self.buffer) }
+    }
+}
+
+fn check(ret: libc::c_int) -> Result<(), FfmpegError> {
+    if ret < 0 {
+        Err(FfmpegError::from_averror(ret))
+    } else {
+        Ok(())
+    }
+}
+
+/// Copy a hardware frame into a new frame in system memory, in the first
+/// format the device can transfer to. Frame properties (timestamps, color
+/// information...) are copied too. Fails with `FfmpegError::Unsupported` if
+/// the device can't download frames.
+pub fn hwframe_download(src: &Frame) -> Result<Frame, FfmpegError> {
+    if src.hw_frames_ctx.is_null() {
+        return Err(FfmpegError::Av(AVERROR_EINVAL));
+    }
+    let format = unsafe {
+        let mut formats: *mut AVPixelFormat = ptr::null_mut();
+        check(ffi::av_hwframe_transfer_get_formats(
+            src.hw_frames_ctx,
+            ffi::AVHWFrameTransferDirection_AV_HWFRAME_TRANSFER_DIRECTION_FROM,
+            &mut formats,
+            0,
+        ))?;
+        let format = *formats;
+        ffi::av_free(formats as _);
+        format
+    };
+    if format == ffi::AVPixelFormat_AV_PIX_FMT_NONE {
+        return Err(FfmpegError::Unsupported);
+    }
+
+    let mut dst = Frame::new()?;
+    dst.format = format.to_int();
+    unsafe {
+        check(ffi::av_hwframe_transfer_data(
+            dst.as_mut_ptr(),
+            src.as_ptr(),
+            0,
+        ))?;
+        check(ffi::av_frame_copy_props(dst.as_mut_ptr(), src.as_ptr()))?;
+    }
+    Ok(dst)
+}
+
+/// Copy a frame in system memory into a new hardware frame from
+/// `hw_frames_ctx` (an `AVHWFramesContext`). Frame properties are copied too.
+/// Fails with `FfmpegError::Unsupported` if the device can't upload frames of
+/// this format.
+pub fn hwframe_upload(hw_frames_ctx: &AvBufferRefGuard, src: &Frame) -> Result<Frame, FfmpegError> {
+    let mut dst = Frame::new()?;
+    unsafe {
+        check(ffi::av_hwframe_get_buffer(
+            hw_frames_ctx.as_ptr() as *mut AVBufferRef,
+            dst.as_mut_ptr(),
+            0,
+        ))?;
+        check(ffi::av_hwframe_transfer_data(
+            dst.as_mut_ptr(),
+            src.as_ptr(),
+            0,
+        ))?;
+        check(ffi::av_frame_copy_props(dst.as_mut_ptr(), src.as_ptr()))?;
+    }
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hwdevice_types() {
+        for kind in hwdevice_types() {
+            assert!(hwdevice_type_name(kind).is_some());
+            // CI machines usually have no hardware for it.
+            if let Ok(device) = HwDevice::create(kind, None, None) {
+                assert_eq!(device.kind(), kind);
+                let clone = device.clone();
+                drop(device);
+                assert_eq!(clone.kind(), kind);
+            }
+        }
+        assert_eq!(
+            hwdevice_type_name(ffi::AVHWDeviceType_AV_HWDEVICE_TYPE_NONE),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hwframe_transfer() {
+        // Only runs with a device that can hold NV12 frames.
+        let hw_frames_ctx = hwdevice_types().find_map(|kind| {
+            let device = HwDevice::create(kind, None, None).ok()?;
+            unsafe {
+                let mut hw_frames_ctx =
+                    AvBufferRefGuard::from_raw(ffi::av_hwframe_ctx_alloc(device.as_ptr() as _));
+                let frames =
+                    &mut *((*hw_frames_ctx.as_mut_ptr()).data as *mut ffi::AVHWFramesContext);
+                let mut constraints =
+                    ffi::av_hwdevice_get_hwframe_constraints(device.as_ptr() as _, ptr::null());
+                let hw_format = constraints
+                    .as_ref()
+                    .and_then(|constraints| constraints.valid_hw_formats.as_ref())
+                    .copied();
+                ffi::av_hwframe_constraints_free(&mut constraints);
+                frames.format = hw_format?;
+                frames.sw_format = ffi::AVPixelFormat_AV_PIX_FMT_NV12;
+                frames.width = 64;
+                frames.height = 64;
+                check(ffi::av_hwframe_ctx_init(hw_frames_ctx.as_mut_ptr())).ok()?;
+                Some(hw_frames_ctx)
+            }
+        });
+        let hw_frames_ctx = match hw_frames_ctx {
+            Some(hw_frames_ctx) => hw_frames_ctx,
+            None => return,
+        };
+
+        let mut src = Frame::new().unwrap();
+        src.format = ffi::AVPixelFormat_AV_PIX_FMT_NV12.to_int();
+        src.width = 64;
+        src.height = 64;
+        src.pts = 42;
+        assert_eq!(unsafe { ffi::av_frame_get_buffer(src.as_mut_ptr(), 0) }, 0);
+
+        match hwframe_upload(&hw_frames_ctx, &src) {
+            Ok(hw_frame) => {
+                assert_eq!(hw_frame.pts, 42);
+                let downloaded = hwframe_download(&hw_frame).unwrap();
+                assert_eq!((downloaded.width, downloaded.height), (64, 64));
+                assert_eq!(downloaded.pts, 42);
+            }
+            Err(e) => assert_eq!(e, FfmpegError::Unsupported),
+        }
+        assert_eq!(
+            hwframe_download(&src).unwrap_err(),
+            FfmpegError::Av(AVERROR_EINVAL)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Copying video frames to and from single contiguous buffers, in the layout
+//! of `av_image_fill_arrays()`: the planes one after the other, each row
+//! padded to a multiple of `align` bytes (1 for no padding).
+
+use super::ffi_enum::FfiEnum;
+use crate::ffi::{self, AVFrame, AVPixelFormat, FfmpegError, Frame, AVERROR_EINVAL};
+use std::{convert::TryFrom, ptr};
+
+fn check(ret: libc::c_int) -> Result<libc::c_int, FfmpegError> {
+    if ret < 0 {
+        Err(FfmpegError::from_averror(ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+fn c_int(x: usize) -> Result<libc::c_int, FfmpegError> {
+    libc::c_int::try_from(x).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))
+}
+
+/// Copy the planes of a video frame into one buffer sized with
+/// `av_image_get_buffer_size()`, with `av_image_copy_to_buffer()`.
+///
+/// # Safety
+/// The data and line sizes of `frame` must be valid for its format, width and
+/// height.
+pub unsafe fn frame_to_vec(frame: &AVFrame, align: usize) -> Result<Vec<u8>, FfmpegError> {
+    let align = c_int(align)?;
+    if frame.data[0].is_null() {
+        return Err(FfmpegError::Av(AVERROR_EINVAL));
+    }
+    let size = check(ffi::av_image_get_buffer_size(
+        AVPixelFormat::from_int(frame.format),
+        frame.width,
+        frame.height,
+        align,
+    ))?;
+    let mut buffer = vec![0; size as usize];
+    check(ffi::av_image_copy_to_buffer(
+        buffer.as_mut_ptr(),
+        size,
+        frame.data.as_ptr() as _,
+        frame.linesize.as_ptr(),
+        AVPixelFormat::from_int(frame.format),
+        frame.width,
+        frame.height,
+        align,
+    ))?;
+    Ok(buffer)
+}
+
+/// A new frame with buffers from `av_frame_get_buffer()`, filled from a
+/// buffer in the layout `frame_to_vec()` produces for the same `align`.
+/// `AVERROR(EINVAL)` if `data` is too short.
+pub fn frame_from_slice(
+    data: &[u8],
+    format: AVPixelFormat,
+    width: usize,
+    height: usize,
+    align: usize,
+) -> Result<Frame, FfmpegError> {
+    let (width, height, align) = (c_int(width)?, c_int(height)?, c_int(align)?);
+    let size = check(unsafe { ffi::av_image_get_buffer_size(format, width, height, align) })?;
+    if data.len() < size as usize {
+        return Err(FfmpegError::Av(AVERROR_EINVAL));
+    }
+
+    let mut frame = Frame::new()?;
+    frame.format = format.to_int();
+    frame.width = width;
+    frame.height = height;
+    check(unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) })?;
+
+    let mut src_data = [ptr::null_mut(); 4];
+    let mut src_linesize = [0; 4];
+    unsafe {
+        check(ffi::av_image_fill_arrays(
+            src_data.as_mut_ptr(),
+            src_linesize.as_mut_ptr(),
+            data.as_ptr(),
+            format,
+            width,
+            height,
+            align,
+        ))?;
+        let dst = &mut *frame.as_mut_ptr();
+        // `uint8_t *[4]` before FFmpeg 6.1, `const uint8_t *const [4]` after.
+        ffi::av_image_copy(
+            dst.data.as_mut_ptr(),
+            dst.linesize.as_mut_ptr(),
+            src_data.as_mut_ptr() as _,
+            src_linesize.as_ptr(),
+            format,
+            width,
+            height,
+        );
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        // Odd sizes, so the chroma planes are rounded up and the frame rows
+        // are padded.
+        let yuv: Vec<u8> = (0..5 * 3 + 2 * 3 * 2).map(|i| i as u8).collect();
+        let frame = frame_from_slice(&yuv, ffi::AVPixelFormat_AV_PIX_FMT_YUV420P, 5, 3, 1).unwrap();
+        assert!(frame.linesize[0] > 5);
+        assert_eq!(unsafe { frame_to_vec(&frame, 1) }.unwrap(), yuv);
+
+        let rgba: Vec<u8> = (0..3 * 2 * 4).map(|i| 255 - i as u8).collect();
+        let frame = frame_from_slice(&rgba, ffi::AVPixelFormat_AV_PIX_FMT_RGBA, 3, 2, 1).unwrap();
+        assert_eq!(unsafe { frame_to_vec(&frame, 1) }.unwrap(), rgba);
+
+        // Rows of 12 bytes padded to 16.
+        let aligned = unsafe { frame_to_vec(&frame, 16) }.unwrap();
+        assert_eq!(aligned.len(), 32);
+        assert_eq!(aligned[..12], rgba[..12]);
+        assert_eq!(aligned[16..28], rgba[12..]);
+        let frame =
+            frame_from_slice(&aligned, ffi::AVPixelFormat_AV_PIX_FMT_RGBA, 3, 2, 16).unwrap();
+        assert_eq!(unsafe { frame_to_vec(&frame, 1) }.unwrap(), rgba);
+
+        assert!(frame_from_slice(&rgba[1..], ffi::AVPixelFormat_AV_PIX_FMT_RGBA, 3, 2, 1).is_err());
+        let empty = Frame::new().unwrap();
+        assert!(unsafe { frame_to_vec(&empty, 1) }.is_err());
+    }
+}
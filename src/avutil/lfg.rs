@@ -0,0 +1,142 @@
+use crate::ffi::{self, AVLFG};
+
+/// Get the next random unsigned 32-bit number using an ALFG.
+///
+/// Please also consider a simple LCG like `state = state*1664525+1013904223`,
+/// it may be good enough and faster for your specific use case.
+pub fn av_lfg_get(c: &mut AVLFG) -> libc::c_uint {
+    let a = c.state[c.index.wrapping_sub(24) as usize & 63]
+        .wrapping_add(c.state[c.index.wrapping_sub(55) as usize & 63]);
+    c.state[c.index as usize & 63] = a;
+    c.index = c.index.wrapping_add(1);
+    a
+}
+
+/// Get the next random unsigned 32-bit number using a MLFG.
+///
+/// Please also consider av_lfg_get() above, it is faster.
+pub fn av_mlfg_get(c: &mut AVLFG) -> libc::c_uint {
+    let a = c.state[c.index.wrapping_sub(55) as usize & 63];
+    let b = c.state[c.index.wrapping_sub(24) as usize & 63];
+    let a = 2u32
+        .wrapping_mul(a)
+        .wrapping_mul(b)
+        .wrapping_add(a)
+        .wrapping_add(b);
+    c.state[c.index as usize & 63] = a;
+    c.index = c.index.wrapping_add(1);
+    a
+}
+
+/// FFmpeg's lagged Fibonacci generator, producing the same sequence as the C
+/// implementation for a given seed (the one used by the noise filters, for
+/// example). With the `rand` feature it implements `rand_core::RngCore`.
+#[derive(Clone, Copy)]
+pub struct AvLfg(pub AVLFG);
+
+impl AvLfg {
+    /// Seeded with `av_lfg_init()`.
+    pub fn new(seed: u32) -> Self {
+        let mut lfg = AVLFG {
+            state: [0; 64],
+            index: 0,
+        };
+        unsafe { ffi::av_lfg_init(&mut lfg, seed) };
+        Self(lfg)
+    }
+
+    /// A seed from `av_get_random_seed()`, which uses the system's entropy
+    /// source if there is one.
+    pub fn random_seed() -> u32 {
+        unsafe { ffi::av_get_random_seed() }
+    }
+
+    /// Two independent normally distributed values (mean 0, standard
+    /// deviation 1), from `av_bmg_get()`.
+    pub fn bmg_pair(&mut self) -> (f64, f64) {
+        let mut out = [0.0; 2];
+        unsafe { ffi::av_bmg_get(&mut self.0, out.as_mut_ptr()) };
+        (out[0], out[1])
+    }
+}
+
+impl core::fmt::Debug for AvLfg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AvLfg")
+            .field("index", &self.0.index)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for AvLfg {
+    fn next_u32(&mut self) -> u32 {
+        av_lfg_get(&mut self.0)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The first values of the C implementation for this seed.
+    const DEADBEEF: [u32; 8] = [
+        0xa89688cc, 0xcf4eb0f3, 0xdf461dee, 0xa734e989, 0x24592a9f, 0xc475ebbe, 0x7145fb00,
+        0x66a1f6a2,
+    ];
+
+    #[test]
+    fn test_lfg_sequence() {
+        let mut lfg = AvLfg::new(0xdeadbeef);
+        for &expected in &DEADBEEF {
+            assert_eq!(av_lfg_get(&mut lfg.0), expected);
+        }
+        let mut lfg = AvLfg::new(1);
+        assert_eq!(av_lfg_get(&mut lfg.0), 0x15e3c112);
+
+        let mut lfg = AvLfg::new(0xdeadbeef);
+        let sum = (0..10000).fold(0u32, |sum, _| sum.wrapping_add(av_lfg_get(&mut lfg.0)));
+        assert_eq!(sum, 0x5bab403b);
+    }
+
+    #[test]
+    fn test_bmg_pair() {
+        let mut lfg = AvLfg::new(0xdeadbeef);
+        let (a, b) = lfg.bmg_pair();
+        assert!((a - 0.5485062386690852).abs() < 1e-12);
+        assert!((b - 1.0717593914305499).abs() < 1e-12);
+        // Two values were consumed.
+        assert_eq!(lfg.0.index, 2);
+        let _ = AvLfg::random_seed();
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_rng_core() {
+        use rand_core::RngCore;
+
+        let mut lfg = AvLfg::new(0xdeadbeef);
+        assert_eq!(lfg.next_u32(), DEADBEEF[0]);
+        assert_eq!(
+            lfg.next_u64(),
+            u64::from(DEADBEEF[2]) << 32 | u64::from(DEADBEEF[1])
+        );
+        let mut bytes = [0u8; 6];
+        lfg.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..4], DEADBEEF[3].to_le_bytes());
+        assert_eq!(bytes[4..], DEADBEEF[4].to_le_bytes()[..2]);
+    }
+}
@@ -1,7 +1,48 @@
-pub mod _avutil;
-pub mod common;
+pub(crate) mod _avutil;
+pub(crate) mod buffer;
+#[cfg(feature = "std")]
+pub(crate) mod channel_layout;
+#[cfg(feature = "std")]
+pub(crate) mod colorimetry;
+pub(crate) mod common;
+#[cfg(feature = "std")]
+pub(crate) mod dict;
+pub(crate) mod downmix_info;
 #[rustfmt::skip]
-pub mod error;
+pub(crate) mod error;
+// Only the parser converts enums without `std`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) mod ffi_enum;
+pub(crate) mod frame;
+#[cfg(feature = "std")]
+pub(crate) mod hmac;
+#[cfg(feature = "std")]
+pub(crate) mod hwcontext;
+#[cfg(feature = "std")]
+pub(crate) mod imgutils;
+pub(crate) mod lfg;
 #[rustfmt::skip]
-pub mod pixfmt;
-pub mod rational;
+pub(crate) mod pixfmt;
+#[cfg(feature = "std")]
+pub(crate) mod planes;
+pub(crate) mod rational;
+#[cfg(feature = "num-rational")]
+mod rational_num;
+pub(crate) mod replaygain;
+#[cfg(feature = "image")]
+pub(crate) mod rgb_image;
+#[cfg(feature = "std")]
+pub(crate) mod stereo3d;
+pub(crate) mod timestamp;
+
+#[cfg(feature = "image")]
+pub use self::rgb_image::*;
+pub use self::{
+    _avutil::*, buffer::*, common::*, downmix_info::*, error::*, frame::*, lfg::*, pixfmt::*,
+    rational::*, replaygain::*, timestamp::*,
+};
+#[cfg(feature = "std")]
+pub use self::{
+    channel_layout::*, colorimetry::*, dict::*, hmac::*, hwcontext::*, planes::*, stereo3d::*,
+};
+include!(concat!(env!("OUT_DIR"), "/namespace/avutil.rs"));
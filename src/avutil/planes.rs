@@ -0,0 +1,366 @@
+//! Borrowed views of the planes of video frames and the samples of audio
+//! frames, without the padding FFmpeg adds to the rows.
+
+use super::ffi_enum::FfiEnum;
+use crate::ffi::{self, AVFrame, AVPixelFormat, AVSampleFormat, FfmpegError, AVERROR_EINVAL};
+use std::{convert::TryFrom, marker::PhantomData, slice};
+
+fn check(ret: libc::c_int) -> Result<libc::c_int, FfmpegError> {
+    if ret < 0 {
+        Err(FfmpegError::from_averror(ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+/// A plane of a video frame. Rows are `linesize` bytes apart, which is
+/// negative for bottom-up images.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<'a> {
+    data: *const u8,
+    linesize: isize,
+    row_size: usize,
+    height: usize,
+    _frame: PhantomData<&'a [u8]>,
+}
+
+impl<'a> Plane<'a> {
+    /// Bytes of pixel data in a row.
+    pub fn row_size(&self) -> usize {
+        self.row_size
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn linesize(&self) -> isize {
+        self.linesize
+    }
+
+    /// The rows from top to bottom, each `row_size()` bytes.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let Plane {
+            data,
+            linesize,
+            row_size,
+            height,
+            ..
+        } = *self;
+        (0..height as isize)
+            .map(move |y| unsafe { slice::from_raw_parts(data.offset(y * linesize), row_size) })
+    }
+}
+
+/// A plane of a writable video frame, see [`Plane`].
+#[derive(Debug)]
+pub struct PlaneMut<'a> {
+    data: *mut u8,
+    linesize: isize,
+    row_size: usize,
+    height: usize,
+    _frame: PhantomData<&'a mut [u8]>,
+}
+
+impl PlaneMut<'_> {
+    /// Bytes of pixel data in a row.
+    pub fn row_size(&self) -> usize {
+        self.row_size
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn linesize(&self) -> isize {
+        self.linesize
+    }
+
+    /// The rows from top to bottom, each `row_size()` bytes.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let (data, linesize, row_size) = (self.data as *const u8, self.linesize, self.row_size);
+        (0..self.height as isize)
+            .map(move |y| unsafe { slice::from_raw_parts(data.offset(y * linesize), row_size) })
+    }
+
+    /// The rows from top to bottom, each `row_size()` bytes.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let (data, linesize, row_size) = (self.data, self.linesize, self.row_size);
+        // Rows don't overlap since `|linesize| >= row_size`.
+        (0..self.height as isize)
+            .map(move |y| unsafe { slice::from_raw_parts_mut(data.offset(y * linesize), row_size) })
+    }
+}
+
+/// Per plane: the data pointer, line size, row size and height.
+unsafe fn plane_geometry(
+    frame: &AVFrame,
+) -> Result<Vec<(*mut u8, isize, usize, usize)>, FfmpegError> {
+    let invalid = FfmpegError::Av(AVERROR_EINVAL);
+    let desc = ffi::av_pix_fmt_desc_get(AVPixelFormat::from_int(frame.format))
+        .as_ref()
+        .ok_or(invalid)?;
+    let opaque = (ffi::AV_PIX_FMT_FLAG_HWACCEL
+        | ffi::AV_PIX_FMT_FLAG_PAL
+        | ffi::AV_PIX_FMT_FLAG_BITSTREAM) as u64;
+    if desc.flags & opaque != 0 {
+        return Err(FfmpegError::Unsupported);
+    }
+    let height = usize::try_from(frame.height).map_err(|_| invalid)?;
+    let mut row_sizes = [0; 4];
+    check(ffi::av_image_fill_linesizes(
+        row_sizes.as_mut_ptr(),
+        AVPixelFormat::from_int(frame.format),
+        frame.width,
+    ))?;
+    let nb_planes = check(ffi::av_pix_fmt_count_planes(AVPixelFormat::from_int(
+        frame.format,
+    )))? as usize;
+    (0..nb_planes)
+        .map(|i| {
+            let row_size = row_sizes[i] as usize;
+            let linesize = frame.linesize[i] as isize;
+            if frame.data[i].is_null() || linesize.unsigned_abs() < row_size {
+                return Err(invalid);
+            }
+            // Planes 1 and 2 are the chroma ones, like in
+            // `av_image_fill_plane_sizes()`.
+            let height = if i == 1 || i == 2 {
+                (height + (1 << desc.log2_chroma_h) - 1) >> desc.log2_chroma_h
+            } else {
+                height
+            };
+            Ok((frame.data[i], linesize, row_size, height))
+        })
+        .collect()
+}
+
+/// The planes of a video frame, e.g. Y, U and V for `AV_PIX_FMT_YUV420P`, or Y
+/// and the interleaved UV for `AV_PIX_FMT_NV12`. Hardware, palette and
+/// bitstream formats are `FfmpegError::Unsupported`.
+///
+/// # Safety
+/// The data and line sizes of `frame` must be valid for its format, width and
+/// height.
+pub unsafe fn frame_planes(frame: &AVFrame) -> Result<Vec<Plane<'_>>, FfmpegError> {
+    Ok(plane_geometry(frame)?
+        .into_iter()
+        .map(|(data, linesize, row_size, height)| Plane {
+            data,
+            linesize,
+            row_size,
+            height,
+            _frame: PhantomData,
+        })
+        .collect())
+}
+
+/// [`frame_planes()`] for writing, after copying the data with
+/// `av_frame_make_writable()` if its buffers are shared.
+///
+/// # Safety
+/// The data and line sizes of `frame` must be valid for its format, width and
+/// height, and it has to be reference counted.
+pub unsafe fn frame_planes_mut(frame: &mut AVFrame) -> Result<Vec<PlaneMut<'_>>, FfmpegError> {
+    check(ffi::av_frame_make_writable(frame))?;
+    Ok(plane_geometry(frame)?
+        .into_iter()
+        .map(|(data, linesize, row_size, height)| PlaneMut {
+            data,
+            linesize,
+            row_size,
+            height,
+            _frame: PhantomData,
+        })
+        .collect())
+}
+
+/// Types of audio samples, with their packed and planar sample formats.
+pub trait Sample: Copy {
+    const PACKED: AVSampleFormat;
+    const PLANAR: AVSampleFormat;
+}
+
+macro_rules! impl_sample {
+    ($($ty:ty => $packed:ident, $planar:ident;)*) => {
+        $(
+            impl Sample for $ty {
+                const PACKED: AVSampleFormat = ffi::$packed;
+                const PLANAR: AVSampleFormat = ffi::$planar;
+            }
+        )*
+    };
+}
+
+impl_sample! {
+    u8 => AVSampleFormat_AV_SAMPLE_FMT_U8, AVSampleFormat_AV_SAMPLE_FMT_U8P;
+    i16 => AVSampleFormat_AV_SAMPLE_FMT_S16, AVSampleFormat_AV_SAMPLE_FMT_S16P;
+    i32 => AVSampleFormat_AV_SAMPLE_FMT_S32, AVSampleFormat_AV_SAMPLE_FMT_S32P;
+    i64 => AVSampleFormat_AV_SAMPLE_FMT_S64, AVSampleFormat_AV_SAMPLE_FMT_S64P;
+    f32 => AVSampleFormat_AV_SAMPLE_FMT_FLT, AVSampleFormat_AV_SAMPLE_FMT_FLTP;
+    f64 => AVSampleFormat_AV_SAMPLE_FMT_DBL, AVSampleFormat_AV_SAMPLE_FMT_DBLP;
+}
+
+fn nb_channels(frame: &AVFrame) -> libc::c_int {
+    #[cfg(ffmpeg_5_1)]
+    {
+        frame.ch_layout.nb_channels
+    }
+    #[cfg(not(ffmpeg_5_1))]
+    {
+        frame.channels
+    }
+}
+
+/// The samples of an audio frame in the sample format of `T`: one slice per
+/// channel for planar formats, a single slice of interleaved samples for
+/// packed ones. Other sample formats are `FfmpegError::Unsupported`.
+///
+/// # Safety
+/// The data of `frame` must be valid for its sample format, channel count and
+/// number of samples.
+pub unsafe fn frame_samples<T: Sample>(frame: &AVFrame) -> Result<Vec<&[T]>, FfmpegError> {
+    let invalid = FfmpegError::Av(AVERROR_EINVAL);
+    let nb_samples = usize::try_from(frame.nb_samples).map_err(|_| invalid)?;
+    let nb_channels = usize::try_from(nb_channels(frame)).map_err(|_| invalid)?;
+    let (nb_planes, plane_len) = match AVSampleFormat::from_int(frame.format) {
+        format if format == T::PACKED => (1, nb_samples * nb_channels),
+        format if format == T::PLANAR => (nb_channels, nb_samples),
+        _ => return Err(FfmpegError::Unsupported),
+    };
+    if frame.extended_data.is_null() {
+        return Err(invalid);
+    }
+    (0..nb_planes)
+        .map(|i| {
+            let data = *frame.extended_data.add(i) as *const T;
+            if data.is_null() {
+                Err(invalid)
+            } else {
+                Ok(slice::from_raw_parts(data, plane_len))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::Frame;
+
+    fn video_frame(format: ffi::AVPixelFormat, width: i32, height: i32) -> Frame {
+        let mut frame = Frame::new().unwrap();
+        frame.format = format.to_int();
+        frame.width = width;
+        frame.height = height;
+        check(unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) }).unwrap();
+        frame
+    }
+
+    fn geometry(planes: &[Plane]) -> Vec<(usize, usize)> {
+        planes
+            .iter()
+            .map(|plane| (plane.row_size(), plane.height()))
+            .collect()
+    }
+
+    #[test]
+    fn test_frame_planes() {
+        let frame = video_frame(ffi::AVPixelFormat_AV_PIX_FMT_YUV420P, 5, 3);
+        let planes = unsafe { frame_planes(&frame) }.unwrap();
+        assert_eq!(geometry(&planes), [(5, 3), (3, 2), (3, 2)]);
+        assert!(planes[0].rows().all(|row| row.len() == 5));
+        assert_eq!(planes[1].rows().count(), 2);
+
+        let frame = video_frame(ffi::AVPixelFormat_AV_PIX_FMT_NV12, 6, 4);
+        let planes = unsafe { frame_planes(&frame) }.unwrap();
+        assert_eq!(geometry(&planes), [(6, 4), (6, 2)]);
+
+        let frame = video_frame(ffi::AVPixelFormat_AV_PIX_FMT_RGBA, 3, 2);
+        let planes = unsafe { frame_planes(&frame) }.unwrap();
+        assert_eq!(geometry(&planes), [(12, 2)]);
+
+        let mut frame = video_frame(ffi::AVPixelFormat_AV_PIX_FMT_PAL8, 3, 2);
+        assert_eq!(
+            unsafe { frame_planes(&frame) }.unwrap_err(),
+            FfmpegError::Unsupported
+        );
+        frame.unref();
+        frame.format = ffi::AVPixelFormat_AV_PIX_FMT_GRAY8.to_int();
+        assert!(unsafe { frame_planes(&frame) }.is_err());
+    }
+
+    #[test]
+    fn test_frame_planes_mut() {
+        let mut frame = video_frame(ffi::AVPixelFormat_AV_PIX_FMT_YUV420P, 4, 4);
+        let mut shared = Frame::new().unwrap();
+        check(unsafe { ffi::av_frame_ref(shared.as_mut_ptr(), frame.as_ptr()) }).unwrap();
+
+        let mut planes = unsafe { frame_planes_mut(&mut frame) }.unwrap();
+        for (i, plane) in planes.iter_mut().enumerate() {
+            for row in plane.rows_mut() {
+                row.fill(i as u8 + 1);
+            }
+        }
+        drop(planes);
+        // The shared buffers were copied before writing.
+        assert_ne!(frame.data[0], shared.data[0]);
+        let planes = unsafe { frame_planes(&frame) }.unwrap();
+        for (i, plane) in planes.iter().enumerate() {
+            assert!(plane.rows().flatten().all(|&b| b == i as u8 + 1));
+        }
+    }
+
+    #[test]
+    fn test_negative_linesize() {
+        // A bottom-up 2x3 gray image: the top row is the last in memory.
+        let mut buffer = [0u8; 3 * 4];
+        buffer[..2].copy_from_slice(&[5, 6]);
+        buffer[4..6].copy_from_slice(&[3, 4]);
+        buffer[8..10].copy_from_slice(&[1, 2]);
+        let mut frame = Frame::new().unwrap();
+        frame.format = ffi::AVPixelFormat_AV_PIX_FMT_GRAY8.to_int();
+        frame.width = 2;
+        frame.height = 3;
+        frame.data[0] = unsafe { buffer.as_mut_ptr().add(8) };
+        frame.linesize[0] = -4;
+
+        let planes = unsafe { frame_planes(&frame) }.unwrap();
+        let rows: Vec<_> = planes[0].rows().collect();
+        assert_eq!(rows, [[1, 2], [3, 4], [5, 6]]);
+    }
+
+    fn audio_frame(format: AVSampleFormat) -> Frame {
+        let mut frame = Frame::new().unwrap();
+        frame.format = format.to_int();
+        frame.nb_samples = 4;
+        #[cfg(ffmpeg_5_1)]
+        unsafe {
+            ffi::av_channel_layout_default(&mut frame.ch_layout, 2)
+        };
+        #[cfg(not(ffmpeg_5_1))]
+        {
+            frame.channels = 2;
+            frame.channel_layout = ffi::AV_CH_LAYOUT_STEREO as u64;
+        }
+        check(unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) }).unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_frame_samples() {
+        let frame = audio_frame(ffi::AVSampleFormat_AV_SAMPLE_FMT_FLTP);
+        let channels = unsafe { frame_samples::<f32>(&frame) }.unwrap();
+        assert_eq!(channels.len(), 2);
+        assert!(channels.iter().all(|samples| samples.len() == 4));
+        assert_eq!(
+            unsafe { frame_samples::<i16>(&frame) }.unwrap_err(),
+            FfmpegError::Unsupported
+        );
+
+        let frame = audio_frame(ffi::AVSampleFormat_AV_SAMPLE_FMT_S16);
+        let interleaved = unsafe { frame_samples::<i16>(&frame) }.unwrap();
+        assert_eq!(interleaved.len(), 1);
+        assert_eq!(interleaved[0].len(), 8);
+    }
+}
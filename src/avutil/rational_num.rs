@@ -0,0 +1,148 @@
+//! Interop of `AVRational` with `num-rational` and `num-traits`. The
+//! arithmetic goes through `av_add_q()` and friends, so results are reduced
+//! (and approximated if they don't fit an `int`) exactly like FFmpeg does.
+
+use crate::ffi::{self, AVRational, FfmpegError, AVERROR_ERANGE};
+use core::{
+    convert::TryFrom,
+    ops::{Add, Div, Mul, Sub},
+};
+use num_rational::{Ratio, Rational32};
+use num_traits::{One, Zero};
+
+/// Reduced, with the sign in the numerator.
+///
+/// # Panics
+/// Like `Ratio::new()`, if the denominator is 0.
+impl From<AVRational> for Rational32 {
+    fn from(q: AVRational) -> Self {
+        Ratio::new(q.num, q.den)
+    }
+}
+
+/// Reduced with `av_reduce()`, which approximates values whose numerator or
+/// denominator don't fit an `int`. Values out of the range of an `int`, and
+/// a zero denominator, are `AVERROR(ERANGE)`.
+impl TryFrom<Ratio<i64>> for AVRational {
+    type Error = FfmpegError;
+
+    fn try_from(q: Ratio<i64>) -> Result<Self, FfmpegError> {
+        let (num, den) = (*q.numer(), *q.denom());
+        // `av_reduce()` takes the absolute values, which don't exist for
+        // `i64::MIN`.
+        if den == 0
+            || num == i64::MIN
+            || den == i64::MIN
+            || i128::from(num).abs() > i128::from(i32::MAX) * i128::from(den).abs()
+        {
+            return Err(FfmpegError::Av(AVERROR_ERANGE));
+        }
+        let mut q = AVRational { num: 0, den: 1 };
+        unsafe { ffi::av_reduce(&mut q.num, &mut q.den, num, den, i64::from(i32::MAX)) };
+        Ok(q)
+    }
+}
+
+macro_rules! impl_op {
+    ($op:ident, $method:ident, $av_op:ident) => {
+        impl $op for AVRational {
+            type Output = AVRational;
+
+            fn $method(self, other: AVRational) -> AVRational {
+                unsafe { ffi::$av_op(self, other) }
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, av_add_q);
+impl_op!(Sub, sub, av_sub_q);
+impl_op!(Mul, mul, av_mul_q);
+impl_op!(Div, div, av_div_q);
+
+impl Zero for AVRational {
+    fn zero() -> Self {
+        AVRational { num: 0, den: 1 }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0 && self.den != 0
+    }
+}
+
+impl One for AVRational {
+    fn one() -> Self {
+        AVRational { num: 1, den: 1 }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn q(num: i32, den: i32) -> AVRational {
+        AVRational { num, den }
+    }
+
+    fn assert_q_eq(a: AVRational, b: AVRational) {
+        assert_eq!((a.num, a.den), (b.num, b.den));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Rational32::from(q(2, -4)), Rational32::new(-1, 2));
+        assert_q_eq(
+            AVRational::try_from(Ratio::new(30000i64, 1001)).unwrap(),
+            q(30000, 1001),
+        );
+        assert_q_eq(
+            AVRational::try_from(Ratio::new(-6i64, 4)).unwrap(),
+            q(-3, 2),
+        );
+        // Fits in range but not in precision, approximated.
+        let approximated = AVRational::try_from(Ratio::new(1i64 << 40, (1 << 40) + 1)).unwrap();
+        assert!(approximated.num > 0 && approximated.num <= approximated.den);
+        assert_eq!(
+            AVRational::try_from(Ratio::new(1i64 << 40, 1)).unwrap_err(),
+            FfmpegError::Av(AVERROR_ERANGE)
+        );
+        assert!(AVRational::try_from(Ratio::new_raw(1i64, 0)).is_err());
+    }
+
+    #[test]
+    fn test_zero_one() {
+        assert!(AVRational::zero().is_zero());
+        assert!(!q(0, 0).is_zero());
+        assert_q_eq(q(3, 4) * AVRational::one(), q(3, 4));
+        assert_q_eq(q(3, 4) + AVRational::zero(), q(3, 4));
+    }
+
+    /// Arithmetic of small random rationals, where FFmpeg's results are
+    /// exact, matches `num-rational`.
+    #[test]
+    fn test_arithmetic_matches_num_rational() {
+        // A fixed xorshift sequence, to be reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move |range: i64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % range as u64) as i64
+        };
+        for _ in 0..1000 {
+            let a = Ratio::new(next(2001) - 1000, next(1000) + 1);
+            let b = Ratio::new(next(2001) - 1000, next(1000) + 1);
+            let (av_a, av_b) = (
+                AVRational::try_from(a).unwrap(),
+                AVRational::try_from(b).unwrap(),
+            );
+            let expected = |r: Ratio<i64>| AVRational::try_from(r).unwrap();
+            assert_q_eq(av_a + av_b, expected(a + b));
+            assert_q_eq(av_a - av_b, expected(a - b));
+            assert_q_eq(av_a * av_b, expected(a * b));
+            if !b.is_zero() {
+                assert_q_eq(av_a / av_b, expected(a / b));
+            }
+        }
+    }
+}
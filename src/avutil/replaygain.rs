@@ -0,0 +1,189 @@
+//! ReplayGain side data (`AV_PKT_DATA_REPLAYGAIN` of streams), with the
+//! fixed point fields converted to dB and linear peaks.
+
+use crate::ffi::AVReplayGain;
+#[cfg(feature = "avformat")]
+use crate::ffi::{self, stream_side_data, AVStream};
+#[cfg(feature = "avformat")]
+use core::{mem, ptr};
+
+/// `AVReplayGain` gains are in microbels, peaks in 1/100000 of full scale.
+const SCALE: f64 = 100000.0;
+
+/// Round half away from zero, `f64::round()` needs `std`. The cast saturates.
+fn round_i64(x: f64) -> i64 {
+    if x < 0.0 {
+        (x - 0.5) as i64
+    } else {
+        (x + 0.5) as i64
+    }
+}
+
+/// `AVReplayGain` with the unknown values as `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReplayGain {
+    /// Track gain in dB.
+    pub track_gain: Option<f64>,
+    /// Track peak amplitude, 1.0 is full scale (but it may be larger).
+    pub track_peak: Option<f64>,
+    /// Album gain in dB.
+    pub album_gain: Option<f64>,
+    /// Album peak amplitude, 1.0 is full scale (but it may be larger).
+    pub album_peak: Option<f64>,
+}
+
+fn gain_from_av(gain: i32) -> Option<f64> {
+    if gain == i32::MIN {
+        None
+    } else {
+        Some(f64::from(gain) / SCALE)
+    }
+}
+
+fn peak_from_av(peak: u32) -> Option<f64> {
+    if peak == 0 {
+        None
+    } else {
+        Some(f64::from(peak) / SCALE)
+    }
+}
+
+fn gain_to_av(gain: Option<f64>) -> i32 {
+    gain.map_or(i32::MIN, |gain| {
+        round_i64(gain * SCALE).clamp(i64::from(i32::MIN) + 1, i64::from(i32::MAX)) as i32
+    })
+}
+
+fn peak_to_av(peak: Option<f64>) -> u32 {
+    peak.map_or(0, |peak| {
+        round_i64(peak * SCALE).clamp(0, i64::from(u32::MAX)) as u32
+    })
+}
+
+/// Gains of `INT32_MIN` and peaks of 0 mean unknown.
+impl From<AVReplayGain> for ReplayGain {
+    fn from(replaygain: AVReplayGain) -> Self {
+        Self {
+            track_gain: gain_from_av(replaygain.track_gain),
+            track_peak: peak_from_av(replaygain.track_peak),
+            album_gain: gain_from_av(replaygain.album_gain),
+            album_peak: peak_from_av(replaygain.album_peak),
+        }
+    }
+}
+
+/// Rounded to the nearest microbel and 1/100000 of full scale, and clamped to
+/// the range of the fields. A peak rounding to 0 becomes unknown.
+impl From<ReplayGain> for AVReplayGain {
+    fn from(replaygain: ReplayGain) -> Self {
+        Self {
+            track_gain: gain_to_av(replaygain.track_gain),
+            track_peak: peak_to_av(replaygain.track_peak),
+            album_gain: gain_to_av(replaygain.album_gain),
+            album_peak: peak_to_av(replaygain.album_peak),
+        }
+    }
+}
+
+/// The ReplayGain side data of a stream, e.g. from the tags of an MP3 or
+/// FLAC file.
+#[cfg(feature = "avformat")]
+pub fn stream_replaygain(stream: &AVStream) -> Option<ReplayGain> {
+    let data = stream_side_data(stream, ffi::AVPacketSideDataType_AV_PKT_DATA_REPLAYGAIN)?;
+    if data.len() < mem::size_of::<AVReplayGain>() {
+        return None;
+    }
+    let replaygain = unsafe { ptr::read_unaligned(data.as_ptr() as *const AVReplayGain) };
+    Some(replaygain.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let replaygain = ReplayGain::from(AVReplayGain {
+            track_gain: -650000,
+            track_peak: 98765,
+            album_gain: 123456,
+            album_peak: 0,
+        });
+        assert_eq!(
+            replaygain,
+            ReplayGain {
+                track_gain: Some(-6.5),
+                track_peak: Some(0.98765),
+                album_gain: Some(1.23456),
+                album_peak: None,
+            }
+        );
+        let back = AVReplayGain::from(replaygain);
+        assert_eq!(
+            (
+                back.track_gain,
+                back.track_peak,
+                back.album_gain,
+                back.album_peak
+            ),
+            (-650000, 98765, 123456, 0)
+        );
+
+        let unknown = AVReplayGain::from(ReplayGain::default());
+        assert_eq!((unknown.track_gain, unknown.track_peak), (i32::MIN, 0));
+        assert_eq!(ReplayGain::from(unknown), ReplayGain::default());
+
+        let rounded = AVReplayGain::from(ReplayGain {
+            track_gain: Some(-0.000004),
+            track_peak: Some(1e9),
+            album_gain: Some(-1e9),
+            album_peak: Some(-1.0),
+        });
+        assert_eq!(
+            (
+                rounded.track_gain,
+                rounded.track_peak,
+                rounded.album_gain,
+                rounded.album_peak
+            ),
+            (0, u32::MAX, i32::MIN + 1, 0)
+        );
+    }
+
+    #[cfg(feature = "avformat")]
+    #[test]
+    fn test_stream_replaygain() {
+        use crate::ffi::new_stream_side_data;
+
+        unsafe {
+            let ctx = ffi::avformat_alloc_context();
+            let stream = ffi::avformat_new_stream(ctx, ptr::null());
+            assert_eq!(stream_replaygain(&*stream), None);
+
+            let data = new_stream_side_data(
+                stream,
+                ffi::AVPacketSideDataType_AV_PKT_DATA_REPLAYGAIN,
+                mem::size_of::<AVReplayGain>(),
+            );
+            ptr::write_unaligned(
+                data as *mut AVReplayGain,
+                AVReplayGain {
+                    track_gain: -300000,
+                    track_peak: 50000,
+                    album_gain: i32::MIN,
+                    album_peak: 0,
+                },
+            );
+            assert_eq!(
+                stream_replaygain(&*stream),
+                Some(ReplayGain {
+                    track_gain: Some(-3.0),
+                    track_peak: Some(0.5),
+                    album_gain: None,
+                    album_peak: None,
+                })
+            );
+            ffi::avformat_free_context(ctx);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Interop with the `image` crate for frames in packed RGB formats, e.g. the
+//! output of `sws_scale()` to `AV_PIX_FMT_RGB24`.
+
+use super::ffi_enum::FfiEnum;
+use crate::ffi::{
+    self, AVFrame, AVPixelFormat, AVPixelFormat_AV_PIX_FMT_RGB24, FfmpegError, Frame,
+    AVERROR_EINVAL,
+};
+use image::{ImageBuffer, Rgb, RgbImage, Rgba};
+use std::{borrow::Cow, convert::TryFrom, ptr, slice};
+
+/// The pixels of a frame as an image. The frame data is borrowed if its rows
+/// are tightly packed, else it's copied once into a packed buffer.
+#[derive(Debug)]
+pub enum ImageView<'a> {
+    /// `AV_PIX_FMT_RGB24`
+    Rgb(ImageBuffer<Rgb<u8>, Cow<'a, [u8]>>),
+    /// `AV_PIX_FMT_RGBA`
+    Rgba(ImageBuffer<Rgba<u8>, Cow<'a, [u8]>>),
+}
+
+impl ImageView<'_> {
+    /// Whether the frame data is used without a copy.
+    pub fn is_borrowed(&self) -> bool {
+        let pixels = match self {
+            ImageView::Rgb(image) => image.as_raw(),
+            ImageView::Rgba(image) => image.as_raw(),
+        };
+        matches!(pixels, Cow::Borrowed(_))
+    }
+}
+
+/// The first plane of a frame with `bytes_per_pixel`, without the padding at
+/// the end of the rows.
+unsafe fn packed_pixels(
+    frame: &AVFrame,
+    bytes_per_pixel: usize,
+) -> Result<Cow<'_, [u8]>, FfmpegError> {
+    let invalid = FfmpegError::Av(AVERROR_EINVAL);
+    let width = usize::try_from(frame.width).map_err(|_| invalid)?;
+    let height = usize::try_from(frame.height).map_err(|_| invalid)?;
+    // Negative line sizes (bottom-up images) aren't supported.
+    let linesize = usize::try_from(frame.linesize[0]).map_err(|_| invalid)?;
+    let row = width.checked_mul(bytes_per_pixel).ok_or(invalid)?;
+    let size = row.checked_mul(height).ok_or(invalid)?;
+    if frame.data[0].is_null() || linesize < row {
+        return Err(invalid);
+    }
+    if linesize == row {
+        return Ok(Cow::Borrowed(slice::from_raw_parts(frame.data[0], size)));
+    }
+    let mut pixels = Vec::with_capacity(size);
+    for y in 0..height {
+        pixels.extend_from_slice(slice::from_raw_parts(frame.data[0].add(y * linesize), row));
+    }
+    Ok(Cow::Owned(pixels))
+}
+
+/// View an `AV_PIX_FMT_RGB24` or `AV_PIX_FMT_RGBA` frame as an image, other
+/// formats are `FfmpegError::Unsupported`.
+///
+/// # Safety
+/// The data and line size of the first plane of `frame` must be valid for its
+/// width and height.
+pub unsafe fn frame_to_image_view(frame: &AVFrame) -> Result<ImageView<'_>, FfmpegError> {
+    let (width, height) = (frame.width as u32, frame.height as u32);
+    // `from_raw()` only fails if the buffer is too small, which
+    // `packed_pixels()` rules out.
+    match AVPixelFormat::from_int(frame.format) {
+        ffi::AVPixelFormat_AV_PIX_FMT_RGB24 => Ok(ImageView::Rgb(
+            ImageBuffer::from_raw(width, height, packed_pixels(frame, 3)?)
+                .ok_or(FfmpegError::Av(AVERROR_EINVAL))?,
+        )),
+        ffi::AVPixelFormat_AV_PIX_FMT_RGBA => Ok(ImageView::Rgba(
+            ImageBuffer::from_raw(width, height, packed_pixels(frame, 4)?)
+                .ok_or(FfmpegError::Av(AVERROR_EINVAL))?,
+        )),
+        _ => Err(FfmpegError::Unsupported),
+    }
+}
+
+/// Copy an image into `frame`, which is unreferenced and gets new
+/// `AV_PIX_FMT_RGB24` buffers from `av_frame_get_buffer()`, with the row
+/// alignment FFmpeg wants for the CPU.
+pub fn image_to_frame(img: &RgbImage, frame: &mut Frame) -> Result<(), FfmpegError> {
+    frame.unref();
+    frame.format = AVPixelFormat_AV_PIX_FMT_RGB24.to_int();
+    frame.width = i32::try_from(img.width()).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+    frame.height = i32::try_from(img.height()).map_err(|_| FfmpegError::Av(AVERROR_EINVAL))?;
+    // Fails for empty images, so the rows below aren't empty.
+    match unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) } {
+        ret if ret < 0 => return Err(FfmpegError::from_averror(ret)),
+        _ => {}
+    }
+    let row = img.width() as usize * 3;
+    let linesize = frame.linesize[0] as usize;
+    for (y, pixels) in img.as_raw().chunks_exact(row).enumerate() {
+        unsafe { ptr::copy_nonoverlapping(pixels.as_ptr(), frame.data[0].add(y * linesize), row) };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::{AVPixelFormat_AV_PIX_FMT_RGBA, AVPixelFormat_AV_PIX_FMT_YUV444P};
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+        })
+    }
+
+    fn assert_view_eq(view: &ImageView, image: &RgbImage) {
+        match view {
+            ImageView::Rgb(view) => assert_eq!(view.as_raw().as_ref(), image.as_raw().as_slice()),
+            ImageView::Rgba(_) => panic!("Expected an RGB view"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Rows of 64 RGB pixels are aligned for every CPU, so the frame has
+        // no padding and the view borrows it.
+        let image = gradient(64, 4);
+        let mut frame = Frame::new().unwrap();
+        image_to_frame(&image, &mut frame).unwrap();
+        let view = unsafe { frame_to_image_view(&frame) }.unwrap();
+        assert!(view.is_borrowed());
+        assert_view_eq(&view, &image);
+
+        // Rows of 5 pixels are padded.
+        let image = gradient(5, 3);
+        image_to_frame(&image, &mut frame).unwrap();
+        assert!(frame.linesize[0] > 15);
+        let view = unsafe { frame_to_image_view(&frame) }.unwrap();
+        assert!(!view.is_borrowed());
+        assert_view_eq(&view, &image);
+
+        assert!(image_to_frame(&RgbImage::new(0, 0), &mut frame).is_err());
+    }
+
+    #[test]
+    fn test_rgba_and_unsupported_formats() {
+        let mut frame = Frame::new().unwrap();
+        frame.format = AVPixelFormat_AV_PIX_FMT_RGBA.to_int();
+        frame.width = 2;
+        frame.height = 2;
+        assert!(unsafe { ffi::av_frame_get_buffer(frame.as_mut_ptr(), 0) } >= 0);
+        for y in 0..2 {
+            let row = unsafe { frame.data[0].add(y * frame.linesize[0] as usize) };
+            unsafe { ptr::write_bytes(row, y as u8 + 1, 8) };
+        }
+        match unsafe { frame_to_image_view(&frame) }.unwrap() {
+            ImageView::Rgba(view) => {
+                assert_eq!(view.get_pixel(1, 0), &Rgba([1, 1, 1, 1]));
+                assert_eq!(view.get_pixel(0, 1), &Rgba([2, 2, 2, 2]));
+            }
+            ImageView::Rgb(_) => panic!("Expected an RGBA view"),
+        }
+
+        frame.format = AVPixelFormat_AV_PIX_FMT_YUV444P.to_int();
+        assert_eq!(
+            unsafe { frame_to_image_view(&frame) }.unwrap_err(),
+            FfmpegError::Unsupported
+        );
+    }
+
+    #[test]
+    #[cfg(ff_has_swscale)]
+    fn test_sws_round_trip() {
+        let image = gradient(32, 16);
+        let mut rgb = Frame::new().unwrap();
+        image_to_frame(&image, &mut rgb).unwrap();
+        let mut yuv = Frame::new().unwrap();
+        yuv.format = AVPixelFormat_AV_PIX_FMT_YUV444P.to_int();
+        yuv.width = 32;
+        yuv.height = 16;
+        let mut back = Frame::new().unwrap();
+        back.format = AVPixelFormat_AV_PIX_FMT_RGB24.to_int();
+        back.width = 32;
+        back.height = 16;
+        unsafe {
+            assert!(ffi::av_frame_get_buffer(yuv.as_mut_ptr(), 0) >= 0);
+            assert!(ffi::av_frame_get_buffer(back.as_mut_ptr(), 0) >= 0);
+            let scale = |src: &Frame, dst: &mut Frame| {
+                let sws = ffi::sws_getContext(
+                    32,
+                    16,
+                    AVPixelFormat::from_int(src.format),
+                    32,
+                    16,
+                    AVPixelFormat::from_int(dst.format),
+                    (ffi::SWS_POINT | ffi::SWS_ACCURATE_RND) as _,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null(),
+                );
+                assert!(!sws.is_null());
+                let ret = ffi::sws_scale(
+                    sws,
+                    src.data.as_ptr() as _,
+                    src.linesize.as_ptr(),
+                    0,
+                    16,
+                    dst.data.as_ptr(),
+                    dst.linesize.as_ptr(),
+                );
+                assert_eq!(ret, 16);
+                ffi::sws_freeContext(sws);
+            };
+            scale(&rgb, &mut yuv);
+            scale(&yuv, &mut back);
+        }
+        let view = unsafe { frame_to_image_view(&back) }.unwrap();
+        match view {
+            ImageView::Rgb(view) => {
+                for (converted, original) in view.pixels().zip(image.pixels()) {
+                    for (a, b) in converted.0.iter().zip(original.0.iter()) {
+                        assert!((i32::from(*a) - i32::from(*b)).abs() <= 3);
+                    }
+                }
+            }
+            ImageView::Rgba(_) => panic!("Expected an RGB view"),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Stereoscopic video side data (`AV_FRAME_DATA_STEREO3D` of frames and
+//! `AV_PKT_DATA_STEREO3D` of streams).
+
+use crate::ffi::{self, AVFrame, AVStereo3D, AVStereo3DType, FfmpegError, Frame, AVERROR_ENOMEM};
+#[cfg(feature = "avformat")]
+use crate::ffi::{stream_side_data, AVStream};
+#[cfg(ffmpeg_7_1)]
+use crate::ffi::{AVStereo3DPrimaryEye, AVStereo3DView};
+use std::ffi::{CStr, CString};
+
+fn static_str(s: *const libc::c_char) -> Option<&'static str> {
+    if s.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(s) }.to_str().ok()
+    }
+}
+
+fn from_name(
+    name: &str,
+    av_from_name: unsafe extern "C" fn(*const libc::c_char) -> libc::c_int,
+) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    match unsafe { av_from_name(name.as_ptr()) } {
+        ret if ret < 0 => None,
+        value => Some(value as u32),
+    }
+}
+
+/// The stereo3d side data of a frame.
+pub fn frame_stereo3d(frame: &AVFrame) -> Option<&AVStereo3D> {
+    unsafe {
+        let side_data =
+            ffi::av_frame_get_side_data(frame, ffi::AVFrameSideDataType_AV_FRAME_DATA_STEREO3D);
+        if side_data.is_null() {
+            None
+        } else {
+            ((*side_data).data as *const AVStereo3D).as_ref()
+        }
+    }
+}
+
+/// The stereo3d side data of a stream.
+#[cfg(feature = "avformat")]
+pub fn stream_stereo3d(stream: &AVStream) -> Option<&AVStereo3D> {
+    let data = stream_side_data(stream, ffi::AVPacketSideDataType_AV_PKT_DATA_STEREO3D)?;
+    if data.len() < std::mem::size_of::<AVStereo3D>() {
+        return None;
+    }
+    unsafe { (data.as_ptr() as *const AVStereo3D).as_ref() }
+}
+
+/// Add stereo3d side data to a frame with `av_stereo3d_create_side_data()`,
+/// replacing an existing one. The other fields keep their defaults and can be
+/// set through the returned reference.
+pub fn set_frame_stereo3d(
+    frame: &mut Frame,
+    kind: AVStereo3DType,
+    flags: libc::c_int,
+) -> Result<&mut AVStereo3D, FfmpegError> {
+    unsafe {
+        ffi::av_frame_remove_side_data(
+            frame.as_mut_ptr(),
+            ffi::AVFrameSideDataType_AV_FRAME_DATA_STEREO3D,
+        );
+        match ffi::av_stereo3d_create_side_data(frame.as_mut_ptr()).as_mut() {
+            Some(stereo3d) => {
+                stereo3d.type_ = kind;
+                stereo3d.flags = flags;
+                Ok(stereo3d)
+            }
+            None => Err(FfmpegError::Av(AVERROR_ENOMEM)),
+        }
+    }
+}
+
+/// The name of a packing, e.g. "side by side". `None` for values FFmpeg
+/// doesn't know.
+pub fn stereo3d_type_name(kind: AVStereo3DType) -> Option<&'static str> {
+    // Unknown values are named "unknown".
+    static_str(unsafe { ffi::av_stereo3d_type_name(kind as _) }).filter(|&name| name != "unknown")
+}
+
+/// The packing of a name, `None` if there is none.
+pub fn stereo3d_type_from_name(name: &str) -> Option<AVStereo3DType> {
+    from_name(name, ffi::av_stereo3d_from_name).map(|value| value as AVStereo3DType)
+}
+
+/// The name of a view, e.g. "left". Since FFmpeg 7.1.
+#[cfg(ffmpeg_7_1)]
+pub fn stereo3d_view_name(view: AVStereo3DView) -> Option<&'static str> {
+    static_str(unsafe { ffi::av_stereo3d_view_name(view as _) }).filter(|&name| name != "unknown")
+}
+
+/// The view of a name, `None` if there is none. Since FFmpeg 7.1.
+#[cfg(ffmpeg_7_1)]
+pub fn stereo3d_view_from_name(name: &str) -> Option<AVStereo3DView> {
+    from_name(name, ffi::av_stereo3d_view_from_name).map(|value| value as AVStereo3DView)
+}
+
+/// The name of a primary eye, e.g. "left". Since FFmpeg 7.1.
+#[cfg(ffmpeg_7_1)]
+pub fn stereo3d_primary_eye_name(eye: AVStereo3DPrimaryEye) -> Option<&'static str> {
+    static_str(unsafe { ffi::av_stereo3d_primary_eye_name(eye as _) })
+        .filter(|&name| name != "unknown")
+}
+
+/// The primary eye of a name, `None` if there is none. Since FFmpeg 7.1.
+#[cfg(ffmpeg_7_1)]
+pub fn stereo3d_primary_eye_from_name(name: &str) -> Option<AVStereo3DPrimaryEye> {
+    from_name(name, ffi::av_stereo3d_primary_eye_from_name)
+        .map(|value| value as AVStereo3DPrimaryEye)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::AV_STEREO3D_FLAG_INVERT;
+
+    #[test]
+    fn test_frame_stereo3d() {
+        let mut frame = Frame::new().unwrap();
+        assert!(frame_stereo3d(&frame).is_none());
+        set_frame_stereo3d(
+            &mut frame,
+            ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE,
+            AV_STEREO3D_FLAG_INVERT as _,
+        )
+        .unwrap();
+        let stereo3d = frame_stereo3d(&frame).unwrap();
+        assert_eq!(stereo3d.type_, ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE);
+        assert_eq!(stereo3d.flags, AV_STEREO3D_FLAG_INVERT as libc::c_int);
+        assert_eq!(stereo3d.view, ffi::AVStereo3DView_AV_STEREO3D_VIEW_PACKED);
+
+        // Replaced, not added.
+        set_frame_stereo3d(&mut frame, ffi::AVStereo3DType_AV_STEREO3D_TOPBOTTOM, 0).unwrap();
+        assert_eq!(frame.nb_side_data, 1);
+        let stereo3d = frame_stereo3d(&frame).unwrap();
+        assert_eq!(stereo3d.type_, ffi::AVStereo3DType_AV_STEREO3D_TOPBOTTOM);
+        assert_eq!(stereo3d.flags, 0);
+    }
+
+    #[cfg(feature = "avformat")]
+    #[test]
+    fn test_stream_stereo3d() {
+        use crate::ffi::new_stream_side_data;
+        use std::{mem, ptr};
+
+        unsafe {
+            let ctx = ffi::avformat_alloc_context();
+            let stream = ffi::avformat_new_stream(ctx, ptr::null());
+            assert!(stream_stereo3d(&*stream).is_none());
+
+            let data = new_stream_side_data(
+                stream,
+                ffi::AVPacketSideDataType_AV_PKT_DATA_STEREO3D,
+                mem::size_of::<AVStereo3D>(),
+            );
+            let stereo3d = &mut *(data as *mut AVStereo3D);
+            stereo3d.type_ = ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE;
+            stereo3d.flags = AV_STEREO3D_FLAG_INVERT as _;
+
+            let stereo3d = stream_stereo3d(&*stream).unwrap();
+            assert_eq!(stereo3d.type_, ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE);
+            assert_eq!(stereo3d.flags, AV_STEREO3D_FLAG_INVERT as libc::c_int);
+            ffi::avformat_free_context(ctx);
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            stereo3d_type_name(ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE),
+            Some("side by side")
+        );
+        assert_eq!(
+            stereo3d_type_from_name("side by side"),
+            Some(ffi::AVStereo3DType_AV_STEREO3D_SIDEBYSIDE)
+        );
+        assert_eq!(
+            stereo3d_type_from_name("top and bottom"),
+            Some(ffi::AVStereo3DType_AV_STEREO3D_TOPBOTTOM)
+        );
+        assert_eq!(stereo3d_type_name(1000), None);
+        assert_eq!(stereo3d_type_from_name("sideways"), None);
+        assert_eq!(stereo3d_type_from_name("side\0by side"), None);
+
+        #[cfg(ffmpeg_7_1)]
+        {
+            assert_eq!(
+                stereo3d_view_name(ffi::AVStereo3DView_AV_STEREO3D_VIEW_LEFT),
+                Some("left")
+            );
+            assert_eq!(
+                stereo3d_view_from_name("right"),
+                Some(ffi::AVStereo3DView_AV_STEREO3D_VIEW_RIGHT)
+            );
+            assert_eq!(
+                stereo3d_primary_eye_name(ffi::AVStereo3DPrimaryEye_AV_PRIMARY_EYE_LEFT),
+                Some("left")
+            );
+            assert_eq!(
+                stereo3d_primary_eye_from_name("right"),
+                Some(ffi::AVStereo3DPrimaryEye_AV_PRIMARY_EYE_RIGHT)
+            );
+        }
+    }
+}
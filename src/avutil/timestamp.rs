@@ -0,0 +1,188 @@
+//! Conversions between timestamps in a time base and `Duration`/seconds,
+//! rescaled with `av_rescale_rnd()` so large timestamps keep their precision.
+
+use crate::ffi::{self, AVRational, AVRounding, AV_NOPTS_VALUE};
+use core::{convert::TryFrom, time::Duration};
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Rounding mode of [`duration_to_ts()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Toward zero.
+    Zero,
+    /// Away from zero.
+    Inf,
+    /// Toward -infinity.
+    Down,
+    /// Toward +infinity.
+    Up,
+    /// To the nearest value, halfway cases away from zero.
+    NearInf,
+}
+
+impl From<Rounding> for AVRounding {
+    fn from(rounding: Rounding) -> Self {
+        match rounding {
+            Rounding::Zero => ffi::AVRounding_AV_ROUND_ZERO,
+            Rounding::Inf => ffi::AVRounding_AV_ROUND_INF,
+            Rounding::Down => ffi::AVRounding_AV_ROUND_DOWN,
+            Rounding::Up => ffi::AVRounding_AV_ROUND_UP,
+            Rounding::NearInf => ffi::AVRounding_AV_ROUND_NEAR_INF,
+        }
+    }
+}
+
+/// Whole seconds (rounded down) and the remaining nanoseconds of a timestamp,
+/// `None` for `AV_NOPTS_VALUE`, time bases that aren't positive, or seconds
+/// that don't fit an `i64`.
+fn split_ts(ts: i64, tb: AVRational) -> Option<(i64, i64)> {
+    if ts == AV_NOPTS_VALUE || tb.num <= 0 || tb.den <= 0 {
+        return None;
+    }
+    let (num, den) = (i64::from(tb.num), i64::from(tb.den));
+    let secs = unsafe { ffi::av_rescale_rnd(ts, num, den, ffi::AVRounding_AV_ROUND_DOWN) };
+    // `av_rescale_rnd()` returns `INT64_MIN` on overflow.
+    if secs == i64::MIN {
+        return None;
+    }
+    // In [0, den), in units of 1/den seconds.
+    let rem = i128::from(ts) * i128::from(num) - i128::from(secs) * i128::from(den);
+    let nanos = unsafe {
+        ffi::av_rescale_rnd(
+            rem as i64,
+            NANOS_PER_SEC,
+            den,
+            ffi::AVRounding_AV_ROUND_NEAR_INF,
+        )
+    };
+    Some((secs, nanos))
+}
+
+/// A timestamp as a `Duration`, `None` for `AV_NOPTS_VALUE` and negative
+/// timestamps. Rounded to the nearest nanosecond.
+pub fn ts_to_duration(ts: i64, tb: AVRational) -> Option<Duration> {
+    let (secs, nanos) = split_ts(ts, tb)?;
+    // `Duration::new()` carries nanoseconds rounded up to a whole second.
+    Some(Duration::new(u64::try_from(secs).ok()?, nanos as u32))
+}
+
+/// A timestamp in seconds, negative timestamps included. `None` for
+/// `AV_NOPTS_VALUE`.
+pub fn ts_to_signed_secs(ts: i64, tb: AVRational) -> Option<f64> {
+    let (secs, nanos) = split_ts(ts, tb)?;
+    Some(secs as f64 + nanos as f64 / NANOS_PER_SEC as f64)
+}
+
+/// A `Duration` as a timestamp in a time base. `AV_NOPTS_VALUE` if the time
+/// base isn't positive, or the duration in nanoseconds or the timestamp don't
+/// fit an `i64` (about 292 years in nanoseconds).
+pub fn duration_to_ts(d: Duration, tb: AVRational, rounding: Rounding) -> i64 {
+    let nanos = match i64::try_from(d.as_nanos()) {
+        Ok(nanos) if tb.num > 0 && tb.den > 0 => nanos,
+        _ => return AV_NOPTS_VALUE,
+    };
+    // `av_rescale_rnd()` returns `INT64_MIN`, which is `AV_NOPTS_VALUE`, on
+    // overflow.
+    unsafe {
+        ffi::av_rescale_rnd(
+            nanos,
+            i64::from(tb.den),
+            i64::from(tb.num) * NANOS_PER_SEC,
+            rounding.into(),
+        )
+    }
+}
+
+/// The current time in microseconds, with `av_gettime_relative()` from an
+/// arbitrary start if `monotonic`, else the wall-clock time since the Unix
+/// epoch with `av_gettime()`. The relative time is only monotonic if
+/// `av_gettime_relative_is_monotonic()` says so, which it does on common
+/// platforms.
+pub fn now_av(monotonic: bool) -> i64 {
+    if monotonic {
+        unsafe { ffi::av_gettime_relative() }
+    } else {
+        unsafe { ffi::av_gettime() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MPEG_TB: AVRational = AVRational { num: 1, den: 90000 };
+    const MS_TB: AVRational = AVRational { num: 1, den: 1000 };
+
+    #[test]
+    fn test_ts_to_duration() {
+        assert_eq!(
+            ts_to_duration(90000 * 3661 + 45000, MPEG_TB),
+            Some(Duration::from_millis(3_661_500))
+        );
+        assert_eq!(
+            ts_to_duration(1, MPEG_TB),
+            Some(Duration::from_nanos(11111))
+        );
+        assert_eq!(
+            ts_to_duration(1001, MS_TB),
+            Some(Duration::from_millis(1001))
+        );
+        assert_eq!(ts_to_duration(0, MS_TB), Some(Duration::from_secs(0)));
+        assert_eq!(ts_to_duration(-1, MS_TB), None);
+        assert_eq!(ts_to_duration(AV_NOPTS_VALUE, MS_TB), None);
+        assert_eq!(ts_to_duration(1, AVRational { num: 1, den: 0 }), None);
+        // Beyond the precision of an f64.
+        let ts = (1 << 53) + 1;
+        assert_eq!(
+            ts_to_duration(ts, MS_TB),
+            Some(Duration::new(9_007_199_254_740, 993_000_000))
+        );
+        assert_eq!(
+            ts_to_duration(i64::MAX, MPEG_TB),
+            Some(Duration::new(i64::MAX as u64 / 90000, 620_077_778))
+        );
+    }
+
+    #[test]
+    fn test_ts_to_signed_secs() {
+        assert_eq!(ts_to_signed_secs(-45000, MPEG_TB), Some(-0.5));
+        assert_eq!(ts_to_signed_secs(-1500, MS_TB), Some(-1.5));
+        assert_eq!(ts_to_signed_secs(2500, MS_TB), Some(2.5));
+        assert_eq!(ts_to_signed_secs(AV_NOPTS_VALUE, MS_TB), None);
+    }
+
+    #[test]
+    fn test_duration_to_ts() {
+        assert_eq!(
+            duration_to_ts(Duration::from_millis(1001), MPEG_TB, Rounding::NearInf),
+            90090
+        );
+        let one_ns = Duration::from_nanos(1);
+        assert_eq!(duration_to_ts(one_ns, MS_TB, Rounding::Up), 1);
+        assert_eq!(duration_to_ts(one_ns, MS_TB, Rounding::Down), 0);
+        assert_eq!(duration_to_ts(one_ns, MS_TB, Rounding::NearInf), 0);
+        assert_eq!(
+            duration_to_ts(Duration::from_micros(500), MS_TB, Rounding::NearInf),
+            1
+        );
+        let d = Duration::new(9_007_199_254_740, 993_000_000);
+        assert_eq!(duration_to_ts(d, MS_TB, Rounding::Zero), (1 << 53) + 1);
+        assert_eq!(
+            ts_to_duration(duration_to_ts(d, MPEG_TB, Rounding::Zero), MPEG_TB),
+            Some(d)
+        );
+        assert_eq!(
+            duration_to_ts(Duration::from_secs(u64::MAX), MS_TB, Rounding::Zero),
+            AV_NOPTS_VALUE
+        );
+    }
+
+    #[test]
+    fn test_now_av() {
+        let start = now_av(true);
+        assert!(now_av(true) >= start);
+        // After 2020-01-01.
+        assert!(now_av(false) > 1_577_836_800_000_000);
+    }
+}
@@ -0,0 +1,95 @@
+//! How FFmpeg was linked and where the binding came from, recorded at build
+//! time. Useful for reporting without calling into the libraries.
+//!
+//! `FFMPEG_HEADER_VERSIONS` contains `AV_VERSION_INT` values. When a prebuilt
+//! or vendored binding is used, they're the versions the binding was
+//! generated for, `LINK_MODE` may be `"unknown"` and `BOUND_HEADERS` and
+//! `INCLUDE_DIRS` are empty. It's `"none"` when nothing is linked because of
+//! `FFMPEG_NO_LINK`. `DLL_DIR` is the dir of the first library of
+//! `FFMPEG_DLL_PATH`, or the dll dir of a vcpkg dll triplet or of a Windows
+//! `FFMPEG_LIBS_DIR`, when linked dynamically. It has to be in the dll search
+//! path at runtime.
+//!
+//! `LIBS` are the libraries linked, each with its `ff_has_<lib>` cfg.
+
+include!(concat!(env!("OUT_DIR"), "/build_meta.rs"));
+
+/// The newest FFmpeg release the binding is at least, e.g. `"6.1"`, matching
+/// the `ffmpeg_X_Y` cfgs. `"unknown"` for releases older than 4.4.
+pub const FFMPEG_VERSION: &str = env!("FFMPEG_VERSION");
+
+/// The `major.minor.micro` version of libavutil the binding is for.
+pub const FFMPEG_LIBAVUTIL_VERSION: &str = env!("FFMPEG_LIBAVUTIL_VERSION");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_link_mode_matches_cfg() {
+        assert!(["static", "dynamic", "unknown", "none"].contains(&LINK_MODE));
+        assert_eq!(cfg!(ffmpeg_link_mode = "static"), LINK_MODE == "static");
+        assert_eq!(cfg!(ffmpeg_link_mode = "dynamic"), LINK_MODE == "dynamic");
+        assert_eq!(cfg!(ffmpeg_link_mode = "unknown"), LINK_MODE == "unknown");
+        assert_eq!(cfg!(ffmpeg_link_mode = "none"), LINK_MODE == "none");
+        if LINK_MODE != "dynamic" {
+            assert!(DLL_DIR.is_none());
+        }
+    }
+
+    #[test]
+    fn test_libs_match_cfgs() {
+        assert!(LIBS.contains(&"avutil"));
+        assert_eq!(cfg!(ff_has_avutil), LIBS.contains(&"avutil"));
+        assert_eq!(cfg!(ff_has_avcodec), LIBS.contains(&"avcodec"));
+        assert_eq!(cfg!(ff_has_avformat), LIBS.contains(&"avformat"));
+        assert_eq!(cfg!(ff_has_avdevice), LIBS.contains(&"avdevice"));
+        assert_eq!(cfg!(ff_has_avfilter), LIBS.contains(&"avfilter"));
+        assert_eq!(cfg!(ff_has_postproc), LIBS.contains(&"postproc"));
+        assert_eq!(cfg!(ff_has_swresample), LIBS.contains(&"swresample"));
+        assert_eq!(cfg!(ff_has_swscale), LIBS.contains(&"swscale"));
+    }
+
+    #[test]
+    fn test_ffmpeg_version_matches_cfgs() {
+        let release = match FFMPEG_VERSION.split_once('.') {
+            Some((major, minor)) => (major.parse().unwrap(), minor.parse().unwrap()),
+            None => (0, 0),
+        };
+        assert_eq!(cfg!(ffmpeg_4_4), release >= (4, 4));
+        assert_eq!(cfg!(ffmpeg_5_0), release >= (5, 0));
+        assert_eq!(cfg!(ffmpeg_5_1), release >= (5, 1));
+        assert_eq!(cfg!(ffmpeg_6_0), release >= (6, 0));
+        assert_eq!(cfg!(ffmpeg_6_1), release >= (6, 1));
+        assert_eq!(cfg!(ffmpeg_7_0), release >= (7, 0));
+        assert_eq!(cfg!(ffmpeg_7_1), release >= (7, 1));
+        assert_eq!(cfg!(ffmpeg_8_0), release >= (8, 0));
+        let mut libavutil = FFMPEG_LIBAVUTIL_VERSION
+            .split('.')
+            .map(|x| x.parse::<u32>().unwrap());
+        assert_eq!(libavutil.next(), Some(crate::ffi::LIBAVUTIL_VERSION_MAJOR));
+        assert_eq!(libavutil.next(), Some(crate::ffi::LIBAVUTIL_VERSION_MINOR));
+        assert_eq!(libavutil.next(), Some(crate::ffi::LIBAVUTIL_VERSION_MICRO));
+        assert_eq!(libavutil.next(), None);
+    }
+
+    #[test]
+    fn test_header_metadata() {
+        if INCLUDE_DIRS.is_empty() {
+            assert!(BOUND_HEADERS.is_empty());
+        } else {
+            assert!(BOUND_HEADERS.contains(&"libavutil/avutil.h"));
+        }
+        // From the headers, or from the prebuilt binding.
+        let (_, avutil_version) = FFMPEG_HEADER_VERSIONS
+            .iter()
+            .find(|(libname, _)| *libname == "avutil")
+            .unwrap();
+        assert_eq!(
+            *avutil_version,
+            crate::ffi::LIBAVUTIL_VERSION_MAJOR << 16
+                | crate::ffi::LIBAVUTIL_VERSION_MINOR << 8
+                | crate::ffi::LIBAVUTIL_VERSION_MICRO
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! What the linked FFmpeg was built with, e.g. to check that `libx264` is
+//! available before offering H.264 output.
+
+use crate::ffi;
+use libc::c_char;
+use std::{collections::BTreeSet, ffi::CStr};
+
+/// License of the linked FFmpeg, parsed from `avutil_license()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum License {
+    /// "GPL version 2 or later" or "GPL version 3 or later"
+    Gpl,
+    /// "LGPL version 2.1 or later" or "LGPL version 3 or later"
+    Lgpl,
+    /// "nonfree and unredistributable"
+    Nonfree,
+    /// A license string this crate doesn't know.
+    Other(String),
+}
+
+impl License {
+    /// The license strings `./configure` sets are matched whole.
+    pub fn parse(license: &str) -> Self {
+        match license {
+            "nonfree and unredistributable" => License::Nonfree,
+            "LGPL version 2.1 or later" | "LGPL version 3 or later" => License::Lgpl,
+            "GPL version 2 or later" | "GPL version 3 or later" => License::Gpl,
+            _ => License::Other(license.to_string()),
+        }
+    }
+}
+
+/// Kind of a component looked up by [`Configuration::has_component()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    Encoder,
+    Decoder,
+    Muxer,
+    Demuxer,
+    Filter,
+    BitstreamFilter,
+}
+
+/// Configure flags of the linked FFmpeg.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Configuration {
+    /// The `./configure` arguments, unquoted.
+    pub args: Vec<String>,
+    enabled: BTreeSet<String>,
+    disabled: BTreeSet<String>,
+}
+
+/// Split configure arguments like a shell would, so quoted arguments with
+/// spaces (`--extra-cflags='-I/a b'`) stay one argument.
+fn split_args(configuration: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = configuration.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => {
+                if let Some(c) = chars.next() {
+                    arg.push(c);
+                }
+            }
+            (Some(_), c) => arg.push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, '\\') => {
+                if let Some(c) = chars.next() {
+                    arg.push(c);
+                }
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    args
+}
+
+impl Configuration {
+    /// Parse a configuration string as returned by `avutil_configuration()`.
+    pub fn parse(configuration: &str) -> Self {
+        let args = split_args(configuration);
+        let mut enabled = BTreeSet::new();
+        let mut disabled = BTreeSet::new();
+        // The last flag of a feature wins, like in `./configure`.
+        for arg in &args {
+            if let Some(feature) = arg.strip_prefix("--enable-") {
+                disabled.remove(feature);
+                enabled.insert(feature.to_string());
+            } else if let Some(feature) = arg.strip_prefix("--disable-") {
+                enabled.remove(feature);
+                disabled.insert(feature.to_string());
+            }
+        }
+        Self {
+            args,
+            enabled,
+            disabled,
+        }
+    }
+
+    /// Whether `--enable-<feature>` was given (and not overridden).
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.enabled.contains(feature)
+    }
+
+    /// Whether `--disable-<feature>` was given (and not overridden).
+    pub fn is_disabled(&self, feature: &str) -> bool {
+        self.disabled.contains(feature)
+    }
+
+    /// Whether a component is available in the linked libraries. Components
+    /// are enabled by default, so this asks the libraries rather than the
+    /// configure flags: it looks the name up, then iterates over the
+    /// registered components (which also matches the alternative names of
+    /// demuxers like `mp4` in "mov,mp4,m4a,3gp,3g2,mj2").
+    pub fn has_component(kind: ComponentKind, name: &str) -> bool {
+        find_component(kind, name)
+    }
+}
+
+#[cfg(any(feature = "avcodec", feature = "avformat", ff_has_avfilter))]
+/// `CStr` of a component name, which is never null for registered components.
+unsafe fn name_matches(component_name: *const c_char, name: &str) -> bool {
+    !component_name.is_null()
+        && CStr::from_ptr(component_name)
+            .to_str()
+            .map(|names| names.split(',').any(|x| x == name))
+            .unwrap_or(false)
+}
+
+#[cfg(any(feature = "avcodec", feature = "avformat", ff_has_avfilter))]
+/// Iterate with one of the `av_*_iterate()` functions until `matches` is true.
+unsafe fn any_registered<T>(
+    iterate: unsafe extern "C" fn(*mut *mut libc::c_void) -> *const T,
+    matches: impl Fn(&T) -> bool,
+) -> bool {
+    let mut opaque = std::ptr::null_mut();
+    loop {
+        let component = iterate(&mut opaque);
+        if component.is_null() {
+            return false;
+        }
+        if matches(&*component) {
+            return true;
+        }
+    }
+}
+
+// Only avutil is linked when no component library is enabled.
+#[cfg_attr(
+    not(any(feature = "avcodec", feature = "avformat", ff_has_avfilter)),
+    allow(unused_variables, unused_unsafe)
+)]
+fn find_component(kind: ComponentKind, name: &str) -> bool {
+    let c_name = match std::ffi::CString::new(name) {
+        Ok(c_name) => c_name,
+        Err(_) => return false,
+    };
+    unsafe {
+        match kind {
+            #[cfg(feature = "avcodec")]
+            ComponentKind::Encoder | ComponentKind::Decoder => {
+                let found = if kind == ComponentKind::Encoder {
+                    ffi::avcodec_find_encoder_by_name(c_name.as_ptr())
+                } else {
+                    ffi::avcodec_find_decoder_by_name(c_name.as_ptr())
+                };
+                !found.is_null()
+                    || any_registered(ffi::av_codec_iterate, |codec| {
+                        let is_kind = if kind == ComponentKind::Encoder {
+                            ffi::av_codec_is_encoder(codec)
+                        } else {
+                            ffi::av_codec_is_decoder(codec)
+                        };
+                        is_kind != 0 && name_matches(codec.name, name)
+                    })
+            }
+            #[cfg(feature = "avformat")]
+            ComponentKind::Muxer => {
+                !ffi::av_guess_format(c_name.as_ptr(), std::ptr::null(), std::ptr::null()).is_null()
+                    || any_registered(ffi::av_muxer_iterate, |muxer| {
+                        name_matches(muxer.name, name)
+                    })
+            }
+            #[cfg(feature = "avformat")]
+            ComponentKind::Demuxer => {
+                !ffi::av_find_input_format(c_name.as_ptr()).is_null()
+                    || any_registered(ffi::av_demuxer_iterate, |demuxer| {
+                        name_matches(demuxer.name, name)
+                    })
+            }
+            #[cfg(ff_has_avfilter)]
+            ComponentKind::Filter => {
+                !ffi::avfilter_get_by_name(c_name.as_ptr()).is_null()
+                    || any_registered(ffi::av_filter_iterate, |filter| {
+                        name_matches(filter.name, name)
+                    })
+            }
+            #[cfg(feature = "avcodec")]
+            ComponentKind::BitstreamFilter => {
+                !ffi::av_bsf_get_by_name(c_name.as_ptr()).is_null()
+                    || any_registered(ffi::av_bsf_iterate, |bsf| name_matches(bsf.name, name))
+            }
+            // The library of the component isn't linked.
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+fn c_str_to_string(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+    }
+}
+
+/// Configure flags of the linked libavutil and libavcodec (they normally
+/// agree, the flags of libavcodec win if they don't).
+pub fn configuration() -> Configuration {
+    let avutil = c_str_to_string(unsafe { ffi::avutil_configuration() });
+    #[cfg(feature = "avcodec")]
+    let avcodec = c_str_to_string(unsafe { ffi::avcodec_configuration() });
+    #[cfg(not(feature = "avcodec"))]
+    let avcodec = avutil.clone();
+    if avutil == avcodec {
+        Configuration::parse(&avutil)
+    } else {
+        Configuration::parse(&format!("{} {}", avutil, avcodec))
+    }
+}
+
+/// License of the linked libavutil.
+pub fn license() -> License {
+    License::parse(&c_str_to_string(unsafe { ffi::avutil_license() }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_configuration() {
+        let configuration = Configuration::parse(
+            "--prefix=/usr --extra-cflags='-I/opt/my include -O2' \
+             --extra-ldflags=\"-L/opt/my lib\" --enable-gpl --enable-libx264 \
+             --disable-doc --enable-doc --enable-libx265 --disable-libx265",
+        );
+        assert_eq!(
+            configuration.args,
+            [
+                "--prefix=/usr",
+                "--extra-cflags=-I/opt/my include -O2",
+                "--extra-ldflags=-L/opt/my lib",
+                "--enable-gpl",
+                "--enable-libx264",
+                "--disable-doc",
+                "--enable-doc",
+                "--enable-libx265",
+                "--disable-libx265",
+            ]
+        );
+        assert!(configuration.is_enabled("gpl"));
+        assert!(configuration.is_enabled("libx264"));
+        assert!(configuration.is_enabled("doc"));
+        assert!(!configuration.is_disabled("doc"));
+        assert!(!configuration.is_enabled("libx265"));
+        assert!(configuration.is_disabled("libx265"));
+        assert!(!configuration.is_enabled("nonfree"));
+    }
+
+    #[test]
+    fn test_parse_empty_configuration() {
+        let configuration = Configuration::parse("");
+        assert!(configuration.args.is_empty());
+        assert!(!configuration.is_enabled("gpl"));
+    }
+
+    #[test]
+    fn test_parse_license() {
+        assert_eq!(License::parse("GPL version 2 or later"), License::Gpl);
+        assert_eq!(License::parse("GPL version 3 or later"), License::Gpl);
+        assert_eq!(License::parse("LGPL version 2.1 or later"), License::Lgpl);
+        assert_eq!(License::parse("LGPL version 3 or later"), License::Lgpl);
+        assert_eq!(
+            License::parse("GPL version 2"),
+            License::Other("GPL version 2".to_string())
+        );
+        assert_eq!(
+            License::parse("nonfree and unredistributable"),
+            License::Nonfree
+        );
+        assert_eq!(
+            License::parse("nonfree"),
+            License::Other("nonfree".to_string())
+        );
+    }
+
+    #[test]
+    fn test_linked_configuration() {
+        let configuration = configuration();
+        if license() == License::Gpl {
+            assert!(configuration.is_enabled("gpl"));
+        }
+        #[cfg(feature = "avcodec")]
+        assert!(Configuration::has_component(ComponentKind::Decoder, "h264"));
+        #[cfg(feature = "avformat")]
+        assert!(Configuration::has_component(ComponentKind::Demuxer, "mp4"));
+        #[cfg(ff_has_avfilter)]
+        assert!(Configuration::has_component(ComponentKind::Filter, "null"));
+        assert!(!Configuration::has_component(
+            ComponentKind::Encoder,
+            "no such encoder"
+        ));
+    }
+}
@@ -1,13 +1,154 @@
-mod avutil;
+//! Every item is available flat in [`ffi`]. The per-library modules re-export
+//! the same items grouped by the FFmpeg library they come from, e.g.
+//! `use rusty_ffmpeg::avcodec::{avcodec_send_packet, AVCodecContext}`. Types
+//! shared between libraries (`AVFrame`, `AVRational`, `AVDictionary`...) live
+//! in [`avutil`].
+//!
+//! Disable the default `std` feature to use the crate in `no_std` environments.
+//! The libraries other than libavutil have features of the same name, their
+//! modules and helpers are only there when the feature is enabled.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod build_meta;
+#[cfg(feature = "std")]
+mod configuration;
+mod self_test;
+
+#[cfg(feature = "std")]
+pub use configuration::{configuration, license, ComponentKind, Configuration, License};
+pub use self_test::{self_test, ComponentStatus, SelfTestReport};
+
+// The build script can't have unit tests of its own, `cargo test` doesn't
+// run them. Its helpers in `build/` are kept free of build script state and
+// compiled here, so their tests run with the library's.
+#[cfg(all(test, feature = "std"))]
+#[path = "../build"]
+mod build_tests {
+    mod binding_output;
+    mod clang_target;
+    mod conan;
+    mod configure_args;
+    mod dylib_name;
+    mod ffmpeg_version;
+    mod homebrew;
+    mod libs_override;
+    mod newtype_enums;
+    mod normalize_path;
+    mod sha256;
+    mod system_include;
+    mod target_env;
+    mod vcpkg_status;
+    mod version_match;
+}
+
+/// Items of libavutil, including the types shared by the other libraries.
+pub mod avutil;
+
+/// Items of libavcodec.
+#[cfg(feature = "avcodec")]
+pub mod avcodec;
+
+/// Items of libavdevice.
+#[cfg(ff_has_avdevice)]
+pub mod avdevice {
+    include!(concat!(env!("OUT_DIR"), "/namespace/avdevice.rs"));
+}
+
+/// Items of libavfilter.
+#[cfg(ff_has_avfilter)]
+pub mod avfilter {
+    include!(concat!(env!("OUT_DIR"), "/namespace/avfilter.rs"));
+}
+
+/// Items of libavformat.
+#[cfg(feature = "avformat")]
+pub mod avformat;
+
+/// Items of libpostproc.
+#[cfg(ff_has_postproc)]
+pub mod postproc {
+    include!(concat!(env!("OUT_DIR"), "/namespace/postproc.rs"));
+}
+
+/// Items of libswresample.
+#[cfg(ff_has_swresample)]
+pub mod swresample {
+    include!(concat!(env!("OUT_DIR"), "/namespace/swresample.rs"));
+}
+
+/// Items of libswscale.
+#[cfg(ff_has_swscale)]
+pub mod swscale {
+    include!(concat!(env!("OUT_DIR"), "/namespace/swscale.rs"));
+}
 
 #[allow(
     non_snake_case,
     non_camel_case_types,
     non_upper_case_globals,
     improper_ctypes,
+    deref_nullptr,
+    unknown_lints,
+    unnecessary_transmutes,
     clippy::all
 )]
 pub mod ffi {
-    pub use crate::avutil::{_avutil::*, common::*, error::*, pixfmt::*, rational::*};
+    #[cfg(all(feature = "std", feature = "avcodec"))]
+    pub use crate::avcodec::context::*;
+    #[cfg(feature = "bytes")]
+    pub use crate::avcodec::packet_bytes::*;
+    #[cfg(feature = "avcodec")]
+    pub use crate::avcodec::{codec_id::*, extradata::*, packet::*, parser::*, subtitle::*};
+    #[cfg(feature = "async-io")]
+    pub use crate::avformat::async_io::*;
+    #[cfg(feature = "avformat")]
+    pub use crate::avformat::stream::*;
+    #[cfg(all(feature = "std", feature = "avformat"))]
+    pub use crate::avformat::{context::*, dyn_buf::*, guess::*, network::*, probe::*};
+    #[cfg(feature = "image")]
+    pub use crate::avutil::rgb_image::*;
+    pub use crate::avutil::{
+        _avutil::*, buffer::*, common::*, downmix_info::*, error::*, frame::*, lfg::*, pixfmt::*,
+        rational::*, replaygain::*, timestamp::*,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::avutil::{
+        channel_layout::*, colorimetry::*, dict::*, hmac::*, hwcontext::*, imgutils::*, planes::*,
+        stereo3d::*,
+    };
     include!(concat!(env!("OUT_DIR"), "/binding.rs"));
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use crate::ffi::{AVMotionVector, AVRational};
+
+    #[test]
+    fn test_serde_round_trip() {
+        let rational = AVRational {
+            num: 1001,
+            den: 30000,
+        };
+        let json = serde_json::to_string(&rational).unwrap();
+        assert_eq!(json, r#"{"num":1001,"den":30000}"#);
+        let back: AVRational = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.num, back.den), (1001, 30000));
+
+        let motion_vector = AVMotionVector {
+            source: -1,
+            w: 16,
+            h: 8,
+            src_x: 1,
+            src_y: 2,
+            dst_x: 3,
+            dst_y: 4,
+            flags: 0,
+            motion_x: 5,
+            motion_y: 6,
+            motion_scale: 7,
+        };
+        let json = serde_json::to_string(&motion_vector).unwrap();
+        let back: AVMotionVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+}
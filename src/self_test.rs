@@ -0,0 +1,168 @@
+//! Check at runtime that the linked FFmpeg libraries are present and match
+//! the binding, instead of finding out with a loader error or a crash on the
+//! first real call.
+
+use crate::ffi;
+
+/// Result of the self test of one FFmpeg component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentStatus {
+    Passed,
+    Failed,
+    /// The component isn't linked: its feature is disabled, or the FFmpeg
+    /// linked lacks it.
+    Skipped,
+}
+
+/// Results of [`self_test()`] per component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub avutil: ComponentStatus,
+    pub avcodec: ComponentStatus,
+    pub avformat: ComponentStatus,
+    pub avdevice: ComponentStatus,
+    pub avfilter: ComponentStatus,
+    pub swscale: ComponentStatus,
+    pub swresample: ComponentStatus,
+    pub postproc: ComponentStatus,
+}
+
+impl SelfTestReport {
+    /// Name and status of every component, in the order they are tested.
+    pub fn components(&self) -> [(&'static str, ComponentStatus); 8] {
+        [
+            ("avutil", self.avutil),
+            ("avcodec", self.avcodec),
+            ("avformat", self.avformat),
+            ("avdevice", self.avdevice),
+            ("avfilter", self.avfilter),
+            ("swscale", self.swscale),
+            ("swresample", self.swresample),
+            ("postproc", self.postproc),
+        ]
+    }
+
+    /// Whether no component failed.
+    pub fn is_green(&self) -> bool {
+        self.components()
+            .iter()
+            .all(|(_, status)| *status != ComponentStatus::Failed)
+    }
+}
+
+fn status(passed: bool) -> ComponentStatus {
+    if passed {
+        ComponentStatus::Passed
+    } else {
+        ComponentStatus::Failed
+    }
+}
+
+/// Whether the major version of the linked library is the one of the headers
+/// the binding is generated from.
+fn major_matches(version: libc::c_uint, major: u32) -> bool {
+    version >> 16 == major
+}
+
+/// The status of the test of a component, `Skipped` if the cfg linking it
+/// (its feature, or `ff_has_<lib>` for the libs a stripped FFmpeg can lack) is
+/// off.
+macro_rules! linked {
+    ($cfg:meta, $passed:expr) => {{
+        #[cfg($cfg)]
+        let status = status($passed);
+        #[cfg(not($cfg))]
+        let status = ComponentStatus::Skipped;
+        status
+    }};
+}
+
+/// Make one cheap call into every linked FFmpeg component, returns an error
+/// naming the first component that fails.
+pub fn self_test() -> Result<SelfTestReport, ffi::FfmpegError> {
+    let report = SelfTestReport {
+        avutil: status(major_matches(
+            unsafe { ffi::avutil_version() },
+            ffi::LIBAVUTIL_VERSION_MAJOR,
+        )),
+        avcodec: linked!(
+            feature = "avcodec",
+            !unsafe { ffi::avcodec_find_decoder(ffi::AVCodecID_AV_CODEC_ID_H264) }.is_null()
+        ),
+        avformat: linked!(
+            feature = "avformat",
+            major_matches(
+                unsafe { ffi::avformat_version() },
+                ffi::LIBAVFORMAT_VERSION_MAJOR,
+            )
+        ),
+        avdevice: linked!(
+            ff_has_avdevice,
+            major_matches(
+                unsafe { ffi::avdevice_version() },
+                ffi::LIBAVDEVICE_VERSION_MAJOR,
+            )
+        ),
+        avfilter: linked!(
+            ff_has_avfilter,
+            !unsafe { ffi::avfilter_get_by_name(b"null\0".as_ptr() as _) }.is_null()
+        ),
+        swscale: linked!(
+            ff_has_swscale,
+            unsafe { ffi::sws_isSupportedInput(ffi::AVPixelFormat_AV_PIX_FMT_YUV420P) } > 0
+        ),
+        swresample: linked!(ff_has_swresample, {
+            let mut context = unsafe { ffi::swr_alloc() };
+            let passed = !context.is_null();
+            unsafe { ffi::swr_free(&mut context) };
+            passed
+        }),
+        postproc: linked!(
+            ff_has_postproc,
+            major_matches(
+                unsafe { ffi::postproc_version() },
+                ffi::LIBPOSTPROC_VERSION_MAJOR,
+            )
+        ),
+    };
+
+    match report
+        .components()
+        .iter()
+        .find(|(_, status)| *status == ComponentStatus::Failed)
+    {
+        Some((component, _)) => Err(ffi::FfmpegError::Component(component)),
+        None => Ok(report),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_self_test() {
+        let report = self_test().unwrap();
+        assert!(report.is_green());
+        assert_eq!(report.avutil, ComponentStatus::Passed);
+        // Only the components of disabled features or missing libs are skipped.
+        let skipped = report
+            .components()
+            .iter()
+            .filter(|(_, status)| *status == ComponentStatus::Skipped)
+            .count();
+        let disabled = [
+            cfg!(feature = "avcodec"),
+            cfg!(feature = "avformat"),
+            cfg!(ff_has_avdevice),
+            cfg!(ff_has_avfilter),
+            cfg!(ff_has_swscale),
+            cfg!(ff_has_swresample),
+            cfg!(ff_has_postproc),
+        ]
+        .iter()
+        .filter(|enabled| !**enabled)
+        .count();
+        assert_eq!(skipped, disabled);
+    }
+}